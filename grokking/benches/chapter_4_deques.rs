@@ -0,0 +1,158 @@
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+use grokking::chapter_4_bad_safe_deque::Deque as BadSafeDeque;
+use grokking::chapter_4_deque::Deque as RcDeque;
+use grokking::chapter_4_linked_list::LinkedList;
+use std::collections::VecDeque;
+use std::hint::black_box;
+
+const SIZE: u64 = 10_000;
+
+/// The operations every benchmarked deque has to offer.
+trait Deque {
+    fn new() -> Self;
+    fn push_front(&mut self, item: u64);
+    fn push_back(&mut self, item: u64);
+    fn pop_front(&mut self) -> Option<u64>;
+    fn pop_back(&mut self) -> Option<u64>;
+}
+
+macro_rules! impl_deque {
+    ($deque:ty) => {
+        impl Deque for $deque {
+            fn new() -> Self {
+                <$deque>::new()
+            }
+
+            fn push_front(&mut self, item: u64) {
+                <$deque>::push_front(self, item);
+            }
+
+            fn push_back(&mut self, item: u64) {
+                <$deque>::push_back(self, item);
+            }
+
+            fn pop_front(&mut self) -> Option<u64> {
+                <$deque>::pop_front(self)
+            }
+
+            fn pop_back(&mut self) -> Option<u64> {
+                <$deque>::pop_back(self)
+            }
+        }
+    };
+}
+
+impl_deque!(LinkedList<u64>);
+impl_deque!(BadSafeDeque<u64>);
+impl_deque!(VecDeque<u64>);
+
+/// The Rc deque names its ends first and last.
+impl Deque for RcDeque<u64> {
+    fn new() -> Self {
+        RcDeque::empty()
+    }
+
+    fn push_front(&mut self, item: u64) {
+        self.add_first(item);
+    }
+
+    fn push_back(&mut self, item: u64) {
+        self.add_last(item);
+    }
+
+    fn pop_front(&mut self) -> Option<u64> {
+        self.pop_first()
+    }
+
+    fn pop_back(&mut self) -> Option<u64> {
+        self.pop_last()
+    }
+}
+
+fn filled<D: Deque>() -> D {
+    let mut deque = D::new();
+    for item in 0..SIZE {
+        deque.push_back(item);
+    }
+    deque
+}
+
+fn bench_deque<D: Deque>(c: &mut Criterion, name: &str) {
+    c.bench_with_input(BenchmarkId::new("push back", name), &SIZE, |b, &size| {
+        b.iter(|| {
+            let mut deque = D::new();
+            for item in 0..size {
+                deque.push_back(black_box(item));
+            }
+            deque
+        })
+    });
+
+    c.bench_with_input(BenchmarkId::new("queue", name), &SIZE, |b, &size| {
+        b.iter(|| {
+            let mut deque = D::new();
+            let mut sum = 0;
+            for item in 0..size {
+                deque.push_back(black_box(item));
+                if item % 2 == 1 {
+                    sum += deque.pop_front().unwrap_or_default();
+                }
+            }
+            sum
+        })
+    });
+
+    c.bench_with_input(BenchmarkId::new("stack", name), &SIZE, |b, &size| {
+        b.iter(|| {
+            let mut deque = D::new();
+            for item in 0..size {
+                deque.push_front(black_box(item));
+            }
+            let mut sum = 0;
+            while let Some(item) = deque.pop_front() {
+                sum += item;
+            }
+            sum
+        })
+    });
+
+    c.bench_function(&format!("pop back/{name}"), |b| {
+        b.iter_batched(
+            filled::<D>,
+            |mut deque| {
+                while black_box(deque.pop_back()).is_some() {}
+                deque
+            },
+            criterion::BatchSize::LargeInput,
+        )
+    });
+}
+
+/// Only the deques that lend out their items take part, as the bad safe one cannot.
+fn bench_iteration(c: &mut Criterion) {
+    let list = filled::<LinkedList<u64>>();
+    c.bench_function("iterate/linked list", |b| {
+        b.iter(|| black_box(&list).iter().sum::<u64>())
+    });
+
+    let deque = filled::<RcDeque<u64>>();
+    c.bench_function("iterate/rc", |b| {
+        b.iter(|| black_box(&deque).iter().sum::<u64>())
+    });
+
+    let deque = filled::<VecDeque<u64>>();
+    c.bench_function("iterate/std", |b| {
+        b.iter(|| black_box(&deque).iter().sum::<u64>())
+    });
+}
+
+fn criterion_benchmark(c: &mut Criterion) {
+    bench_deque::<LinkedList<u64>>(c, "linked list");
+    bench_deque::<RcDeque<u64>>(c, "rc");
+    bench_deque::<BadSafeDeque<u64>>(c, "bad safe");
+    bench_deque::<VecDeque<u64>>(c, "std");
+    bench_iteration(c);
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);
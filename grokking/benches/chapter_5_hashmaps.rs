@@ -0,0 +1,94 @@
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+use grokking::chapter_5_hashmap::HashMap as ChainedHashMap;
+use grokking::chapter_5_robin_hood_hashmap::RobinHoodHashMap;
+use std::collections::HashMap as StdHashMap;
+use std::hint::black_box;
+
+const SIZE: u64 = 10_000;
+
+/// The operations every benchmarked map has to offer.
+trait Map: Default {
+    fn insert(&mut self, key: u64, value: u64);
+    fn get(&self, key: &u64) -> Option<&u64>;
+    fn remove(&mut self, key: &u64) -> Option<u64>;
+}
+
+macro_rules! impl_map {
+    ($map:ty) => {
+        impl Map for $map {
+            fn insert(&mut self, key: u64, value: u64) {
+                <$map>::insert(self, key, value);
+            }
+
+            fn get(&self, key: &u64) -> Option<&u64> {
+                <$map>::get(self, key)
+            }
+
+            fn remove(&mut self, key: &u64) -> Option<u64> {
+                <$map>::remove(self, key)
+            }
+        }
+    };
+}
+
+impl_map!(ChainedHashMap<u64, u64>);
+impl_map!(RobinHoodHashMap<u64, u64>);
+impl_map!(StdHashMap<u64, u64>);
+
+fn filled<M: Map>() -> M {
+    let mut map = M::default();
+    for key in 0..SIZE {
+        map.insert(key, key);
+    }
+    map
+}
+
+fn bench_map<M: Map>(c: &mut Criterion, name: &str) {
+    c.bench_with_input(BenchmarkId::new("insert", name), &SIZE, |b, &size| {
+        b.iter(|| {
+            let mut map = M::default();
+            for key in 0..size {
+                map.insert(black_box(key), key);
+            }
+            map
+        })
+    });
+
+    let map = filled::<M>();
+    c.bench_with_input(BenchmarkId::new("lookup hit", name), &SIZE, |b, &size| {
+        b.iter(|| {
+            (0..size)
+                .filter(|key| map.get(black_box(key)).is_some())
+                .count()
+        })
+    });
+    c.bench_with_input(BenchmarkId::new("lookup miss", name), &SIZE, |b, &size| {
+        b.iter(|| {
+            (size..2 * size)
+                .filter(|key| map.get(black_box(key)).is_some())
+                .count()
+        })
+    });
+
+    c.bench_with_input(BenchmarkId::new("remove", name), &SIZE, |b, &size| {
+        b.iter_batched(
+            filled::<M>,
+            |mut map| {
+                for key in 0..size {
+                    map.remove(black_box(&key));
+                }
+                map
+            },
+            criterion::BatchSize::LargeInput,
+        )
+    });
+}
+
+fn criterion_benchmark(c: &mut Criterion) {
+    bench_map::<ChainedHashMap<u64, u64>>(c, "chained");
+    bench_map::<RobinHoodHashMap<u64, u64>>(c, "robin hood");
+    bench_map::<StdHashMap<u64, u64>>(c, "std");
+}
+
+criterion_group!(benches, criterion_benchmark);
+criterion_main!(benches);
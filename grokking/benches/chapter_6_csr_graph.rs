@@ -0,0 +1,52 @@
+use criterion::{Criterion, criterion_group, criterion_main};
+use grokking::graph::{Graph, VertexId, shortest_path};
+use std::hint::black_box;
+
+const SIDE: usize = 200;
+
+/// A square grid where every vertex is attached to its right and lower neighbors,
+/// with weights that vary so Dijkstra has some reordering to do.
+fn grid() -> (Graph<usize>, VertexId, VertexId) {
+    let mut graph = Graph::undirected();
+    let ids = (0..SIDE * SIDE)
+        .map(|i| graph.add_vertex(i))
+        .collect::<Vec<_>>();
+    for x in 0..SIDE {
+        for y in 0..SIDE {
+            let id = ids[x * SIDE + y];
+            if x + 1 < SIDE {
+                graph.attach_weighted(
+                    &id,
+                    &ids[(x + 1) * SIDE + y],
+                    ((x * 7 + y) % 5 + 1) as isize,
+                );
+            }
+            if y + 1 < SIDE {
+                graph.attach_weighted(&id, &ids[x * SIDE + y + 1], ((x + y * 3) % 5 + 1) as isize);
+            }
+        }
+    }
+    (graph, ids[0], ids[SIDE * SIDE - 1])
+}
+
+fn bench_representations(c: &mut Criterion) {
+    let (graph, departure, destination) = grid();
+    let (frozen, _, _) = grid();
+    let frozen = frozen.freeze();
+
+    c.bench_function("graph/breath first", |b| {
+        b.iter(|| graph.breath_search_iterator(black_box(&departure)).count())
+    });
+    c.bench_function("csr/breath first", |b| {
+        b.iter(|| frozen.breath_search_iterator(black_box(&departure)).count())
+    });
+    c.bench_function("graph/dijkstra", |b| {
+        b.iter(|| shortest_path(&graph, black_box(departure), black_box(destination)))
+    });
+    c.bench_function("csr/dijkstra", |b| {
+        b.iter(|| shortest_path(&frozen, black_box(departure), black_box(destination)))
+    });
+}
+
+criterion_group!(benches, bench_representations);
+criterion_main!(benches);
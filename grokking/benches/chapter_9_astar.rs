@@ -0,0 +1,77 @@
+use criterion::{BenchmarkId, Criterion, criterion_group, criterion_main};
+use grokking::graph::{Distance, Graph, VertexId, astar};
+use std::hint::black_box;
+
+const SIDE: isize = 60;
+
+type Point = (isize, isize);
+
+type Heuristic = fn(&Point, &Point) -> Distance;
+
+fn manhattan(from: &Point, to: &Point) -> Distance {
+    (from.0 - to.0).abs() + (from.1 - to.1).abs()
+}
+
+/// A square grid with a wall down the middle that has a single gap at the bottom,
+/// so the cheapest route is not a straight line.
+fn walled_grid() -> (Graph<Point>, VertexId, VertexId) {
+    let mut graph = Graph::undirected();
+    let mut ids = Vec::new();
+    for x in 0..SIDE {
+        for y in 0..SIDE {
+            ids.push(graph.add_vertex((x, y)));
+        }
+    }
+    let id = |x: isize, y: isize| ids[(x * SIDE + y) as usize];
+    let wall = |x: isize, y: isize| x == SIDE / 2 && y < SIDE - 1;
+
+    for x in 0..SIDE {
+        for y in 0..SIDE {
+            if wall(x, y) {
+                continue;
+            }
+            if x + 1 < SIDE && !wall(x + 1, y) {
+                graph.attach(&id(x, y), &id(x + 1, y));
+            }
+            if y + 1 < SIDE && !wall(x, y + 1) {
+                graph.attach(&id(x, y), &id(x, y + 1));
+            }
+        }
+    }
+    (graph, id(0, 0), id(SIDE - 1, 0))
+}
+
+fn bench_searches(c: &mut Criterion) {
+    let (graph, departure, destination) = walled_grid();
+
+    let dijkstra = astar(&graph, departure, destination, |_, _| 0);
+    let manhattan_search = astar(&graph, departure, destination, manhattan);
+    assert_eq!(
+        dijkstra.path.map(|path| path.distance),
+        manhattan_search.path.map(|path| path.distance)
+    );
+
+    let searches: [(&str, Heuristic, usize); 2] = [
+        ("dijkstra", |_, _| 0, dijkstra.expanded_vertices),
+        ("manhattan", manhattan, manhattan_search.expanded_vertices),
+    ];
+    for (name, heuristic, expanded_vertices) in searches {
+        c.bench_with_input(
+            BenchmarkId::new(name, format!("{expanded_vertices} expanded")),
+            &heuristic,
+            |b, heuristic| {
+                b.iter(|| {
+                    astar(
+                        &graph,
+                        black_box(departure),
+                        black_box(destination),
+                        heuristic,
+                    )
+                })
+            },
+        );
+    }
+}
+
+criterion_group!(benches, bench_searches);
+criterion_main!(benches);
@@ -115,10 +115,6 @@ impl<T> Deque<T> {
             .as_ref()
             .map(|node| RefMut::map(node.borrow_mut(), |node| &mut node.elem))
     }
-
-    pub fn into_iter(self) -> IntoIter<T> {
-        IntoIter(self)
-    }
 }
 
 impl<T> Drop for Deque<T> {
@@ -127,6 +123,21 @@ impl<T> Drop for Deque<T> {
     }
 }
 
+impl<T> Default for Deque<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> IntoIterator for Deque<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter(self)
+    }
+}
+
 pub struct IntoIter<T>(Deque<T>);
 
 impl<T> Iterator for IntoIter<T> {
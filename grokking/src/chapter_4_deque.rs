@@ -1,4 +1,7 @@
-use std::cell::RefCell;
+use std::cell::{RefCell, RefMut};
+use std::marker::PhantomData;
+use std::mem;
+use std::ptr::NonNull;
 use std::rc::{Rc, Weak};
 
 struct Node<T> {
@@ -49,6 +52,7 @@ impl<T> Node<T> {
 pub struct Deque<T> {
     first: Option<NodeStrongRef<T>>,
     last: Option<NodeWeakRef<T>>,
+    len: usize,
 }
 
 impl<T> Drop for Deque<T> {
@@ -84,6 +88,7 @@ impl<T> Deque<T> {
         Deque {
             first: None,
             last: None,
+            len: 0,
         }
     }
 
@@ -97,7 +102,16 @@ impl<T> Deque<T> {
         Drain::new(self)
     }
 
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
     pub fn add_first(&mut self, new_first: T) {
+        self.len += 1;
         self.first = Some(match self.first.take() {
             Some(first) => Node::prepend(first, new_first),
             None => {
@@ -112,13 +126,19 @@ impl<T> Deque<T> {
         match self.first.take() {
             None => None,
             Some(node_strong_ref) => {
+                self.len -= 1;
                 self.first = node_strong_ref.borrow_mut().next.take();
+                match &self.first {
+                    Some(first) => first.borrow_mut().prev = None,
+                    None => self.last = None,
+                }
                 Some(Deque::extract_strong_ref_item(node_strong_ref))
             }
         }
     }
 
     pub fn add_last(&mut self, new_last: T) {
+        self.len += 1;
         self.last = Some(match self.last.take().and_then(|v| v.upgrade()) {
             Some(last) => Node::append(last, new_last),
             None => {
@@ -131,6 +151,7 @@ impl<T> Deque<T> {
 
     pub fn pop_last(&mut self) -> Option<T> {
         self.last.take().map(|node_weak_ref| {
+            self.len -= 1;
             let node_strong_ref = Weak::upgrade(&node_weak_ref).unwrap();
             let previous = node_strong_ref.borrow_mut().prev.take();
 
@@ -151,6 +172,141 @@ impl<T> Deque<T> {
         })
     }
 
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            front: self.first.as_ref().map(strong_ptr),
+            back: self.last.as_ref().map(weak_ptr),
+            remaining: self.len,
+            deque: PhantomData,
+        }
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+        IterMut {
+            front: self.first.as_ref().map(strong_ptr),
+            back: self.last.as_ref().map(weak_ptr),
+            remaining: self.len,
+            deque: PhantomData,
+        }
+    }
+
+    /// A cursor on the first item, or on the ghost position when the deque is empty.
+    pub fn cursor_front_mut(&mut self) -> CursorMut<'_, T> {
+        CursorMut {
+            current: self.first.clone(),
+            index: 0,
+            deque: self,
+        }
+    }
+
+    /// A cursor on the last item, or on the ghost position when the deque is empty.
+    pub fn cursor_back_mut(&mut self) -> CursorMut<'_, T> {
+        CursorMut {
+            current: self.last.as_ref().and_then(Weak::upgrade),
+            index: self.len.saturating_sub(1),
+            deque: self,
+        }
+    }
+
+    /// Moves the items from the given position on into a new deque. Walks from the
+    /// nearest end, so it takes `O(min(at, len - at))`.
+    ///
+    /// Panics when the position is past the end.
+    pub fn split_off(&mut self, at: usize) -> Deque<T> {
+        assert!(at <= self.len, "Cannot split off at {at}, past the end");
+        if at == 0 {
+            return mem::replace(self, Deque::empty());
+        }
+        let Some(new_first) = self.node_at(at) else {
+            return Deque::empty();
+        };
+
+        let new_last = new_first.borrow_mut().prev.take();
+        if let Some(new_last) = new_last.as_ref().and_then(Weak::upgrade) {
+            new_last.borrow_mut().next = None;
+        }
+        let split = Deque {
+            first: Some(new_first),
+            last: mem::replace(&mut self.last, new_last),
+            len: self.len - at,
+        };
+        self.len = at;
+        split
+    }
+
+    /// Moves every item of the other deque to the end of this one in `O(1)`.
+    pub fn append(&mut self, other: &mut Deque<T>) {
+        let Some(other_first) = other.first.take() else {
+            return;
+        };
+        match self.last.as_ref().and_then(Weak::upgrade) {
+            Some(last) => {
+                other_first.borrow_mut().prev = Some(Rc::downgrade(&last));
+                last.borrow_mut().next = Some(other_first);
+            }
+            None => self.first = Some(other_first),
+        }
+        self.last = other.last.take();
+        self.len += mem::take(&mut other.len);
+    }
+
+    fn node_at(&self, index: usize) -> Option<NodeStrongRef<T>> {
+        if index >= self.len {
+            return None;
+        }
+        if index < self.len / 2 {
+            let mut node = self.first.clone()?;
+            for _ in 0..index {
+                let next = node.borrow().next.clone()?;
+                node = next;
+            }
+            Some(node)
+        } else {
+            let mut node = self.last.as_ref()?.upgrade()?;
+            for _ in index..self.len - 1 {
+                let prev = node.borrow().prev.as_ref()?.upgrade()?;
+                node = prev;
+            }
+            Some(node)
+        }
+    }
+
+    /// Puts the item right after the node, which must be in this deque.
+    fn insert_after(&mut self, node: &NodeStrongRef<T>, item: T) -> NodeStrongRef<T> {
+        let new_node = Node::new(item);
+        let next = node.borrow_mut().next.take();
+        match &next {
+            Some(next) => next.borrow_mut().prev = Some(Rc::downgrade(&new_node)),
+            None => self.last = Some(Rc::downgrade(&new_node)),
+        }
+        {
+            let mut new_node_mut = new_node.borrow_mut();
+            new_node_mut.prev = Some(Rc::downgrade(node));
+            new_node_mut.next = next;
+        }
+        node.borrow_mut().next = Some(Rc::clone(&new_node));
+        self.len += 1;
+        new_node
+    }
+
+    /// Takes the node out of this deque, returning the node that followed it.
+    fn unlink(&mut self, node: &NodeStrongRef<T>) -> Option<NodeStrongRef<T>> {
+        let mut node_mut = node.borrow_mut();
+        let prev = node_mut.prev.take().and_then(|prev| prev.upgrade());
+        let next = node_mut.next.take();
+
+        match &next {
+            Some(next) => next.borrow_mut().prev = prev.as_ref().map(Rc::downgrade),
+            None => self.last = prev.as_ref().map(Rc::downgrade),
+        }
+        match prev {
+            Some(prev) => prev.borrow_mut().next = next.clone(),
+            None => self.first = next.clone(),
+        }
+        self.len -= 1;
+        next
+    }
+
     fn extract_strong_ref_item(strong_ref: NodeStrongRef<T>) -> T {
         Rc::into_inner(strong_ref)
             .expect("Tried to extract value from Rc with multiple strong pointers")
@@ -159,6 +315,220 @@ impl<T> Deque<T> {
     }
 }
 
+fn strong_ptr<T>(node: &NodeStrongRef<T>) -> NonNull<RefCell<Node<T>>> {
+    NonNull::from(&**node)
+}
+
+fn weak_ptr<T>(node: &NodeWeakRef<T>) -> NonNull<RefCell<Node<T>>> {
+    NonNull::new(node.as_ptr().cast_mut()).expect("Weak pointers to nodes are never dangling")
+}
+
+/// Borrows the items without going through the `RefCell`s, which is sound as long as
+/// the deque is borrowed: only `&mut Deque` methods ever borrow a node mutably.
+pub struct Iter<'a, T> {
+    front: Option<NonNull<RefCell<Node<T>>>>,
+    back: Option<NonNull<RefCell<Node<T>>>>,
+    /// How many items are left between both ends, so that they stop when they meet.
+    remaining: usize,
+    deque: PhantomData<&'a Deque<T>>,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        // SAFETY: the node is kept alive by the deque, which is borrowed for 'a, and no
+        // one can borrow it mutably meanwhile.
+        let node = unsafe { self.front?.as_ref().try_borrow_unguarded() }.ok()?;
+        self.front = node.next.as_ref().map(strong_ptr);
+        self.remaining -= 1;
+        Some(&node.item)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<T> DoubleEndedIterator for Iter<'_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        // SAFETY: as in `next`.
+        let node = unsafe { self.back?.as_ref().try_borrow_unguarded() }.ok()?;
+        self.back = node.prev.as_ref().map(weak_ptr);
+        self.remaining -= 1;
+        Some(&node.item)
+    }
+}
+
+impl<T> ExactSizeIterator for Iter<'_, T> {}
+
+/// Like [`Iter`], handing out each item once, while the deque is borrowed mutably.
+pub struct IterMut<'a, T> {
+    front: Option<NonNull<RefCell<Node<T>>>>,
+    back: Option<NonNull<RefCell<Node<T>>>>,
+    remaining: usize,
+    deque: PhantomData<&'a mut Deque<T>>,
+}
+
+impl<'a, T> Iterator for IterMut<'a, T> {
+    type Item = &'a mut T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        // SAFETY: the deque is borrowed mutably for 'a and each item is handed out only
+        // once, so the reference is unique. Reading `next` does not touch the item.
+        let node = unsafe { self.front?.as_ref() }.as_ptr();
+        unsafe {
+            self.front = (*node).next.as_ref().map(strong_ptr);
+            self.remaining -= 1;
+            Some(&mut (*node).item)
+        }
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<T> DoubleEndedIterator for IterMut<'_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        // SAFETY: as in `next`.
+        let node = unsafe { self.back?.as_ref() }.as_ptr();
+        unsafe {
+            self.back = (*node).prev.as_ref().map(weak_ptr);
+            self.remaining -= 1;
+            Some(&mut (*node).item)
+        }
+    }
+}
+
+impl<T> ExactSizeIterator for IterMut<'_, T> {}
+
+impl<'a, T> IntoIterator for &'a Deque<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a mut Deque<T> {
+    type Item = &'a mut T;
+    type IntoIter = IterMut<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+/// A position in the deque that can move both ways and change the deque around it.
+///
+/// Besides the items, the cursor can stand on a ghost position between the last and
+/// the first item, which is where moving past either end takes it.
+pub struct CursorMut<'a, T> {
+    deque: &'a mut Deque<T>,
+    /// The node the cursor is on, `None` on the ghost position.
+    current: Option<NodeStrongRef<T>>,
+    /// The position of the current node, or the length of the deque on the ghost.
+    index: usize,
+}
+
+impl<T> CursorMut<'_, T> {
+    /// The position of the current item, `None` on the ghost position.
+    pub fn index(&self) -> Option<usize> {
+        self.current.as_ref().map(|_| self.index)
+    }
+
+    pub fn current(&mut self) -> Option<RefMut<'_, T>> {
+        self.current
+            .as_ref()
+            .map(|node| RefMut::map(node.borrow_mut(), |node| &mut node.item))
+    }
+
+    pub fn move_next(&mut self) {
+        self.current = match self.current.take() {
+            Some(node) => {
+                self.index += 1;
+                node.borrow().next.clone()
+            }
+            None => {
+                self.index = 0;
+                self.deque.first.clone()
+            }
+        };
+        if self.current.is_none() {
+            self.index = self.deque.len;
+        }
+    }
+
+    pub fn move_prev(&mut self) {
+        self.current = match self.current.take() {
+            Some(node) => {
+                self.index = self.index.wrapping_sub(1);
+                node.borrow().prev.as_ref().and_then(Weak::upgrade)
+            }
+            None => {
+                self.index = self.deque.len.wrapping_sub(1);
+                self.deque.last.as_ref().and_then(Weak::upgrade)
+            }
+        };
+        if self.current.is_none() {
+            self.index = self.deque.len;
+        }
+    }
+
+    /// Inserts the item before the current one, or at the end on the ghost position.
+    pub fn insert_before(&mut self, item: T) {
+        let prev = match &self.current {
+            Some(node) => node.borrow().prev.as_ref().and_then(Weak::upgrade),
+            None => self.deque.last.as_ref().and_then(Weak::upgrade),
+        };
+        match prev {
+            Some(prev) => {
+                self.deque.insert_after(&prev, item);
+            }
+            None => self.deque.add_first(item),
+        }
+        self.index += 1;
+    }
+
+    /// Inserts the item after the current one, or at the start on the ghost position.
+    pub fn insert_after(&mut self, item: T) {
+        match &self.current {
+            Some(node) => {
+                let node = Rc::clone(node);
+                self.deque.insert_after(&node, item);
+            }
+            None => {
+                self.deque.add_first(item);
+                self.index = self.deque.len;
+            }
+        }
+    }
+
+    /// Takes the current item out, moving the cursor to the next one.
+    pub fn remove_current(&mut self) -> Option<T> {
+        let node = self.current.take()?;
+        self.current = self.deque.unlink(&node);
+        if self.current.is_none() {
+            self.index = self.deque.len;
+        }
+        Some(Deque::extract_strong_ref_item(node))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use crate::chapter_4_deque::Deque;
@@ -238,4 +608,122 @@ mod tests {
 
         assert!(iter.next().is_none());
     }
+
+    fn deque_of(items: impl IntoIterator<Item = i32>) -> Deque<i32> {
+        let mut deque = Deque::empty();
+        for item in items {
+            deque.add_last(item);
+        }
+        deque
+    }
+
+    fn items(deque: &Deque<i32>) -> Vec<i32> {
+        deque.iter().copied().collect()
+    }
+
+    #[test]
+    fn should_iter_from_both_ends() {
+        let mut deque = deque_of(1..=5);
+        deque.add_first(0);
+        deque.pop_last();
+
+        let mut iter = deque.iter();
+        assert_eq!(iter.len(), 5);
+        assert_eq!(iter.next(), Some(&0));
+        assert_eq!(iter.next_back(), Some(&4));
+        assert_eq!(iter.collect::<Vec<_>>(), vec![&1, &2, &3]);
+        assert_eq!(deque.iter().rev().count(), deque.len());
+
+        for item in deque.iter_mut().rev().take(2) {
+            *item *= 10;
+        }
+        for item in &mut deque {
+            *item += 1;
+        }
+        assert_eq!(items(&deque), vec![1, 2, 3, 31, 41]);
+    }
+
+    #[test]
+    fn should_edit_through_cursor() {
+        let mut deque = deque_of(1..=4);
+
+        let mut cursor = deque.cursor_front_mut();
+        cursor.move_next();
+        assert_eq!(cursor.index(), Some(1));
+        assert_eq!(cursor.remove_current(), Some(2));
+        assert_eq!(cursor.current().as_deref(), Some(&3));
+
+        cursor.insert_before(20);
+        cursor.insert_after(30);
+        assert_eq!(cursor.index(), Some(2));
+        *cursor.current().unwrap() = 0;
+
+        cursor.move_prev();
+        cursor.move_prev();
+        cursor.move_prev();
+        assert_eq!(cursor.index(), None);
+        cursor.insert_after(-1);
+        cursor.insert_before(99);
+        cursor.move_prev();
+        assert_eq!(cursor.remove_current(), Some(99));
+        assert_eq!(cursor.index(), None);
+
+        assert_eq!(items(&deque), vec![-1, 1, 20, 0, 30, 4]);
+        assert_eq!(deque.len(), 6);
+        assert_eq!(
+            deque.iter().rev().copied().collect::<Vec<_>>(),
+            vec![4, 30, 0, 20, 1, -1]
+        );
+        assert_eq!(deque.pop_last(), Some(4));
+        assert_eq!(deque.pop_first(), Some(-1));
+    }
+
+    #[test]
+    fn should_empty_through_cursor() {
+        let mut deque = deque_of(1..=2);
+
+        let mut cursor = deque.cursor_back_mut();
+        assert_eq!(cursor.remove_current(), Some(2));
+        cursor.move_prev();
+        assert_eq!(cursor.remove_current(), Some(1));
+        assert_eq!(cursor.remove_current(), None);
+
+        assert!(deque.is_empty());
+        assert_eq!(deque.iter().next_back(), None);
+        deque.add_last(3);
+        assert_eq!(items(&deque), vec![3]);
+    }
+
+    #[test]
+    fn should_split_off_and_append() {
+        let mut deque = deque_of(0..10);
+
+        let mut back = deque.split_off(7);
+        let mut middle = deque.split_off(3);
+        assert_eq!(items(&deque), vec![0, 1, 2]);
+        assert_eq!(items(&middle), vec![3, 4, 5, 6]);
+        assert_eq!(
+            back.iter().rev().copied().collect::<Vec<_>>(),
+            vec![9, 8, 7]
+        );
+        assert_eq!(deque.split_off(3).len(), 0);
+
+        middle.append(&mut back);
+        deque.append(&mut middle);
+        assert!(middle.is_empty() && back.is_empty());
+        assert_eq!(items(&deque), (0..10).collect::<Vec<_>>());
+        assert_eq!(deque.iter().rev().count(), 10);
+
+        let mut all = deque.split_off(0);
+        assert!(deque.is_empty());
+        deque.append(&mut all);
+        assert_eq!(deque.pop_last(), Some(9));
+        assert_eq!(deque.len(), 9);
+    }
+
+    #[test]
+    #[should_panic(expected = "past the end")]
+    fn should_refuse_to_split_off_past_the_end() {
+        deque_of(0..2).split_off(3);
+    }
 }
@@ -0,0 +1,487 @@
+use std::fmt;
+use std::marker::PhantomData;
+use std::ptr::NonNull;
+
+/// A doubly linked list over raw pointers, so that it hands out plain references and
+/// pays no reference counts or borrow flags, unlike the `Rc<RefCell<_>>` deques.
+///
+/// Every node is a `Box` the list owns and leaks into a pointer, and turns back into a
+/// `Box` when the node leaves the list.
+pub struct LinkedList<T> {
+    front: Link<T>,
+    back: Link<T>,
+    len: usize,
+    /// Tells the compiler the list owns boxed nodes, which makes it covariant in `T`
+    /// and lets the drop checker know that dropping the list drops items.
+    nodes: PhantomData<Box<Node<T>>>,
+}
+
+type Link<T> = Option<NonNull<Node<T>>>;
+
+struct Node<T> {
+    elem: T,
+    prev: Link<T>,
+    next: Link<T>,
+}
+
+// SAFETY: the list owns its items just like a `Vec` would, and the pointers never leave
+// it, so it can cross threads whenever its items can.
+unsafe impl<T: Send> Send for LinkedList<T> {}
+unsafe impl<T: Sync> Sync for LinkedList<T> {}
+
+impl<T> LinkedList<T> {
+    pub fn new() -> Self {
+        LinkedList {
+            front: None,
+            back: None,
+            len: 0,
+            nodes: PhantomData,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn push_front(&mut self, elem: T) {
+        let node = NonNull::from(Box::leak(Box::new(Node {
+            elem,
+            prev: None,
+            next: self.front,
+        })));
+        match self.front {
+            // SAFETY: every link points to a live node of this list.
+            Some(old_front) => unsafe { (*old_front.as_ptr()).prev = Some(node) },
+            None => self.back = Some(node),
+        }
+        self.front = Some(node);
+        self.len += 1;
+    }
+
+    pub fn push_back(&mut self, elem: T) {
+        let node = NonNull::from(Box::leak(Box::new(Node {
+            elem,
+            prev: self.back,
+            next: None,
+        })));
+        match self.back {
+            // SAFETY: every link points to a live node of this list.
+            Some(old_back) => unsafe { (*old_back.as_ptr()).next = Some(node) },
+            None => self.front = Some(node),
+        }
+        self.back = Some(node);
+        self.len += 1;
+    }
+
+    pub fn pop_front(&mut self) -> Option<T> {
+        self.front.map(|node| {
+            // SAFETY: the node came from `Box::leak` and is unlinked right away, so
+            // nothing points to it once it is boxed again.
+            let node = unsafe { Box::from_raw(node.as_ptr()) };
+            self.front = node.next;
+            match self.front {
+                Some(new_front) => unsafe { (*new_front.as_ptr()).prev = None },
+                None => self.back = None,
+            }
+            self.len -= 1;
+            node.elem
+        })
+    }
+
+    pub fn pop_back(&mut self) -> Option<T> {
+        self.back.map(|node| {
+            // SAFETY: as in `pop_front`.
+            let node = unsafe { Box::from_raw(node.as_ptr()) };
+            self.back = node.prev;
+            match self.back {
+                Some(new_back) => unsafe { (*new_back.as_ptr()).next = None },
+                None => self.front = None,
+            }
+            self.len -= 1;
+            node.elem
+        })
+    }
+
+    pub fn peek_front(&self) -> Option<&T> {
+        // SAFETY: the node lives as long as the list, which is borrowed.
+        self.front.map(|node| unsafe { &(*node.as_ptr()).elem })
+    }
+
+    pub fn peek_back(&self) -> Option<&T> {
+        // SAFETY: as in `peek_front`.
+        self.back.map(|node| unsafe { &(*node.as_ptr()).elem })
+    }
+
+    pub fn peek_front_mut(&mut self) -> Option<&mut T> {
+        // SAFETY: the list is borrowed mutably, so the reference is unique.
+        self.front.map(|node| unsafe { &mut (*node.as_ptr()).elem })
+    }
+
+    pub fn peek_back_mut(&mut self) -> Option<&mut T> {
+        // SAFETY: as in `peek_front_mut`.
+        self.back.map(|node| unsafe { &mut (*node.as_ptr()).elem })
+    }
+
+    pub fn clear(&mut self) {
+        while self.pop_front().is_some() {}
+    }
+
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter {
+            front: self.front,
+            back: self.back,
+            remaining: self.len,
+            list: PhantomData,
+        }
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+        IterMut {
+            front: self.front,
+            back: self.back,
+            remaining: self.len,
+            list: PhantomData,
+        }
+    }
+}
+
+impl<T> Drop for LinkedList<T> {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+impl<T> Default for LinkedList<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Clone> Clone for LinkedList<T> {
+    fn clone(&self) -> Self {
+        self.iter().cloned().collect()
+    }
+}
+
+impl<T> Extend<T> for LinkedList<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for elem in iter {
+            self.push_back(elem);
+        }
+    }
+}
+
+impl<T> FromIterator<T> for LinkedList<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut list = LinkedList::new();
+        list.extend(iter);
+        list
+    }
+}
+
+impl<T: PartialEq> PartialEq for LinkedList<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len == other.len && self.iter().eq(other.iter())
+    }
+}
+
+impl<T: Eq> Eq for LinkedList<T> {}
+
+impl<T: fmt::Debug> fmt::Debug for LinkedList<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+pub struct Iter<'a, T> {
+    front: Link<T>,
+    back: Link<T>,
+    /// How many items are left between both ends, so that they stop when they meet.
+    remaining: usize,
+    list: PhantomData<&'a T>,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.front.map(|node| {
+            // SAFETY: the list is borrowed for 'a, so its nodes stay alive and unchanged.
+            let node = unsafe { &*node.as_ptr() };
+            self.remaining -= 1;
+            self.front = node.next;
+            &node.elem
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<T> DoubleEndedIterator for Iter<'_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.back.map(|node| {
+            // SAFETY: as in `next`.
+            let node = unsafe { &*node.as_ptr() };
+            self.remaining -= 1;
+            self.back = node.prev;
+            &node.elem
+        })
+    }
+}
+
+impl<T> ExactSizeIterator for Iter<'_, T> {}
+
+// SAFETY: the iterator only hands out shared references to items, like a `&LinkedList`.
+unsafe impl<T: Sync> Send for Iter<'_, T> {}
+unsafe impl<T: Sync> Sync for Iter<'_, T> {}
+
+pub struct IterMut<'a, T> {
+    front: Link<T>,
+    back: Link<T>,
+    remaining: usize,
+    list: PhantomData<&'a mut T>,
+}
+
+impl<'a, T> Iterator for IterMut<'a, T> {
+    type Item = &'a mut T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.front.map(|node| {
+            // SAFETY: the list is borrowed mutably for 'a and both ends stop where they
+            // meet, so every item is handed out once.
+            let node = unsafe { &mut *node.as_ptr() };
+            self.remaining -= 1;
+            self.front = node.next;
+            &mut node.elem
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<T> DoubleEndedIterator for IterMut<'_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.back.map(|node| {
+            // SAFETY: as in `next`.
+            let node = unsafe { &mut *node.as_ptr() };
+            self.remaining -= 1;
+            self.back = node.prev;
+            &mut node.elem
+        })
+    }
+}
+
+impl<T> ExactSizeIterator for IterMut<'_, T> {}
+
+// SAFETY: the iterator hands out unique references to items, like a `&mut LinkedList`,
+// and sharing it only shares access to where it stands.
+unsafe impl<T: Send> Send for IterMut<'_, T> {}
+unsafe impl<T: Sync> Sync for IterMut<'_, T> {}
+
+pub struct IntoIter<T>(LinkedList<T>);
+
+impl<T> Iterator for IntoIter<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.pop_front()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.0.len, Some(self.0.len))
+    }
+}
+
+impl<T> DoubleEndedIterator for IntoIter<T> {
+    fn next_back(&mut self) -> Option<T> {
+        self.0.pop_back()
+    }
+}
+
+impl<T> ExactSizeIterator for IntoIter<T> {}
+
+impl<T> IntoIterator for LinkedList<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter(self)
+    }
+}
+
+impl<'a, T> IntoIterator for &'a LinkedList<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a mut LinkedList<T> {
+    type Item = &'a mut T;
+    type IntoIter = IterMut<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+/// Runs cleanly under Miri with `cargo +nightly miri test chapter_4_linked_list`, which
+/// checks the pointer juggling above for undefined behavior and leaks.
+#[cfg(test)]
+mod tests {
+    use super::LinkedList;
+    use std::rc::Rc;
+
+    #[test]
+    fn should_push_and_pop_at_both_ends() {
+        let mut list = LinkedList::new();
+        assert_eq!(list.pop_front(), None);
+        assert_eq!(list.pop_back(), None);
+
+        list.push_front(2);
+        list.push_front(1);
+        list.push_back(3);
+        assert_eq!(list.len(), 3);
+        assert_eq!((list.peek_front(), list.peek_back()), (Some(&1), Some(&3)));
+
+        assert_eq!(list.pop_back(), Some(3));
+        assert_eq!(list.pop_front(), Some(1));
+        assert_eq!(list.pop_back(), Some(2));
+        assert!(list.is_empty());
+        assert_eq!(list.peek_front(), None);
+
+        list.push_back(4);
+        assert_eq!(list.pop_front(), Some(4));
+        assert_eq!(list.pop_back(), None);
+    }
+
+    #[test]
+    fn should_change_items_through_peeks() {
+        let mut list = (1..=3).collect::<LinkedList<_>>();
+
+        *list.peek_front_mut().unwrap() *= 10;
+        if let Some(back) = list.peek_back_mut() {
+            *back += 1;
+        }
+
+        assert_eq!(list.iter().copied().collect::<Vec<_>>(), vec![10, 2, 4]);
+    }
+
+    #[test]
+    fn should_iter_from_both_ends() {
+        let mut list = (1..=5).collect::<LinkedList<_>>();
+
+        let mut iter = list.iter();
+        assert_eq!(iter.len(), 5);
+        assert_eq!(iter.next(), Some(&1));
+        assert_eq!(iter.next_back(), Some(&5));
+        assert_eq!(iter.next(), Some(&2));
+        assert_eq!(iter.collect::<Vec<_>>(), vec![&3, &4]);
+
+        let mut iter = list.iter_mut();
+        let (first, last) = (iter.next().unwrap(), iter.next_back().unwrap());
+        *first = 0;
+        *last = 0;
+        for elem in iter.rev() {
+            *elem *= -1;
+        }
+        assert_eq!(
+            list.iter().copied().collect::<Vec<_>>(),
+            vec![0, -2, -3, -4, 0]
+        );
+
+        let mut into_iter = list.into_iter();
+        assert_eq!(into_iter.next_back(), Some(0));
+        assert_eq!(into_iter.next(), Some(0));
+        assert_eq!(into_iter.collect::<Vec<_>>(), vec![-2, -3, -4]);
+    }
+
+    #[test]
+    fn should_drop_every_item() {
+        let counted = Rc::new(());
+        let mut list = (0..10)
+            .map(|_| Rc::clone(&counted))
+            .collect::<LinkedList<_>>();
+        list.pop_back();
+        let mut into_iter = list.clone().into_iter();
+        into_iter.next();
+
+        assert_eq!(Rc::strong_count(&counted), 1 + 9 + 8);
+        drop(into_iter);
+        drop(list);
+        assert_eq!(Rc::strong_count(&counted), 1);
+    }
+
+    #[test]
+    fn should_compare_and_format() {
+        let list = (1..=3).collect::<LinkedList<_>>();
+
+        assert_eq!(list, list.clone());
+        assert_ne!(list, (1..=2).collect());
+        assert_eq!(format!("{list:?}"), "[1, 2, 3]");
+    }
+
+    #[test]
+    fn should_be_covariant() {
+        fn shorten<'a>(list: LinkedList<&'static str>) -> LinkedList<&'a str> {
+            list
+        }
+        fn shorten_iter<'i, 'a>(iter: super::Iter<'i, &'static str>) -> super::Iter<'i, &'a str> {
+            iter
+        }
+
+        let list = LinkedList::from_iter(["a", "b"]);
+        assert_eq!(shorten_iter(list.iter()).count(), 2);
+
+        let local = String::from("c");
+        let mut list = shorten(list);
+        list.push_back(&local);
+        assert_eq!(list.len(), 3);
+    }
+
+    #[test]
+    fn should_send_between_threads() {
+        let list = (0..100).collect::<LinkedList<_>>();
+
+        let sum = std::thread::spawn(move || list.iter().sum::<i32>())
+            .join()
+            .unwrap();
+
+        assert_eq!(sum, 4950);
+
+        let mut list = (0..100).collect::<LinkedList<_>>();
+        std::thread::scope(|scope| {
+            let mut iter = list.iter();
+            let front = iter.by_ref().take(10).sum::<i32>();
+            let back = scope.spawn(move || iter.sum::<i32>()).join().unwrap();
+            assert_eq!((front, back), (45, 4905));
+        });
+        std::thread::scope(|scope| {
+            let iter = list.iter_mut();
+            scope.spawn(move || iter.for_each(|elem| *elem *= 2));
+        });
+        assert_eq!(list.iter().sum::<i32>(), 9900);
+    }
+}
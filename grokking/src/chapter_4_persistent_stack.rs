@@ -1,33 +1,102 @@
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::ops::Deref;
 use std::rc::Rc;
+use std::sync::Arc;
 
-pub struct PersistentStack<T> {
-    head: Link<T>,
+/// The reference counted pointer the nodes of a persistent structure are held in.
+/// Implemented by marker types, which is why it asks for `'static`.
+pub trait PointerKind: 'static {
+    type Pointer<U>: Deref<Target = U> + Clone;
+
+    fn new<U>(value: U) -> Self::Pointer<U>;
+
+    /// The value, when no other pointer holds it.
+    fn try_unwrap<U>(pointer: Self::Pointer<U>) -> Result<U, Self::Pointer<U>>;
+
+    fn ptr_eq<U>(this: &Self::Pointer<U>, other: &Self::Pointer<U>) -> bool;
+}
+
+/// Holds nodes in `Rc`, which is cheaper but keeps them on one thread.
+pub struct RcPointer;
+
+/// Holds nodes in `Arc`, so that versions can be shared between threads.
+pub struct ArcPointer;
+
+impl PointerKind for RcPointer {
+    type Pointer<U> = Rc<U>;
+
+    fn new<U>(value: U) -> Rc<U> {
+        Rc::new(value)
+    }
+
+    fn try_unwrap<U>(pointer: Rc<U>) -> Result<U, Rc<U>> {
+        Rc::try_unwrap(pointer)
+    }
+
+    fn ptr_eq<U>(this: &Rc<U>, other: &Rc<U>) -> bool {
+        Rc::ptr_eq(this, other)
+    }
+}
+
+impl PointerKind for ArcPointer {
+    type Pointer<U> = Arc<U>;
+
+    fn new<U>(value: U) -> Arc<U> {
+        Arc::new(value)
+    }
+
+    fn try_unwrap<U>(pointer: Arc<U>) -> Result<U, Arc<U>> {
+        Arc::try_unwrap(pointer)
+    }
+
+    fn ptr_eq<U>(this: &Arc<U>, other: &Arc<U>) -> bool {
+        Arc::ptr_eq(this, other)
+    }
+}
+
+/// A stack that is never changed in place: every version shares its tail with the
+/// versions it was built from.
+pub struct PersistentStack<T, P: PointerKind = RcPointer> {
+    head: Link<T, P>,
+    len: usize,
 }
 
-type Link<T> = Option<Rc<Node<T>>>;
+/// A persistent stack that is `Send` and `Sync` when its items are.
+pub type ArcPersistentStack<T> = PersistentStack<T, ArcPointer>;
+
+type Link<T, P> = Option<<P as PointerKind>::Pointer<Node<T, P>>>;
 
-struct Node<T> {
+struct Node<T, P: PointerKind> {
     elem: T,
-    next: Link<T>,
+    next: Link<T, P>,
 }
 
 impl<T> PersistentStack<T> {
     pub fn new() -> Self {
-        PersistentStack { head: None }
+        PersistentStack::empty()
+    }
+}
+
+impl<T, P: PointerKind> PersistentStack<T, P> {
+    pub fn empty() -> Self {
+        PersistentStack { head: None, len: 0 }
     }
 
-    pub fn prepend(&self, elem: T) -> PersistentStack<T> {
+    pub fn prepend(&self, elem: T) -> PersistentStack<T, P> {
         PersistentStack {
-            head: Some(Rc::new(Node {
+            head: Some(P::new(Node {
                 elem,
                 next: self.head.clone(),
             })),
+            len: self.len + 1,
         }
     }
 
-    pub fn tail(&self) -> PersistentStack<T> {
+    pub fn tail(&self) -> PersistentStack<T, P> {
         PersistentStack {
             head: self.head.as_ref().and_then(|node| node.next.clone()),
+            len: self.len.saturating_sub(1),
         }
     }
 
@@ -35,18 +104,95 @@ impl<T> PersistentStack<T> {
         self.head.as_ref().map(|node| &node.elem)
     }
 
-    pub fn iter(&self) -> Iter<'_, T> {
+    /// Takes `O(1)`, as every version keeps its length.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.head.is_none()
+    }
+
+    /// A new stack with the items in the opposite order. Nothing can be shared, so the
+    /// items are cloned.
+    pub fn reverse(&self) -> PersistentStack<T, P>
+    where
+        T: Clone,
+    {
+        self.iter()
+            .fold(PersistentStack::empty(), |reversed, elem| {
+                reversed.prepend(elem.clone())
+            })
+    }
+
+    pub fn iter(&self) -> Iter<'_, T, P> {
         Iter {
             next: self.head.as_deref(),
+            remaining: self.len,
+        }
+    }
+}
+
+impl<T, P: PointerKind> Default for PersistentStack<T, P> {
+    fn default() -> Self {
+        Self::empty()
+    }
+}
+
+impl<T, P: PointerKind> Clone for PersistentStack<T, P> {
+    fn clone(&self) -> Self {
+        PersistentStack {
+            head: self.head.clone(),
+            len: self.len,
         }
     }
 }
 
-impl<T> Drop for PersistentStack<T> {
+/// Builds a stack whose head is the first item, so that it iterates in the same order
+/// the items came in.
+impl<T, P: PointerKind> FromIterator<T> for PersistentStack<T, P> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let items = iter.into_iter().collect::<Vec<_>>();
+        items
+            .into_iter()
+            .rev()
+            .fold(PersistentStack::empty(), |stack, elem| stack.prepend(elem))
+    }
+}
+
+impl<T: PartialEq, P: PointerKind> PartialEq for PersistentStack<T, P> {
+    fn eq(&self, other: &Self) -> bool {
+        let shared = match (&self.head, &other.head) {
+            (Some(head), Some(other_head)) => P::ptr_eq(head, other_head),
+            (None, None) => true,
+            _ => false,
+        };
+        self.len == other.len && (shared || self.iter().eq(other.iter()))
+    }
+}
+
+impl<T: Eq, P: PointerKind> Eq for PersistentStack<T, P> {}
+
+impl<T: Hash, P: PointerKind> Hash for PersistentStack<T, P> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.len.hash(state);
+        for elem in self.iter() {
+            elem.hash(state);
+        }
+    }
+}
+
+impl<T: fmt::Debug, P: PointerKind> fmt::Debug for PersistentStack<T, P> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T, P: PointerKind> Drop for PersistentStack<T, P> {
     fn drop(&mut self) {
         let mut head = self.head.take();
         while let Some(node) = head {
-            if let Ok(mut node) = Rc::try_unwrap(node) {
+            if let Ok(mut node) = P::try_unwrap(node) {
                 head = node.next.take();
             } else {
                 break;
@@ -55,24 +201,137 @@ impl<T> Drop for PersistentStack<T> {
     }
 }
 
-pub struct Iter<'a, T> {
-    next: Option<&'a Node<T>>,
+impl<'a, T, P: PointerKind> IntoIterator for &'a PersistentStack<T, P> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T, P>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+pub struct Iter<'a, T, P: PointerKind> {
+    next: Option<&'a Node<T, P>>,
+    remaining: usize,
 }
 
-impl<'a, T> Iterator for Iter<'a, T> {
+impl<'a, T, P: PointerKind> Iterator for Iter<'a, T, P> {
     type Item = &'a T;
 
     fn next(&mut self) -> Option<Self::Item> {
         self.next.map(|node| {
             self.next = node.next.as_deref();
+            self.remaining -= 1;
             &node.elem
         })
     }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<T, P: PointerKind> ExactSizeIterator for Iter<'_, T, P> {}
+
+/// A persistent first in, first out queue made of two persistent stacks: items are
+/// taken from the front one and added to the back one. Whenever the back grows longer
+/// than the front, the banker's queue moves it over, reversed, to the end of the front.
+///
+/// Each item is moved once, so a run of operations where every version is used once
+/// takes amortized `O(1)` per operation. Going on from an old version again and again
+/// can repeat the same move, as the moves are not lazy.
+pub struct PersistentQueue<T, P: PointerKind = RcPointer> {
+    front: PersistentStack<T, P>,
+    back: PersistentStack<T, P>,
+}
+
+/// A persistent queue that is `Send` and `Sync` when its items are.
+pub type ArcPersistentQueue<T> = PersistentQueue<T, ArcPointer>;
+
+impl<T: Clone> PersistentQueue<T> {
+    pub fn new() -> Self {
+        PersistentQueue::empty()
+    }
+}
+
+impl<T: Clone, P: PointerKind> PersistentQueue<T, P> {
+    pub fn empty() -> Self {
+        PersistentQueue {
+            front: PersistentStack::empty(),
+            back: PersistentStack::empty(),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.front.len() + self.back.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.front.is_empty()
+    }
+
+    pub fn front(&self) -> Option<&T> {
+        self.front.head()
+    }
+
+    pub fn push_back(&self, elem: T) -> Self {
+        PersistentQueue::balanced(self.front.clone(), self.back.prepend(elem))
+    }
+
+    /// The queue without its front item.
+    pub fn pop_front(&self) -> Self {
+        PersistentQueue::balanced(self.front.tail(), self.back.clone())
+    }
+
+    /// Iterates from the front to the back.
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        let back = self.back.iter().collect::<Vec<_>>();
+        self.front.iter().chain(back.into_iter().rev())
+    }
+
+    /// Keeps the back no longer than the front, so that the front is only empty when
+    /// the whole queue is.
+    fn balanced(front: PersistentStack<T, P>, back: PersistentStack<T, P>) -> Self {
+        if back.len() <= front.len() {
+            return PersistentQueue { front, back };
+        }
+        let front = front.iter().chain(back.reverse().iter()).cloned().collect();
+        PersistentQueue {
+            front,
+            back: PersistentStack::empty(),
+        }
+    }
+}
+
+impl<T: Clone, P: PointerKind> Default for PersistentQueue<T, P> {
+    fn default() -> Self {
+        Self::empty()
+    }
+}
+
+impl<T, P: PointerKind> Clone for PersistentQueue<T, P> {
+    fn clone(&self) -> Self {
+        PersistentQueue {
+            front: self.front.clone(),
+            back: self.back.clone(),
+        }
+    }
+}
+
+impl<T: Clone, P: PointerKind> FromIterator<T> for PersistentQueue<T, P> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        PersistentQueue {
+            front: iter.into_iter().collect(),
+            back: PersistentStack::empty(),
+        }
+    }
 }
 
 #[cfg(test)]
 mod test {
-    use super::PersistentStack;
+    use super::{ArcPersistentQueue, ArcPersistentStack, PersistentQueue, PersistentStack};
+    use std::collections::HashSet;
+    use std::thread;
 
     #[test]
     fn basics() {
@@ -105,4 +364,80 @@ mod test {
         assert_eq!(iter.next(), Some(&2));
         assert_eq!(iter.next(), Some(&1));
     }
+
+    #[test]
+    fn collect_reverse_and_compare() {
+        let list = (1..=3).collect::<PersistentStack<_>>();
+        let reversed = list.reverse();
+
+        assert_eq!(list.iter().collect::<Vec<_>>(), vec![&1, &2, &3]);
+        assert_eq!(reversed.iter().collect::<Vec<_>>(), vec![&3, &2, &1]);
+        assert_eq!(
+            (list.len(), list.tail().len(), list.iter().len()),
+            (3, 2, 3)
+        );
+        assert_eq!(reversed.reverse(), list);
+        assert_ne!(reversed, list);
+        assert_ne!(list.tail(), list);
+
+        let hashed = HashSet::from([list.clone(), reversed.reverse(), reversed]);
+        assert_eq!(hashed.len(), 2);
+    }
+
+    #[test]
+    fn share_between_threads() {
+        fn shareable<T: Send + Sync>(_: &T) {}
+        let list = (0..1000).collect::<ArcPersistentStack<_>>();
+        shareable(&list);
+
+        let workers = (0..4)
+            .map(|worker| {
+                let list = list.clone();
+                thread::spawn(move || list.prepend(worker).iter().sum::<i32>())
+            })
+            .collect::<Vec<_>>();
+        let sums = workers
+            .into_iter()
+            .map(|worker| worker.join().unwrap())
+            .collect::<Vec<_>>();
+
+        assert_eq!(sums, vec![499500, 499501, 499502, 499503]);
+        assert_eq!(list.len(), 1000);
+    }
+
+    #[test]
+    fn drop_long_list() {
+        let list = (0..1_000_000).collect::<ArcPersistentStack<_>>();
+        let shorter = list.tail();
+        drop(list);
+        assert_eq!(shorter.len(), 999_999);
+    }
+
+    #[test]
+    fn queue_basics() {
+        let queue = (1..=2).collect::<PersistentQueue<_>>();
+        let queue = queue.push_back(3).push_back(4).push_back(5);
+        let older = queue.clone();
+
+        assert_eq!(queue.iter().collect::<Vec<_>>(), vec![&1, &2, &3, &4, &5]);
+        assert_eq!(queue.front(), Some(&1));
+
+        let queue = queue.pop_front().pop_front().pop_front();
+        assert_eq!(queue.front(), Some(&4));
+        assert_eq!(queue.len(), 2);
+        assert_eq!(older.len(), 5);
+
+        let queue = queue.pop_front().pop_front().pop_front();
+        assert!(queue.is_empty());
+        assert_eq!(queue.front(), None);
+    }
+
+    #[test]
+    fn queue_between_threads() {
+        let queue = ArcPersistentQueue::empty().push_back(1).push_back(2);
+
+        let front = thread::spawn(move || queue.pop_front().front().copied());
+
+        assert_eq!(front.join().unwrap(), Some(2));
+    }
 }
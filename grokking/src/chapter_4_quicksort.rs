@@ -129,8 +129,8 @@ mod tests {
         let mut my_vec = vec![1, 2];
         let [first_half, second_half] = partition_slice(&mut my_vec);
 
-        assert_eq!(first_half, []);
-        assert_eq!(second_half, []);
+        assert_eq!(first_half, [0; 0]);
+        assert_eq!(second_half, [0; 0]);
     }
 
     #[test]
@@ -138,7 +138,7 @@ mod tests {
         let mut my_vec = vec![2, 1, 3];
         let [first_half, second_half] = partition_slice(&mut my_vec);
 
-        assert_eq!(first_half, []);
+        assert_eq!(first_half, [0; 0]);
         assert_eq!(second_half, [2, 3]);
     }
 }
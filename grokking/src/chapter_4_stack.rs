@@ -53,7 +53,7 @@ impl<T> Stack<T> {
         self.head.as_ref().map(|node| &node.item)
     }
 
-    fn peek_head_mut(&mut self) -> Option<&mut T> {
+    pub(crate) fn peek_head_mut(&mut self) -> Option<&mut T> {
         self.head.as_mut().map(|node| &mut node.item)
     }
 
@@ -64,6 +64,14 @@ impl<T> Stack<T> {
         }));
     }
 
+    /// Moves the head node on top of another stack, reusing its allocation.
+    pub(crate) fn move_head_to(&mut self, other: &mut Stack<T>) {
+        if let Some(mut node) = self.pop_head_node() {
+            node.next = other.head.take();
+            other.head = Some(node);
+        }
+    }
+
     pub fn pop_head(&mut self) -> Option<T> {
         self.pop_head_node().map(|v| v.item)
     }
@@ -110,6 +118,55 @@ impl<T> Stack<T> {
         node_previous_to_the_one.next = the_one_node_to_remove.next.take();
         Some(the_one_node_to_remove.item)
     }
+
+    pub fn retain<F: FnMut(&mut T) -> bool>(&mut self, mut keep: F) {
+        let mut remaining = self.head.take();
+        let mut tail = &mut self.head;
+        while let Some(mut node) = remaining {
+            remaining = node.next.take();
+            if keep(&mut node.item) {
+                tail = &mut tail.insert(node).next;
+            }
+        }
+    }
+}
+
+impl<T: Clone> Clone for Stack<T> {
+    fn clone(&self) -> Self {
+        let mut items = self.iter().collect::<Vec<_>>();
+        let mut stack = Stack::empty();
+        while let Some(item) = items.pop() {
+            stack.prepend(item.clone());
+        }
+        stack
+    }
+}
+
+impl<T> IntoIterator for Stack<T> {
+    type Item = T;
+    type IntoIter = StackDrain<T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.drain()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a Stack<T> {
+    type Item = &'a T;
+    type IntoIter = StackIter<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a mut Stack<T> {
+    type Item = &'a mut T;
+    type IntoIter = StackIterMut<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
 }
 
 impl<T> Drop for Stack<T> {
@@ -272,4 +329,45 @@ mod tests {
         assert_eq!(stack.pop_head(), Some(2));
         assert_eq!(stack.pop_head(), None);
     }
+
+    #[test]
+    fn should_move_head_to_other_stack() {
+        let mut stack = Stack::empty();
+        let mut other = Stack::empty();
+
+        stack.prepend(2);
+        stack.prepend(1);
+        other.prepend(3);
+        let head_address = stack.peek_head().unwrap() as *const i32;
+        stack.move_head_to(&mut other);
+
+        assert_eq!(other.peek_head().unwrap() as *const i32, head_address);
+        assert_eq!(other.iter().collect::<Vec<_>>(), vec![&1, &3]);
+        assert_eq!(stack.iter().collect::<Vec<_>>(), vec![&2]);
+    }
+
+    #[test]
+    fn should_retain_items() {
+        let mut stack = Stack::empty();
+
+        for item in (1..=6).rev() {
+            stack.prepend(item);
+        }
+
+        stack.retain(|v| *v % 2 == 0);
+
+        assert_eq!(stack.iter().collect::<Vec<_>>(), vec![&2, &4, &6]);
+    }
+
+    #[test]
+    fn should_clone_preserving_order() {
+        let mut stack = Stack::empty();
+
+        stack.prepend(2);
+        stack.prepend(1);
+        let cloned = stack.clone();
+        stack.pop_head();
+
+        assert_eq!(cloned.iter().collect::<Vec<_>>(), vec![&1, &2]);
+    }
 }
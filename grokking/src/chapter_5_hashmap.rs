@@ -1,104 +1,769 @@
 use crate::chapter_4_stack::Stack;
-use std::hash::{DefaultHasher, Hash, Hasher};
+use std::borrow::Borrow;
+use std::error::Error;
+use std::fmt::{self, Debug, Formatter};
+use std::hash::{BuildHasher, Hash, RandomState};
+use std::iter::{Flatten, FusedIterator};
 use std::mem;
+use std::ops::Index;
+use std::{slice, vec};
 
-struct HashTableEntry<K, V>
-where
-    K: Hash + PartialEq,
-{
+#[derive(Clone)]
+struct HashTableEntry<K, V> {
     key: K,
     value: V,
 }
 
-struct HashMap<K, V>
+/// The smallest amount of shelves a hash table starts with.
+pub(crate) const MINIMUM_SHELVES: usize = 16;
+
+/// Finds how many shelves are needed to hold `capacity` values without growing,
+/// or `None` when that amount does not fit in `usize`.
+pub(crate) fn shelves_for_capacity(capacity: usize) -> Option<usize> {
+    let needed = capacity.checked_mul(4)?.div_ceil(3);
+    Some(needed.checked_next_power_of_two()?.max(MINIMUM_SHELVES))
+}
+
+/// How many values the shelves hold before the table has to grow.
+pub(crate) fn capacity_of_shelves(shelves: usize) -> usize {
+    shelves / 4 * 3
+}
+
+/// Halves the shelves while they are less than three sixteenths full, so that a
+/// shrunk table is still far from growing again.
+pub(crate) fn shrunk_shelves(number_of_values: usize, mut shelves: usize) -> usize {
+    while shelves > MINIMUM_SHELVES && 16 * number_of_values < 3 * shelves {
+        shelves /= 2;
+    }
+    shelves
+}
+
+pub(crate) fn try_create_shelves<T>(how_many: usize) -> Result<Vec<Stack<T>>, TryReserveError> {
+    let mut shelves = Vec::new();
+    shelves
+        .try_reserve_exact(how_many)
+        .map_err(|_| TryReserveError::AllocError { shelves: how_many })?;
+    shelves.extend((0..how_many).map(|_| Stack::empty()));
+    Ok(shelves)
+}
+
+/// Moves every node of the old shelves to the new ones, without reallocating them.
+pub(crate) fn move_to_shelves<T, F: Fn(&T) -> usize>(
+    old_shelves: Vec<Stack<T>>,
+    new_shelves: &mut [Stack<T>],
+    shelf_of: F,
+) {
+    for mut shelf in old_shelves {
+        while let Some(item) = shelf.peek_head() {
+            let index = shelf_of(item);
+            shelf.move_head_to(&mut new_shelves[index]);
+        }
+    }
+}
+
+pub(crate) fn shelf_index<Q, S>(hash_builder: &S, key: &Q, shelves: usize) -> usize
+where
+    Q: Hash + ?Sized,
+    S: BuildHasher,
+{
+    (hash_builder.hash_one(key) % shelves as u64) as usize
+}
+
+/// The error returned when a hash table cannot reserve the requested capacity.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TryReserveError {
+    /// The requested capacity does not fit in `usize`.
+    CapacityOverflow,
+    /// The allocator could not provide memory for the requested amount of shelves, which
+    /// are the slots of [`crate::chapter_5_robin_hood_hashmap::RobinHoodHashMap`].
+    AllocError { shelves: usize },
+}
+
+impl fmt::Display for TryReserveError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            TryReserveError::CapacityOverflow => write!(f, "capacity overflow"),
+            TryReserveError::AllocError { shelves } => {
+                write!(f, "memory allocation failed for {shelves} shelves")
+            }
+        }
+    }
+}
+
+impl Error for TryReserveError {}
+
+/// A hash map that chains colliding entries in stacks.
+///
+/// By default keys are hashed with [`RandomState`], which seeds every map
+/// differently to resist HashDoS. Another [`BuildHasher`] can be plugged in
+/// with [`HashMap::with_hasher`], e.g. a deterministic one for reproducible tests.
+///
+/// The table doubles when it gets three quarters full, and shrinks when removals
+/// leave it less than three sixteenths full, though never below the capacity asked
+/// for with [`HashMap::with_capacity`] or [`HashMap::reserve`].
+#[derive(Clone)]
+pub struct HashMap<K, V, S = RandomState>
 where
     K: Hash + PartialEq,
 {
     hash_table: Vec<Stack<HashTableEntry<K, V>>>,
-    number_of_values: u32,
+    number_of_values: usize,
+    /// Removals never shrink the table below the shelves asked for with
+    /// `with_capacity` or `reserve`, only `shrink_to_fit` does.
+    reserved_shelves: usize,
+    hash_builder: S,
 }
 
-impl<K, V> HashMap<K, V>
+impl<K, V> HashMap<K, V, RandomState>
 where
     K: Hash + PartialEq,
 {
-    fn hash_key(&self, key: &K) -> usize {
-        let mut s = DefaultHasher::new();
-        key.hash(&mut s);
-        (s.finish() % self.hash_table.len() as u64) as usize
+    pub fn new() -> Self {
+        Self::with_hasher(RandomState::new())
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self::with_capacity_and_hasher(capacity, RandomState::new())
+    }
+}
+
+impl<K, V, S> HashMap<K, V, S>
+where
+    K: Hash + PartialEq,
+    S: BuildHasher,
+{
+    pub fn with_hasher(hash_builder: S) -> Self {
+        Self::with_capacity_and_hasher(0, hash_builder)
+    }
+
+    /// # Panics
+    /// If the amount of shelves for `capacity` overflows `usize`.
+    pub fn with_capacity_and_hasher(capacity: usize, hash_builder: S) -> Self {
+        let shelves = shelves_for_capacity(capacity).expect("capacity overflow");
+        HashMap {
+            hash_table: Stack::create_many(shelves),
+            number_of_values: 0,
+            reserved_shelves: shelves,
+            hash_builder,
+        }
+    }
+
+    pub fn hasher(&self) -> &S {
+        &self.hash_builder
+    }
+
+    /// How many values the map holds before it has to grow.
+    pub fn capacity(&self) -> usize {
+        capacity_of_shelves(self.hash_table.len())
+    }
+
+    /// Makes room for at least `additional` more values.
+    ///
+    /// # Panics
+    /// If the new capacity overflows `usize` or cannot be allocated.
+    pub fn reserve(&mut self, additional: usize) {
+        if let Err(error) = self.try_reserve(additional) {
+            panic!("{error}");
+        }
+    }
+
+    /// Makes room for at least `additional` more values, reporting failures instead of panicking.
+    pub fn try_reserve(&mut self, additional: usize) -> Result<(), TryReserveError> {
+        let shelves = self
+            .number_of_values
+            .checked_add(additional)
+            .and_then(shelves_for_capacity)
+            .ok_or(TryReserveError::CapacityOverflow)?;
+        if shelves > self.hash_table.len() {
+            self.resize_table(shelves)?;
+        }
+        self.reserved_shelves = self.reserved_shelves.max(shelves);
+        Ok(())
+    }
+
+    /// Gives back as much memory as possible while still holding every value.
+    ///
+    /// This also drops the capacity reserved earlier, so that removals may shrink the
+    /// table again.
+    pub fn shrink_to_fit(&mut self) {
+        let shelves = shelves_for_capacity(self.number_of_values).expect("Current size should fit");
+        self.reserved_shelves = shelves;
+        if shelves < self.hash_table.len() {
+            // Shrinking only saves memory, so the current table stays if it cannot allocate.
+            let _ = self.resize_table(shelves);
+        }
+    }
+
+    fn hash_key<Q: Hash + ?Sized>(&self, key: &Q) -> usize {
+        shelf_index(&self.hash_builder, key, self.hash_table.len())
+    }
+
+    fn resize_table(&mut self, shelves: usize) -> Result<(), TryReserveError> {
+        let old_hash_table = mem::replace(&mut self.hash_table, try_create_shelves(shelves)?);
+        let hash_builder = &self.hash_builder;
+        // Keys are already unique, so entries can be moved without looking for duplicates.
+        move_to_shelves(old_hash_table, &mut self.hash_table, |entry| {
+            shelf_index(hash_builder, &entry.key, shelves)
+        });
+        Ok(())
     }
 
     fn maybe_grow_table(&mut self) {
         let old_hash_table_len = self.hash_table.len();
 
-        let should_grow = 4 * self.number_of_values > 3 * old_hash_table_len as u32;
-        if !should_grow {
-            return;
+        let should_grow = self.number_of_values > capacity_of_shelves(old_hash_table_len);
+        if should_grow {
+            self.resize_table(old_hash_table_len * 2)
+                .expect("Growing the table should be able to allocate");
         }
-
-        let preserved_old_hash_table = mem::replace(
-            &mut self.hash_table,
-            Stack::create_many(old_hash_table_len * 2),
-        );
-        self.number_of_values = 0;
-        preserved_old_hash_table
-            .into_iter()
-            .flat_map(Stack::drain)
-            .for_each(|entry| self.insert_hash_table_entry(entry));
     }
 
-    pub fn new() -> Self {
-        HashMap {
-            hash_table: Stack::create_many(16),
-            number_of_values: 0,
+    fn maybe_shrink_table(&mut self) {
+        let shelves =
+            shrunk_shelves(self.number_of_values, self.hash_table.len()).max(self.reserved_shelves);
+        if shelves < self.hash_table.len() {
+            // A failed allocation leaves the current table in place, which still holds everything.
+            let _ = self.resize_table(shelves);
         }
     }
 
-    fn insert_hash_table_entry(&mut self, new_entry: HashTableEntry<K, V>) {
+    fn insert_hash_table_entry(&mut self, new_entry: HashTableEntry<K, V>) -> Option<V> {
         let hash = self.hash_key(&new_entry.key);
         let shelf = &mut self.hash_table[hash];
         if let Some(existing_entry) = shelf
             .iter_mut()
             .find(|existing_entry| existing_entry.key == new_entry.key)
         {
-            existing_entry.value = new_entry.value
+            Some(mem::replace(&mut existing_entry.value, new_entry.value))
         } else {
             shelf.prepend(new_entry);
             self.number_of_values += 1;
+            None
         }
     }
 
-    pub fn insert(&mut self, key: K, value: V) {
-        self.insert_hash_table_entry(HashTableEntry { key, value });
+    /// Inserts the value, returning the one previously stored under the same key.
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        let previous = self.insert_hash_table_entry(HashTableEntry { key, value });
         self.maybe_grow_table();
+        previous
+    }
+
+    pub fn entry(&mut self, key: K) -> Entry<'_, K, V, S> {
+        if self.contains_key(&key) {
+            Entry::Occupied(OccupiedEntry { map: self, key })
+        } else {
+            Entry::Vacant(VacantEntry { map: self, key })
+        }
     }
 
-    pub fn get(&self, key_ref: &K) -> Option<&V> {
+    pub fn get<Q>(&self, key_ref: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + PartialEq + ?Sized,
+    {
         let hash = self.hash_key(key_ref);
         self.hash_table[hash]
             .iter()
-            .find(|&it| &it.key == key_ref)
+            .find(|&it| it.key.borrow() == key_ref)
             .map(|entry| &entry.value)
     }
 
-    pub fn remove(&mut self, key_ref: &K) -> Option<V> {
+    pub fn get_mut<Q>(&mut self, key_ref: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + PartialEq + ?Sized,
+    {
         let hash = self.hash_key(key_ref);
         self.hash_table[hash]
-            .remove_by(|it| &it.key == key_ref)
-            .map(|entry| {
-                self.number_of_values -= 1;
-                entry.value
-            })
+            .iter_mut()
+            .find(|it| it.key.borrow() == key_ref)
+            .map(|entry| &mut entry.value)
+    }
+
+    pub fn contains_key<Q>(&self, key_ref: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + PartialEq + ?Sized,
+    {
+        self.get(key_ref).is_some()
+    }
+
+    pub fn remove<Q>(&mut self, key_ref: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + PartialEq + ?Sized,
+    {
+        let hash = self.hash_key(key_ref);
+        let removed = self.hash_table[hash].remove_by(|it| it.key.borrow() == key_ref)?;
+        self.number_of_values -= 1;
+        self.maybe_shrink_table();
+        Some(removed.value)
     }
 
     pub fn size(&self) -> u32 {
+        self.number_of_values as u32
+    }
+
+    pub fn len(&self) -> usize {
         self.number_of_values
     }
+
+    pub fn is_empty(&self) -> bool {
+        self.number_of_values == 0
+    }
+
+    pub fn clear(&mut self) {
+        self.drain();
+    }
+
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        Iter {
+            entries: self.hash_table.iter().flatten(),
+            remaining: self.len(),
+        }
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_, K, V> {
+        IterMut {
+            remaining: self.len(),
+            entries: self.hash_table.iter_mut().flatten(),
+        }
+    }
+
+    pub fn keys(&self) -> Keys<'_, K, V> {
+        Keys(self.iter())
+    }
+
+    pub fn values(&self) -> Values<'_, K, V> {
+        Values(self.iter())
+    }
+
+    pub fn values_mut(&mut self) -> ValuesMut<'_, K, V> {
+        ValuesMut(self.iter_mut())
+    }
+
+    /// Empties the map, yielding every key and value it held.
+    pub fn drain(&mut self) -> Drain<K, V> {
+        let hash_table_len = self.hash_table.len();
+        let hash_table = mem::replace(&mut self.hash_table, Stack::create_many(hash_table_len));
+        let remaining = mem::replace(&mut self.number_of_values, 0);
+        Drain(IntoIter {
+            entries: hash_table.into_iter().flatten(),
+            remaining,
+        })
+    }
+
+    /// Keeps only the entries for which `keep` returns true.
+    pub fn retain<F: FnMut(&K, &mut V) -> bool>(&mut self, mut keep: F) {
+        let mut removed = 0;
+        for shelf in &mut self.hash_table {
+            shelf.retain(|entry| {
+                let should_keep = keep(&entry.key, &mut entry.value);
+                if !should_keep {
+                    removed += 1;
+                }
+                should_keep
+            });
+        }
+        self.number_of_values -= removed;
+        self.maybe_shrink_table();
+    }
+}
+
+impl<K, V, S> Default for HashMap<K, V, S>
+where
+    K: Hash + PartialEq,
+    S: BuildHasher + Default,
+{
+    fn default() -> Self {
+        Self::with_hasher(S::default())
+    }
+}
+
+impl<K, V, S> Extend<(K, V)> for HashMap<K, V, S>
+where
+    K: Hash + PartialEq,
+    S: BuildHasher,
+{
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        for (key, value) in iter {
+            self.insert(key, value);
+        }
+    }
+}
+
+impl<K, V, S> FromIterator<(K, V)> for HashMap<K, V, S>
+where
+    K: Hash + PartialEq,
+    S: BuildHasher + Default,
+{
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut map = HashMap::default();
+        map.extend(iter);
+        map
+    }
+}
+
+impl<K, V, S> IntoIterator for HashMap<K, V, S>
+where
+    K: Hash + PartialEq,
+    S: BuildHasher,
+{
+    type Item = (K, V);
+    type IntoIter = IntoIter<K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter {
+            remaining: self.len(),
+            entries: self.hash_table.into_iter().flatten(),
+        }
+    }
+}
+
+impl<'a, K, V, S> IntoIterator for &'a HashMap<K, V, S>
+where
+    K: Hash + PartialEq,
+    S: BuildHasher,
+{
+    type Item = (&'a K, &'a V);
+    type IntoIter = Iter<'a, K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, K, V, S> IntoIterator for &'a mut HashMap<K, V, S>
+where
+    K: Hash + PartialEq,
+    S: BuildHasher,
+{
+    type Item = (&'a K, &'a mut V);
+    type IntoIter = IterMut<'a, K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+impl<K, Q, V, S> Index<&Q> for HashMap<K, V, S>
+where
+    K: Hash + PartialEq + Borrow<Q>,
+    S: BuildHasher,
+    Q: Hash + PartialEq + ?Sized,
+{
+    type Output = V;
+
+    fn index(&self, key: &Q) -> &V {
+        self.get(key).expect("Key not found in HashMap")
+    }
+}
+
+impl<K, V, S> Debug for HashMap<K, V, S>
+where
+    K: Hash + PartialEq + Debug,
+    S: BuildHasher,
+    V: Debug,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<K, V, S> PartialEq for HashMap<K, V, S>
+where
+    K: Hash + PartialEq,
+    S: BuildHasher,
+    V: PartialEq,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len()
+            && self
+                .iter()
+                .all(|(key, value)| other.get(key) == Some(value))
+    }
+}
+
+impl<K, V, S> Eq for HashMap<K, V, S>
+where
+    K: Hash + Eq,
+    S: BuildHasher,
+    V: Eq,
+{
+}
+
+pub enum Entry<'a, K, V, S = RandomState>
+where
+    K: Hash + PartialEq,
+{
+    Occupied(OccupiedEntry<'a, K, V, S>),
+    Vacant(VacantEntry<'a, K, V, S>),
+}
+
+impl<'a, K, V, S> Entry<'a, K, V, S>
+where
+    K: Hash + PartialEq,
+    S: BuildHasher,
+{
+    pub fn key(&self) -> &K {
+        match self {
+            Entry::Occupied(entry) => entry.key(),
+            Entry::Vacant(entry) => entry.key(),
+        }
+    }
+
+    pub fn or_insert(self, default: V) -> &'a mut V {
+        self.or_insert_with(|| default)
+    }
+
+    pub fn or_insert_with<F: FnOnce() -> V>(self, default: F) -> &'a mut V {
+        match self {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(default()),
+        }
+    }
+
+    pub fn or_default(self) -> &'a mut V
+    where
+        V: Default,
+    {
+        self.or_insert_with(V::default)
+    }
+
+    pub fn and_modify<F: FnOnce(&mut V)>(mut self, modify: F) -> Self {
+        if let Entry::Occupied(entry) = &mut self {
+            modify(entry.get_mut());
+        }
+        self
+    }
+}
+
+/// An entry whose key is already stored in the map.
+pub struct OccupiedEntry<'a, K, V, S = RandomState>
+where
+    K: Hash + PartialEq,
+{
+    map: &'a mut HashMap<K, V, S>,
+    key: K,
+}
+
+impl<'a, K, V, S> OccupiedEntry<'a, K, V, S>
+where
+    K: Hash + PartialEq,
+    S: BuildHasher,
+{
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+    pub fn get(&self) -> &V {
+        self.map
+            .get(&self.key)
+            .expect("Occupied entry should be in the map")
+    }
+
+    pub fn get_mut(&mut self) -> &mut V {
+        self.map
+            .get_mut(&self.key)
+            .expect("Occupied entry should be in the map")
+    }
+
+    pub fn into_mut(self) -> &'a mut V {
+        self.map
+            .get_mut(&self.key)
+            .expect("Occupied entry should be in the map")
+    }
+
+    pub fn insert(&mut self, value: V) -> V {
+        mem::replace(self.get_mut(), value)
+    }
+
+    pub fn remove(self) -> V {
+        self.map
+            .remove(&self.key)
+            .expect("Occupied entry should be in the map")
+    }
+}
+
+/// An entry whose key is not stored in the map yet.
+pub struct VacantEntry<'a, K, V, S = RandomState>
+where
+    K: Hash + PartialEq,
+{
+    map: &'a mut HashMap<K, V, S>,
+    key: K,
+}
+
+impl<'a, K, V, S> VacantEntry<'a, K, V, S>
+where
+    K: Hash + PartialEq,
+    S: BuildHasher,
+{
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+    pub fn into_key(self) -> K {
+        self.key
+    }
+
+    pub fn insert(self, value: V) -> &'a mut V {
+        let map = self.map;
+        map.number_of_values += 1;
+        map.maybe_grow_table();
+
+        let hash = map.hash_key(&self.key);
+        let shelf = &mut map.hash_table[hash];
+        shelf.prepend(HashTableEntry {
+            key: self.key,
+            value,
+        });
+        &mut shelf
+            .peek_head_mut()
+            .expect("Entry was just prepended")
+            .value
+    }
+}
+
+pub struct Iter<'a, K, V> {
+    entries: Flatten<slice::Iter<'a, Stack<HashTableEntry<K, V>>>>,
+    remaining: usize,
 }
 
+impl<'a, K, V> Iterator for Iter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let entry = self.entries.next()?;
+        self.remaining -= 1;
+        Some((&entry.key, &entry.value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<K, V> ExactSizeIterator for Iter<'_, K, V> {}
+impl<K, V> FusedIterator for Iter<'_, K, V> {}
+
+pub struct IterMut<'a, K, V> {
+    entries: Flatten<slice::IterMut<'a, Stack<HashTableEntry<K, V>>>>,
+    remaining: usize,
+}
+
+impl<'a, K, V> Iterator for IterMut<'a, K, V> {
+    type Item = (&'a K, &'a mut V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let entry = self.entries.next()?;
+        self.remaining -= 1;
+        Some((&entry.key, &mut entry.value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<K, V> ExactSizeIterator for IterMut<'_, K, V> {}
+impl<K, V> FusedIterator for IterMut<'_, K, V> {}
+
+pub struct IntoIter<K, V> {
+    entries: Flatten<vec::IntoIter<Stack<HashTableEntry<K, V>>>>,
+    remaining: usize,
+}
+
+impl<K, V> Iterator for IntoIter<K, V> {
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let entry = self.entries.next()?;
+        self.remaining -= 1;
+        Some((entry.key, entry.value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<K, V> ExactSizeIterator for IntoIter<K, V> {}
+impl<K, V> FusedIterator for IntoIter<K, V> {}
+
+pub struct Drain<K, V>(IntoIter<K, V>);
+
+impl<K, V> Iterator for Drain<K, V> {
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl<K, V> ExactSizeIterator for Drain<K, V> {}
+impl<K, V> FusedIterator for Drain<K, V> {}
+
+pub struct Keys<'a, K, V>(Iter<'a, K, V>);
+
+impl<'a, K, V> Iterator for Keys<'a, K, V> {
+    type Item = &'a K;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(|(key, _)| key)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl<K, V> ExactSizeIterator for Keys<'_, K, V> {}
+impl<K, V> FusedIterator for Keys<'_, K, V> {}
+
+pub struct Values<'a, K, V>(Iter<'a, K, V>);
+
+impl<'a, K, V> Iterator for Values<'a, K, V> {
+    type Item = &'a V;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(|(_, value)| value)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl<K, V> ExactSizeIterator for Values<'_, K, V> {}
+impl<K, V> FusedIterator for Values<'_, K, V> {}
+
+pub struct ValuesMut<'a, K, V>(IterMut<'a, K, V>);
+
+impl<'a, K, V> Iterator for ValuesMut<'a, K, V> {
+    type Item = &'a mut V;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(|(_, value)| value)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl<K, V> ExactSizeIterator for ValuesMut<'_, K, V> {}
+impl<K, V> FusedIterator for ValuesMut<'_, K, V> {}
+
 #[cfg(test)]
 mod tests {
-    use crate::chapter_5_hashmap::HashMap;
+    use crate::chapter_5_hashmap::{Entry, HashMap, TryReserveError, shelves_for_capacity};
+    use std::hash::{BuildHasher, BuildHasherDefault, DefaultHasher, RandomState};
 
     #[test]
     fn should_insert_and_get() {
@@ -110,6 +775,14 @@ mod tests {
         assert_eq!(map.get(&"key"), Some(&2));
     }
 
+    #[test]
+    fn should_return_previous_value_on_insert() {
+        let mut map = HashMap::new();
+
+        assert_eq!(map.insert("key", 1), None);
+        assert_eq!(map.insert("key", 2), Some(1));
+    }
+
     #[test]
     fn should_remove() {
         let mut map = HashMap::new();
@@ -148,4 +821,308 @@ mod tests {
 
         assert_eq!(map.get(&"key".to_string()), None);
     }
+
+    #[test]
+    fn should_collect_from_iterator() {
+        let map: HashMap<&str, i32> = [("a", 1), ("b", 2), ("a", 3)].into_iter().collect();
+
+        assert_eq!(map.get(&"a"), Some(&3));
+        assert_eq!(map.get(&"b"), Some(&2));
+        assert_eq!(map.size(), 2);
+    }
+
+    #[test]
+    fn should_lookup_string_keys_by_str() {
+        let mut map = HashMap::new();
+
+        map.insert("key".to_string(), 1);
+
+        assert_eq!(map.get("key"), Some(&1));
+        assert!(map.contains_key("key"));
+        assert_eq!(map["key"], 1);
+        assert_eq!(map.remove("key"), Some(1));
+        assert!(!map.contains_key("key"));
+    }
+
+    #[test]
+    #[should_panic]
+    fn should_panic_when_indexing_missing_key() {
+        let map: HashMap<&str, i32> = HashMap::new();
+
+        let _ = map[&"key"];
+    }
+
+    #[test]
+    fn should_count_words_with_entry() {
+        let mut map = HashMap::new();
+
+        for word in "a b a c a b".split(' ') {
+            map.entry(word).and_modify(|count| *count += 1).or_insert(1);
+        }
+
+        assert_eq!(map.get(&"a"), Some(&3));
+        assert_eq!(map.get(&"b"), Some(&2));
+        assert_eq!(map.get(&"c"), Some(&1));
+        assert_eq!(map.len(), 3);
+    }
+
+    #[test]
+    fn should_keep_entry_reference_valid_across_growth() {
+        let mut map: HashMap<i32, i32> = HashMap::new();
+
+        for i in 0..100 {
+            *map.entry(i).or_default() += i;
+        }
+
+        assert_eq!(map.len(), 100);
+        for i in 0..100 {
+            assert_eq!(map[&i], i);
+        }
+    }
+
+    #[test]
+    fn should_remove_occupied_entry() {
+        let mut map = HashMap::new();
+        map.insert("key", 1);
+
+        if let Entry::Occupied(entry) = map.entry("key") {
+            assert_eq!(entry.remove(), 1);
+        }
+
+        assert!(map.is_empty());
+    }
+
+    #[test]
+    fn should_mutate_through_get_mut_and_iter_mut() {
+        let mut map: HashMap<i32, i32> = (0..10).map(|i| (i, i)).collect();
+
+        *map.get_mut(&0).unwrap() = 100;
+        for (_, value) in map.iter_mut() {
+            *value *= 2;
+        }
+
+        assert_eq!(map[&0], 200);
+        assert_eq!(map.values().sum::<i32>(), 200 + 2 * (1..10).sum::<i32>());
+    }
+
+    #[test]
+    fn should_iterate_keys_and_values() {
+        let map: HashMap<i32, String> = (0..50).map(|i| (i, i.to_string())).collect();
+
+        let mut keys = map.keys().copied().collect::<Vec<_>>();
+        keys.sort();
+        let mut pairs = (&map)
+            .into_iter()
+            .map(|(key, value)| (*key, value.clone()))
+            .collect::<Vec<_>>();
+        pairs.sort();
+
+        assert_eq!(map.iter().len(), 50);
+        assert_eq!(keys, (0..50).collect::<Vec<_>>());
+        assert_eq!(
+            pairs,
+            (0..50).map(|i| (i, i.to_string())).collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn should_drain_and_leave_map_empty() {
+        let mut map: HashMap<i32, i32> = (0..20).map(|i| (i, i)).collect();
+
+        let mut drained = map.drain().collect::<Vec<_>>();
+        drained.sort();
+
+        assert_eq!(drained, (0..20).map(|i| (i, i)).collect::<Vec<_>>());
+        assert!(map.is_empty());
+        assert_eq!(map.get(&0), None);
+    }
+
+    #[test]
+    fn should_retain_matching_entries() {
+        let mut map: HashMap<i32, i32> = (0..20).map(|i| (i, i)).collect();
+
+        map.retain(|key, _| key % 2 == 0);
+
+        assert_eq!(map.len(), 10);
+        assert!(map.contains_key(&4));
+        assert!(!map.contains_key(&5));
+    }
+
+    #[test]
+    fn should_clone_and_compare() {
+        let map: HashMap<i32, i32> = (0..20).map(|i| (i, i)).collect();
+        let mut cloned = map.clone();
+
+        assert_eq!(map, cloned);
+        cloned.insert(0, 1);
+        assert_ne!(map, cloned);
+        cloned.insert(0, 0);
+        cloned.insert(20, 20);
+        assert_ne!(map, cloned);
+    }
+
+    #[test]
+    fn should_format_as_map() {
+        let map: HashMap<&str, i32> = [("key", 1)].into_iter().collect();
+
+        assert_eq!(format!("{map:?}"), r#"{"key": 1}"#);
+    }
+
+    #[test]
+    fn should_consume_into_pairs() {
+        let map: HashMap<i32, i32> = (0..5).map(|i| (i, i * i)).collect();
+
+        let mut pairs = map.into_iter().collect::<Vec<_>>();
+        pairs.sort();
+
+        assert_eq!(pairs, vec![(0, 0), (1, 1), (2, 4), (3, 9), (4, 16)]);
+    }
+
+    #[test]
+    fn should_work_with_deterministic_hasher() {
+        let mut map: HashMap<&str, i32, BuildHasherDefault<DefaultHasher>> =
+            HashMap::with_hasher(BuildHasherDefault::default());
+        let mut other: HashMap<&str, i32, BuildHasherDefault<DefaultHasher>> = HashMap::default();
+
+        for (i, key) in ["a", "b", "c", "d"].into_iter().enumerate() {
+            map.insert(key, i as i32);
+            other.insert(key, i as i32);
+        }
+
+        assert_eq!(map["c"], 2);
+        assert_eq!(
+            map.keys().collect::<Vec<_>>(),
+            other.keys().collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn should_seed_every_default_map_differently() {
+        let map: HashMap<i32, i32> = HashMap::new();
+        let other: HashMap<i32, i32> = HashMap::new();
+
+        assert_ne!(map.hasher().hash_one(42), other.hasher().hash_one(42));
+        assert_ne!(RandomState::new().hash_one(42), map.hasher().hash_one(42));
+    }
+
+    #[test]
+    fn should_preallocate_shelves_for_capacity() {
+        let map: HashMap<i32, i32> = HashMap::with_capacity_and_hasher(100, RandomState::new());
+
+        assert_eq!(shelves_for_capacity(0), Some(16));
+        assert_eq!(shelves_for_capacity(12), Some(16));
+        assert_eq!(shelves_for_capacity(13), Some(32));
+        assert_eq!(shelves_for_capacity(usize::MAX), None);
+        assert_eq!(map.hash_table.len(), 256);
+    }
+
+    #[test]
+    fn should_not_grow_before_reaching_capacity() {
+        let mut map = HashMap::with_capacity(100);
+        let capacity = map.capacity();
+
+        for i in 0..capacity {
+            map.insert(i, i);
+        }
+
+        assert!(capacity >= 100);
+        assert_eq!(map.capacity(), capacity);
+        map.insert(capacity, capacity);
+        assert!(map.capacity() > capacity);
+    }
+
+    #[test]
+    fn should_reserve_additional_capacity() {
+        let mut map: HashMap<i32, i32> = (0..10).map(|i| (i, i)).collect();
+
+        map.reserve(1000);
+
+        assert!(map.capacity() >= 1010);
+        assert!((0..10).all(|i| map[&i] == i));
+    }
+
+    #[test]
+    fn should_report_capacity_overflow() {
+        let mut map: HashMap<i32, i32> = (0..10).map(|i| (i, i)).collect();
+
+        assert_eq!(
+            map.try_reserve(usize::MAX),
+            Err(TryReserveError::CapacityOverflow)
+        );
+        assert_eq!(map.try_reserve(10), Ok(()));
+        assert_eq!(map.len(), 10);
+    }
+
+    #[test]
+    #[should_panic(expected = "capacity overflow")]
+    fn should_panic_when_reserving_too_much() {
+        let mut map: HashMap<i32, i32> = HashMap::new();
+
+        map.reserve(usize::MAX);
+    }
+
+    #[test]
+    fn should_shrink_to_fit() {
+        let mut map: HashMap<i32, i32> = HashMap::with_capacity(1000);
+        map.extend((0..10).map(|i| (i, i)));
+
+        map.shrink_to_fit();
+
+        assert_eq!(map.capacity(), 12);
+        assert!((0..10).all(|i| map[&i] == i));
+    }
+
+    #[test]
+    fn should_shrink_after_mass_removal() {
+        let mut map: HashMap<i32, i32> = (0..1000).map(|i| (i, i)).collect();
+        let grown_capacity = map.capacity();
+
+        for i in 10..1000 {
+            map.remove(&i);
+        }
+
+        assert!(map.capacity() < grown_capacity / 8);
+        assert!(map.len() <= map.capacity());
+        assert!((0..10).all(|i| map[&i] == i));
+
+        map.retain(|key, _| *key < 5);
+        assert_eq!(map.len(), 5);
+    }
+
+    #[test]
+    fn should_not_shrink_below_reserved_capacity() {
+        let mut map = HashMap::with_capacity(1000);
+        let reserved_capacity = map.capacity();
+
+        map.insert(0, 0);
+        map.remove(&0);
+        assert_eq!(map.capacity(), reserved_capacity);
+
+        map.reserve(5000);
+        let reserved_capacity = map.capacity();
+        map.extend((0..5000).map(|i| (i, i)));
+        map.retain(|key, _| *key < 10);
+        assert_eq!(map.capacity(), reserved_capacity);
+
+        map.shrink_to_fit();
+        assert_eq!(map.capacity(), 12);
+        map.extend((10..1000).map(|i| (i, i)));
+        for i in 10..1000 {
+            map.remove(&i);
+        }
+        assert!(map.capacity() < 100);
+    }
+
+    #[test]
+    fn should_reuse_nodes_when_rehashing() {
+        let mut map = HashMap::new();
+        map.insert(0, 0);
+        let address = map.get(&0).unwrap() as *const i32;
+
+        for i in 1..1000 {
+            map.insert(i, i);
+        }
+
+        assert_eq!(map.get(&0).unwrap() as *const i32, address);
+    }
 }
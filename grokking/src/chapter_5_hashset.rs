@@ -1,48 +1,142 @@
 use crate::chapter_4_stack::Stack;
-use std::hash::{DefaultHasher, Hash, Hasher};
+use crate::chapter_5_hashmap::{
+    TryReserveError, capacity_of_shelves, move_to_shelves, shelf_index, shelves_for_capacity,
+    shrunk_shelves, try_create_shelves,
+};
+use std::fmt::{self, Debug, Formatter};
+use std::hash::{BuildHasher, Hash, RandomState};
+use std::iter::{Chain, Flatten, FusedIterator};
 use std::mem;
+use std::ops::{BitAnd, BitOr, BitXor, Sub};
+use std::{slice, vec};
 
-pub struct HashSet<K>
+/// A hash set that chains colliding values in stacks.
+///
+/// Like [`crate::chapter_5_hashmap::HashMap`], it hashes with a randomly seeded
+/// [`RandomState`] unless another [`BuildHasher`] is given, and it grows and
+/// shrinks its table by the same rules.
+#[derive(Clone)]
+pub struct HashSet<K, S = RandomState>
 where
     K: Hash + PartialEq,
 {
     hash_table: Vec<Stack<K>>,
-    number_of_values: u32,
+    number_of_values: usize,
+    /// Removals never shrink the table below the shelves asked for with
+    /// `with_capacity` or `reserve`, only `shrink_to_fit` does.
+    reserved_shelves: usize,
+    hash_builder: S,
 }
 
-impl<K> HashSet<K>
+impl<K> HashSet<K, RandomState>
 where
     K: Hash + PartialEq,
 {
+    pub fn new() -> Self {
+        Self::with_hasher(RandomState::new())
+    }
+
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self::with_capacity_and_hasher(capacity, RandomState::new())
+    }
+}
+
+impl<K, S> HashSet<K, S>
+where
+    K: Hash + PartialEq,
+    S: BuildHasher,
+{
+    pub fn with_hasher(hash_builder: S) -> Self {
+        Self::with_capacity_and_hasher(0, hash_builder)
+    }
+
+    /// # Panics
+    /// If the amount of shelves for `capacity` overflows `usize`.
+    pub fn with_capacity_and_hasher(capacity: usize, hash_builder: S) -> Self {
+        let shelves = shelves_for_capacity(capacity).expect("capacity overflow");
+        HashSet {
+            hash_table: Stack::create_many(shelves),
+            number_of_values: 0,
+            reserved_shelves: shelves,
+            hash_builder,
+        }
+    }
+
+    pub fn hasher(&self) -> &S {
+        &self.hash_builder
+    }
+
+    /// How many values the set holds before it has to grow.
+    pub fn capacity(&self) -> usize {
+        capacity_of_shelves(self.hash_table.len())
+    }
+
+    /// Makes room for at least `additional` more values.
+    ///
+    /// # Panics
+    /// If the new capacity overflows `usize` or cannot be allocated.
+    pub fn reserve(&mut self, additional: usize) {
+        if let Err(error) = self.try_reserve(additional) {
+            panic!("{error}");
+        }
+    }
+
+    /// Makes room for at least `additional` more values, reporting failures instead of panicking.
+    pub fn try_reserve(&mut self, additional: usize) -> Result<(), TryReserveError> {
+        let shelves = self
+            .number_of_values
+            .checked_add(additional)
+            .and_then(shelves_for_capacity)
+            .ok_or(TryReserveError::CapacityOverflow)?;
+        if shelves > self.hash_table.len() {
+            self.resize_table(shelves)?;
+        }
+        self.reserved_shelves = self.reserved_shelves.max(shelves);
+        Ok(())
+    }
+
+    /// Gives back as much memory as possible while still holding every value.
+    ///
+    /// This also drops the capacity reserved earlier, so that removals may shrink the
+    /// table again.
+    pub fn shrink_to_fit(&mut self) {
+        let shelves = shelves_for_capacity(self.number_of_values).expect("Current size should fit");
+        self.reserved_shelves = shelves;
+        if shelves < self.hash_table.len() {
+            // Shrinking only saves memory, so the current table stays if it cannot allocate.
+            let _ = self.resize_table(shelves);
+        }
+    }
+
     fn hash_key(&self, key: &K) -> usize {
-        let mut s = DefaultHasher::new();
-        key.hash(&mut s);
-        (s.finish() % self.hash_table.len() as u64) as usize
+        shelf_index(&self.hash_builder, key, self.hash_table.len())
+    }
+
+    fn resize_table(&mut self, shelves: usize) -> Result<(), TryReserveError> {
+        let old_hash_table = mem::replace(&mut self.hash_table, try_create_shelves(shelves)?);
+        let hash_builder = &self.hash_builder;
+        move_to_shelves(old_hash_table, &mut self.hash_table, |value| {
+            shelf_index(hash_builder, value, shelves)
+        });
+        Ok(())
     }
 
     fn maybe_resize_table(&mut self) {
         let old_hash_table_len = self.hash_table.len();
 
-        let should_resize = 4 * self.number_of_values > 3 * old_hash_table_len as u32;
-        if !should_resize {
-            return;
+        let should_resize = self.number_of_values > capacity_of_shelves(old_hash_table_len);
+        if should_resize {
+            self.resize_table(old_hash_table_len * 2)
+                .expect("Growing the table should be able to allocate");
         }
-
-        let preserved_old_hash_table = mem::replace(
-            &mut self.hash_table,
-            Stack::create_many(old_hash_table_len * 2),
-        );
-        self.number_of_values = 0;
-        preserved_old_hash_table
-            .into_iter()
-            .flat_map(Stack::drain)
-            .for_each(|entry| self.insert_hash_table_value(entry));
     }
 
-    pub fn new() -> Self {
-        HashSet {
-            hash_table: Stack::create_many(16),
-            number_of_values: 0,
+    fn maybe_shrink_table(&mut self) {
+        let shelves =
+            shrunk_shelves(self.number_of_values, self.hash_table.len()).max(self.reserved_shelves);
+        if shelves < self.hash_table.len() {
+            // A failed allocation leaves the current table in place, which still holds everything.
+            let _ = self.resize_table(shelves);
         }
     }
 
@@ -67,22 +161,407 @@ where
 
     pub fn remove(&mut self, lookup_value: &K) -> bool {
         let hash = self.hash_key(lookup_value);
-        self.hash_table[hash]
+        let removed = self.hash_table[hash]
             .remove_by(|value_in_shelf| value_in_shelf == lookup_value)
-            .map(|_| {
-                self.number_of_values -= 1;
-            })
-            .is_some()
+            .is_some();
+        if removed {
+            self.number_of_values -= 1;
+            self.maybe_shrink_table();
+        }
+        removed
     }
 
     pub fn size(&self) -> u32 {
+        self.number_of_values as u32
+    }
+
+    pub fn len(&self) -> usize {
         self.number_of_values
     }
+
+    pub fn is_empty(&self) -> bool {
+        self.number_of_values == 0
+    }
+
+    pub fn clear(&mut self) {
+        self.drain();
+    }
+
+    pub fn iter(&self) -> Iter<'_, K> {
+        Iter {
+            values: self.hash_table.iter().flatten(),
+            remaining: self.len(),
+        }
+    }
+
+    /// Empties the set, yielding every value it held.
+    pub fn drain(&mut self) -> Drain<K> {
+        let hash_table_len = self.hash_table.len();
+        let hash_table = mem::replace(&mut self.hash_table, Stack::create_many(hash_table_len));
+        let remaining = mem::replace(&mut self.number_of_values, 0);
+        Drain(IntoIter {
+            values: hash_table.into_iter().flatten(),
+            remaining,
+        })
+    }
+
+    /// Keeps only the values for which `keep` returns true.
+    pub fn retain<F: FnMut(&K) -> bool>(&mut self, mut keep: F) {
+        let mut removed = 0;
+        for shelf in &mut self.hash_table {
+            shelf.retain(|value| {
+                let should_keep = keep(value);
+                if !should_keep {
+                    removed += 1;
+                }
+                should_keep
+            });
+        }
+        self.number_of_values -= removed;
+        self.maybe_shrink_table();
+    }
+
+    /// Lazily visits the values in `self` or in `other`, without repeating them.
+    pub fn union<'a>(&'a self, other: &'a HashSet<K, S>) -> Union<'a, K, S> {
+        Union(self.iter().chain(other.difference(self)))
+    }
+
+    /// Lazily visits the values in both `self` and `other`.
+    pub fn intersection<'a>(&'a self, other: &'a HashSet<K, S>) -> Intersection<'a, K, S> {
+        Intersection {
+            iter: self.iter(),
+            other,
+        }
+    }
+
+    /// Lazily visits the values in `self` but not in `other`.
+    pub fn difference<'a>(&'a self, other: &'a HashSet<K, S>) -> Difference<'a, K, S> {
+        Difference {
+            iter: self.iter(),
+            other,
+        }
+    }
+
+    /// Lazily visits the values in either `self` or `other`, but not in both.
+    pub fn symmetric_difference<'a>(
+        &'a self,
+        other: &'a HashSet<K, S>,
+    ) -> SymmetricDifference<'a, K, S> {
+        SymmetricDifference(self.difference(other).chain(other.difference(self)))
+    }
+
+    pub fn is_subset(&self, other: &HashSet<K, S>) -> bool {
+        self.len() <= other.len() && self.iter().all(|value| other.contains(value))
+    }
+
+    pub fn is_superset(&self, other: &HashSet<K, S>) -> bool {
+        other.is_subset(self)
+    }
+
+    pub fn is_disjoint(&self, other: &HashSet<K, S>) -> bool {
+        let (smaller, bigger) = if self.len() <= other.len() {
+            (self, other)
+        } else {
+            (other, self)
+        };
+        smaller.iter().all(|value| !bigger.contains(value))
+    }
+}
+
+impl<K, S> Default for HashSet<K, S>
+where
+    K: Hash + PartialEq,
+    S: BuildHasher + Default,
+{
+    fn default() -> Self {
+        Self::with_hasher(S::default())
+    }
+}
+
+impl<K, S> Extend<K> for HashSet<K, S>
+where
+    K: Hash + PartialEq,
+    S: BuildHasher,
+{
+    fn extend<I: IntoIterator<Item = K>>(&mut self, iter: I) {
+        for value in iter {
+            self.insert(value);
+        }
+    }
+}
+
+impl<K, S> FromIterator<K> for HashSet<K, S>
+where
+    K: Hash + PartialEq,
+    S: BuildHasher + Default,
+{
+    fn from_iter<I: IntoIterator<Item = K>>(iter: I) -> Self {
+        let mut set = HashSet::default();
+        set.extend(iter);
+        set
+    }
+}
+
+impl<K, S> IntoIterator for HashSet<K, S>
+where
+    K: Hash + PartialEq,
+    S: BuildHasher,
+{
+    type Item = K;
+    type IntoIter = IntoIter<K>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter {
+            remaining: self.len(),
+            values: self.hash_table.into_iter().flatten(),
+        }
+    }
+}
+
+impl<'a, K, S> IntoIterator for &'a HashSet<K, S>
+where
+    K: Hash + PartialEq,
+    S: BuildHasher,
+{
+    type Item = &'a K;
+    type IntoIter = Iter<'a, K>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<K, S> Debug for HashSet<K, S>
+where
+    K: Hash + PartialEq + Debug,
+    S: BuildHasher,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_set().entries(self.iter()).finish()
+    }
+}
+
+impl<K, S> PartialEq for HashSet<K, S>
+where
+    K: Hash + PartialEq,
+    S: BuildHasher,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.is_subset(other)
+    }
+}
+
+impl<K, S> Eq for HashSet<K, S>
+where
+    K: Hash + Eq,
+    S: BuildHasher,
+{
+}
+
+impl<K, S> BitOr<&HashSet<K, S>> for &HashSet<K, S>
+where
+    K: Hash + PartialEq + Clone,
+    S: BuildHasher + Default,
+{
+    type Output = HashSet<K, S>;
+
+    fn bitor(self, other: &HashSet<K, S>) -> HashSet<K, S> {
+        self.union(other).cloned().collect()
+    }
+}
+
+impl<K, S> BitAnd<&HashSet<K, S>> for &HashSet<K, S>
+where
+    K: Hash + PartialEq + Clone,
+    S: BuildHasher + Default,
+{
+    type Output = HashSet<K, S>;
+
+    fn bitand(self, other: &HashSet<K, S>) -> HashSet<K, S> {
+        self.intersection(other).cloned().collect()
+    }
+}
+
+impl<K, S> Sub<&HashSet<K, S>> for &HashSet<K, S>
+where
+    K: Hash + PartialEq + Clone,
+    S: BuildHasher + Default,
+{
+    type Output = HashSet<K, S>;
+
+    fn sub(self, other: &HashSet<K, S>) -> HashSet<K, S> {
+        self.difference(other).cloned().collect()
+    }
+}
+
+impl<K, S> BitXor<&HashSet<K, S>> for &HashSet<K, S>
+where
+    K: Hash + PartialEq + Clone,
+    S: BuildHasher + Default,
+{
+    type Output = HashSet<K, S>;
+
+    fn bitxor(self, other: &HashSet<K, S>) -> HashSet<K, S> {
+        self.symmetric_difference(other).cloned().collect()
+    }
+}
+
+pub struct Iter<'a, K> {
+    values: Flatten<slice::Iter<'a, Stack<K>>>,
+    remaining: usize,
+}
+
+impl<'a, K> Iterator for Iter<'a, K> {
+    type Item = &'a K;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let value = self.values.next()?;
+        self.remaining -= 1;
+        Some(value)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<K> ExactSizeIterator for Iter<'_, K> {}
+impl<K> FusedIterator for Iter<'_, K> {}
+
+pub struct IntoIter<K> {
+    values: Flatten<vec::IntoIter<Stack<K>>>,
+    remaining: usize,
+}
+
+impl<K> Iterator for IntoIter<K> {
+    type Item = K;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let value = self.values.next()?;
+        self.remaining -= 1;
+        Some(value)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<K> ExactSizeIterator for IntoIter<K> {}
+impl<K> FusedIterator for IntoIter<K> {}
+
+pub struct Drain<K>(IntoIter<K>);
+
+impl<K> Iterator for Drain<K> {
+    type Item = K;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl<K> ExactSizeIterator for Drain<K> {}
+impl<K> FusedIterator for Drain<K> {}
+
+pub struct Intersection<'a, K, S>
+where
+    K: Hash + PartialEq,
+{
+    iter: Iter<'a, K>,
+    other: &'a HashSet<K, S>,
+}
+
+impl<'a, K, S> Iterator for Intersection<'a, K, S>
+where
+    K: Hash + PartialEq,
+    S: BuildHasher,
+{
+    type Item = &'a K;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let other = self.other;
+        self.iter.find(|value| other.contains(value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, self.iter.size_hint().1)
+    }
+}
+
+pub struct Difference<'a, K, S>
+where
+    K: Hash + PartialEq,
+{
+    iter: Iter<'a, K>,
+    other: &'a HashSet<K, S>,
+}
+
+impl<'a, K, S> Iterator for Difference<'a, K, S>
+where
+    K: Hash + PartialEq,
+    S: BuildHasher,
+{
+    type Item = &'a K;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let other = self.other;
+        self.iter.find(|value| !other.contains(value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, self.iter.size_hint().1)
+    }
+}
+
+pub struct Union<'a, K, S>(Chain<Iter<'a, K>, Difference<'a, K, S>>)
+where
+    K: Hash + PartialEq;
+
+impl<'a, K, S> Iterator for Union<'a, K, S>
+where
+    K: Hash + PartialEq,
+    S: BuildHasher,
+{
+    type Item = &'a K;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+pub struct SymmetricDifference<'a, K, S>(Chain<Difference<'a, K, S>, Difference<'a, K, S>>)
+where
+    K: Hash + PartialEq;
+
+impl<'a, K, S> Iterator for SymmetricDifference<'a, K, S>
+where
+    K: Hash + PartialEq,
+    S: BuildHasher,
+{
+    type Item = &'a K;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    use crate::chapter_5_hashmap::TryReserveError;
     use crate::chapter_5_hashset::HashSet;
+    use std::hash::{BuildHasherDefault, DefaultHasher};
 
     #[test]
     fn should_insert_and_verify() {
@@ -132,4 +611,153 @@ mod tests {
 
         assert!(!map.contains(&"key".to_string()));
     }
+
+    #[test]
+    fn should_work_with_deterministic_hasher() {
+        let mut set: HashSet<i32, BuildHasherDefault<DefaultHasher>> =
+            HashSet::with_capacity_and_hasher(100, BuildHasherDefault::default());
+
+        for i in 0..100 {
+            set.insert(i);
+        }
+
+        assert!((0..100).all(|i| set.contains(&i)));
+        assert_eq!(set.size(), 100);
+    }
+
+    #[test]
+    fn should_manage_capacity() {
+        let mut set = HashSet::with_capacity(100);
+        let capacity = set.capacity();
+
+        for i in 0..capacity {
+            set.insert(i);
+        }
+        assert_eq!(set.capacity(), capacity);
+
+        set.reserve(1000);
+        assert!(set.capacity() >= capacity + 1000);
+        assert_eq!(
+            set.try_reserve(usize::MAX),
+            Err(TryReserveError::CapacityOverflow)
+        );
+
+        for i in 10..capacity {
+            set.remove(&i);
+        }
+        set.shrink_to_fit();
+        assert_eq!(set.capacity(), 12);
+        assert!((0..10).all(|i| set.contains(&i)));
+    }
+
+    #[test]
+    fn should_shrink_after_mass_removal() {
+        let mut set = HashSet::new();
+        for i in 0..1000 {
+            set.insert(i);
+        }
+        let grown_capacity = set.capacity();
+
+        for i in 10..1000 {
+            set.remove(&i);
+        }
+
+        assert!(set.capacity() < grown_capacity / 8);
+        assert!((0..10).all(|i| set.contains(&i)));
+        assert_eq!(set.size(), 10);
+    }
+
+    #[test]
+    fn should_not_shrink_below_reserved_capacity() {
+        let mut set = HashSet::with_capacity(1000);
+        let reserved_capacity = set.capacity();
+
+        set.insert(0);
+        set.remove(&0);
+
+        assert_eq!(set.capacity(), reserved_capacity);
+        set.shrink_to_fit();
+        assert_eq!(set.capacity(), 12);
+    }
+
+    fn set_of(values: impl IntoIterator<Item = i32>) -> HashSet<i32> {
+        values.into_iter().collect()
+    }
+
+    fn sorted<'a>(values: impl Iterator<Item = &'a i32>) -> Vec<i32> {
+        let mut values = values.copied().collect::<Vec<_>>();
+        values.sort();
+        values
+    }
+
+    #[test]
+    fn should_iterate_members() {
+        let set = set_of(0..50);
+
+        assert_eq!(set.iter().len(), 50);
+        assert_eq!(sorted(set.iter()), (0..50).collect::<Vec<_>>());
+        assert_eq!(sorted((&set).into_iter()), (0..50).collect::<Vec<_>>());
+
+        let mut owned = set.into_iter().collect::<Vec<_>>();
+        owned.sort();
+        assert_eq!(owned, (0..50).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn should_drain_and_retain() {
+        let mut set = set_of(0..20);
+
+        set.retain(|value| value % 2 == 0);
+        assert_eq!(sorted(set.iter()), (0..20).step_by(2).collect::<Vec<_>>());
+
+        let mut drained = set.drain().collect::<Vec<_>>();
+        drained.sort();
+        assert_eq!(drained, (0..20).step_by(2).collect::<Vec<_>>());
+        assert!(set.is_empty());
+    }
+
+    #[test]
+    fn should_combine_sets_lazily() {
+        let a = set_of([1, 2, 3, 4]);
+        let b = set_of([3, 4, 5]);
+
+        assert_eq!(sorted(a.union(&b)), vec![1, 2, 3, 4, 5]);
+        assert_eq!(sorted(a.intersection(&b)), vec![3, 4]);
+        assert_eq!(sorted(a.difference(&b)), vec![1, 2]);
+        assert_eq!(sorted(b.difference(&a)), vec![5]);
+        assert_eq!(sorted(a.symmetric_difference(&b)), vec![1, 2, 5]);
+    }
+
+    #[test]
+    fn should_combine_sets_with_operators() {
+        let a = set_of([1, 2, 3, 4]);
+        let b = set_of([3, 4, 5]);
+
+        assert_eq!(&a | &b, set_of(1..=5));
+        assert_eq!(&a & &b, set_of([3, 4]));
+        assert_eq!(&a - &b, set_of([1, 2]));
+        assert_eq!(&a ^ &b, set_of([1, 2, 5]));
+    }
+
+    #[test]
+    fn should_compare_sets() {
+        let all = set_of(0..10);
+        let evens = set_of((0..10).step_by(2));
+        let odds = set_of((1..10).step_by(2));
+
+        assert!(evens.is_subset(&all));
+        assert!(!all.is_subset(&evens));
+        assert!(all.is_superset(&odds));
+        assert!(evens.is_disjoint(&odds));
+        assert!(!evens.is_disjoint(&all));
+        assert_eq!(&evens | &odds, all);
+        assert_eq!(all.clone(), all);
+    }
+
+    #[test]
+    fn should_format_as_set() {
+        let set = set_of([1]);
+
+        assert_eq!(format!("{set:?}"), "{1}");
+    }
 }
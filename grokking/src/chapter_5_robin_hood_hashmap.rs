@@ -0,0 +1,1018 @@
+use crate::chapter_5_hashmap::TryReserveError;
+use std::borrow::Borrow;
+use std::fmt::{self, Debug, Formatter};
+use std::hash::{BuildHasher, Hash, RandomState};
+use std::iter::{Flatten, FusedIterator};
+use std::mem;
+use std::ops::Index;
+use std::{slice, vec};
+
+const MINIMUM_SLOTS: usize = 16;
+const DEFAULT_MAX_LOAD_FACTOR: f32 = 0.875;
+
+#[derive(Clone)]
+struct Bucket<K, V> {
+    hash: u64,
+    key: K,
+    value: V,
+}
+
+/// A hash map that stores every entry inline, in a single array of slots.
+///
+/// Collisions are resolved with linear probing following the Robin Hood rule: an
+/// entry that is far away from its ideal slot steals the place of one that is
+/// closer to its own. That keeps probe sequences short and sorted, so a lookup
+/// can stop as soon as it meets an entry "richer" than the key it looks for.
+/// Removal shifts the following entries back instead of leaving tombstones.
+#[derive(Clone)]
+pub struct RobinHoodHashMap<K, V, S = RandomState>
+where
+    K: Hash + PartialEq,
+{
+    slots: Vec<Option<Bucket<K, V>>>,
+    number_of_values: usize,
+    max_load_factor: f32,
+    hash_builder: S,
+}
+
+fn create_slots<K, V>(how_many: usize) -> Vec<Option<Bucket<K, V>>> {
+    (0..how_many).map(|_| None).collect()
+}
+
+fn try_create_slots<K, V>(how_many: usize) -> Result<Vec<Option<Bucket<K, V>>>, TryReserveError> {
+    let mut slots = Vec::new();
+    slots
+        .try_reserve_exact(how_many)
+        .map_err(|_| TryReserveError::AllocError { shelves: how_many })?;
+    slots.extend((0..how_many).map(|_| None));
+    Ok(slots)
+}
+
+/// The slots needed to hold `capacity` values, or `None` when they overflow `usize`.
+fn slots_for_capacity(capacity: usize, max_load_factor: f32) -> Option<usize> {
+    let mut slots = MINIMUM_SLOTS;
+    while capacity as f32 > slots as f32 * max_load_factor {
+        slots = slots.checked_mul(2)?;
+    }
+    Some(slots)
+}
+
+/// How many values the slots hold before the table has to grow.
+fn capacity_of_slots(slots: usize, max_load_factor: f32) -> usize {
+    (slots as f32 * max_load_factor) as usize
+}
+
+impl<K, V> RobinHoodHashMap<K, V, RandomState>
+where
+    K: Hash + PartialEq,
+{
+    pub fn new() -> Self {
+        Self::with_hasher(RandomState::new())
+    }
+
+    /// # Panics
+    /// If the capacity overflows `usize`.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self::with_capacity_and_hasher(capacity, RandomState::new())
+    }
+}
+
+impl<K, V, S> RobinHoodHashMap<K, V, S>
+where
+    K: Hash + PartialEq,
+    S: BuildHasher,
+{
+    pub fn with_hasher(hash_builder: S) -> Self {
+        Self::with_capacity_and_hasher(0, hash_builder)
+    }
+
+    /// # Panics
+    /// If the capacity overflows `usize`.
+    pub fn with_capacity_and_hasher(capacity: usize, hash_builder: S) -> Self {
+        RobinHoodHashMap {
+            slots: create_slots(
+                slots_for_capacity(capacity, DEFAULT_MAX_LOAD_FACTOR).expect("capacity overflow"),
+            ),
+            number_of_values: 0,
+            max_load_factor: DEFAULT_MAX_LOAD_FACTOR,
+            hash_builder,
+        }
+    }
+
+    pub fn hasher(&self) -> &S {
+        &self.hash_builder
+    }
+
+    /// How many values the map holds before it has to grow.
+    pub fn capacity(&self) -> usize {
+        capacity_of_slots(self.slots.len(), self.max_load_factor)
+    }
+
+    /// Makes room for at least `additional` more values.
+    ///
+    /// # Panics
+    /// If the new capacity overflows `usize` or cannot be allocated.
+    pub fn reserve(&mut self, additional: usize) {
+        if let Err(error) = self.try_reserve(additional) {
+            panic!("{error}");
+        }
+    }
+
+    /// Makes room for at least `additional` more values, reporting failures instead of panicking.
+    pub fn try_reserve(&mut self, additional: usize) -> Result<(), TryReserveError> {
+        let needed_slots = self
+            .number_of_values
+            .checked_add(additional)
+            .and_then(|capacity| slots_for_capacity(capacity, self.max_load_factor))
+            .ok_or(TryReserveError::CapacityOverflow)?;
+        if needed_slots > self.slots.len() {
+            self.resize(needed_slots)?;
+        }
+        Ok(())
+    }
+
+    /// Gives back as much memory as possible while still holding every value.
+    pub fn shrink_to_fit(&mut self) {
+        let needed_slots = slots_for_capacity(self.number_of_values, self.max_load_factor)
+            .expect("Current size should fit");
+        if needed_slots < self.slots.len() {
+            // Shrinking only saves memory, so the current slots stay if they cannot allocate.
+            let _ = self.resize(needed_slots);
+        }
+    }
+
+    pub fn max_load_factor(&self) -> f32 {
+        self.max_load_factor
+    }
+
+    /// Sets how full the slots may get before the table grows.
+    ///
+    /// # Panics
+    /// If the load factor is not strictly between 0 and 1.
+    pub fn set_max_load_factor(&mut self, max_load_factor: f32) {
+        assert!(
+            max_load_factor > 0.0 && max_load_factor < 1.0,
+            "Load factor must be between 0 and 1, got {max_load_factor}"
+        );
+        self.max_load_factor = max_load_factor;
+        let needed_slots =
+            slots_for_capacity(self.number_of_values, max_load_factor).expect("capacity overflow");
+        if needed_slots > self.slots.len() {
+            self.resize(needed_slots)
+                .expect("Growing the table should be able to allocate");
+        }
+    }
+
+    fn mask(&self) -> usize {
+        self.slots.len() - 1
+    }
+
+    fn ideal_slot(&self, hash: u64) -> usize {
+        hash as usize & self.mask()
+    }
+
+    fn probe_distance(&self, slot: usize, hash: u64) -> usize {
+        slot.wrapping_sub(self.ideal_slot(hash)) & self.mask()
+    }
+
+    fn resize(&mut self, how_many_slots: usize) -> Result<(), TryReserveError> {
+        let old_slots = mem::replace(&mut self.slots, try_create_slots(how_many_slots)?);
+        for bucket in old_slots.into_iter().flatten() {
+            self.place_new_bucket(bucket);
+        }
+        Ok(())
+    }
+
+    fn maybe_grow_for_one_more(&mut self) {
+        let should_grow =
+            (self.number_of_values + 1) as f32 > self.slots.len() as f32 * self.max_load_factor;
+        if should_grow {
+            self.resize(self.slots.len() * 2)
+                .expect("Growing the table should be able to allocate");
+        }
+    }
+
+    /// Places a bucket whose key is known not to be in the table, returning its slot.
+    fn place_new_bucket(&mut self, bucket: Bucket<K, V>) -> usize {
+        let mut slot = self.ideal_slot(bucket.hash);
+        let mut distance = 0;
+        let mut carried = bucket;
+        let mut placed_at = None;
+
+        loop {
+            let existing_distance = match &self.slots[slot] {
+                None => {
+                    self.slots[slot] = Some(carried);
+                    return placed_at.unwrap_or(slot);
+                }
+                Some(existing) => self.probe_distance(slot, existing.hash),
+            };
+
+            if existing_distance < distance {
+                let existing = self.slots[slot]
+                    .as_mut()
+                    .expect("Slot was just seen occupied");
+                mem::swap(existing, &mut carried);
+                placed_at.get_or_insert(slot);
+                distance = existing_distance;
+            }
+
+            slot = (slot + 1) & self.mask();
+            distance += 1;
+        }
+    }
+
+    fn find_slot<Q>(&self, hash: u64, key_ref: &Q) -> Option<usize>
+    where
+        K: Borrow<Q>,
+        Q: PartialEq + ?Sized,
+    {
+        let mut slot = self.ideal_slot(hash);
+        let mut distance = 0;
+
+        loop {
+            let bucket = self.slots[slot].as_ref()?;
+            if self.probe_distance(slot, bucket.hash) < distance {
+                return None;
+            }
+            if bucket.hash == hash && bucket.key.borrow() == key_ref {
+                return Some(slot);
+            }
+            slot = (slot + 1) & self.mask();
+            distance += 1;
+        }
+    }
+
+    fn take_slot(&mut self, mut slot: usize) -> Bucket<K, V> {
+        let removed = self.slots[slot].take().expect("Slot should be occupied");
+        self.number_of_values -= 1;
+
+        loop {
+            let next = (slot + 1) & self.mask();
+            match &self.slots[next] {
+                Some(bucket) if self.probe_distance(next, bucket.hash) > 0 => {
+                    self.slots[slot] = self.slots[next].take();
+                    slot = next;
+                }
+                _ => return removed,
+            }
+        }
+    }
+
+    fn bucket_at_mut(&mut self, slot: usize) -> &mut Bucket<K, V> {
+        self.slots[slot].as_mut().expect("Slot should be occupied")
+    }
+
+    /// Inserts the value, returning the one previously stored under the same key.
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        match self.entry(key) {
+            Entry::Occupied(mut entry) => Some(entry.insert(value)),
+            Entry::Vacant(entry) => {
+                entry.insert(value);
+                None
+            }
+        }
+    }
+
+    pub fn entry(&mut self, key: K) -> Entry<'_, K, V, S> {
+        let hash = self.hash_builder.hash_one(&key);
+        match self.find_slot(hash, &key) {
+            Some(slot) => Entry::Occupied(OccupiedEntry {
+                map: self,
+                slot,
+                key,
+            }),
+            None => Entry::Vacant(VacantEntry {
+                map: self,
+                hash,
+                key,
+            }),
+        }
+    }
+
+    pub fn get<Q>(&self, key_ref: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + PartialEq + ?Sized,
+    {
+        let slot = self.find_slot(self.hash_builder.hash_one(key_ref), key_ref)?;
+        self.slots[slot].as_ref().map(|bucket| &bucket.value)
+    }
+
+    pub fn get_mut<Q>(&mut self, key_ref: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + PartialEq + ?Sized,
+    {
+        let slot = self.find_slot(self.hash_builder.hash_one(key_ref), key_ref)?;
+        Some(&mut self.bucket_at_mut(slot).value)
+    }
+
+    pub fn contains_key<Q>(&self, key_ref: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + PartialEq + ?Sized,
+    {
+        self.get(key_ref).is_some()
+    }
+
+    pub fn remove<Q>(&mut self, key_ref: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + PartialEq + ?Sized,
+    {
+        let slot = self.find_slot(self.hash_builder.hash_one(key_ref), key_ref)?;
+        Some(self.take_slot(slot).value)
+    }
+
+    pub fn size(&self) -> u32 {
+        self.number_of_values as u32
+    }
+
+    pub fn len(&self) -> usize {
+        self.number_of_values
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.number_of_values == 0
+    }
+
+    pub fn clear(&mut self) {
+        self.drain();
+    }
+
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        Iter {
+            buckets: self.slots.iter().flatten(),
+            remaining: self.len(),
+        }
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_, K, V> {
+        IterMut {
+            remaining: self.len(),
+            buckets: self.slots.iter_mut().flatten(),
+        }
+    }
+
+    pub fn keys(&self) -> Keys<'_, K, V> {
+        Keys(self.iter())
+    }
+
+    pub fn values(&self) -> Values<'_, K, V> {
+        Values(self.iter())
+    }
+
+    pub fn values_mut(&mut self) -> ValuesMut<'_, K, V> {
+        ValuesMut(self.iter_mut())
+    }
+
+    /// Empties the map, yielding every key and value it held.
+    pub fn drain(&mut self) -> Drain<K, V> {
+        let how_many_slots = self.slots.len();
+        let slots = mem::replace(&mut self.slots, create_slots(how_many_slots));
+        let remaining = mem::replace(&mut self.number_of_values, 0);
+        Drain(IntoIter {
+            buckets: slots.into_iter().flatten(),
+            remaining,
+        })
+    }
+
+    /// Keeps only the entries for which `keep` returns true.
+    pub fn retain<F: FnMut(&K, &mut V) -> bool>(&mut self, mut keep: F) {
+        // Shifting entries back while scanning could move an entry over the scan
+        // position, so the kept entries are placed again in a fresh table instead.
+        let how_many_slots = self.slots.len();
+        let slots = mem::replace(&mut self.slots, create_slots(how_many_slots));
+        self.number_of_values = 0;
+        for mut bucket in slots.into_iter().flatten() {
+            if keep(&bucket.key, &mut bucket.value) {
+                self.place_new_bucket(bucket);
+                self.number_of_values += 1;
+            }
+        }
+    }
+}
+
+impl<K, V, S> Default for RobinHoodHashMap<K, V, S>
+where
+    K: Hash + PartialEq,
+    S: BuildHasher + Default,
+{
+    fn default() -> Self {
+        Self::with_hasher(S::default())
+    }
+}
+
+impl<K, V, S> Extend<(K, V)> for RobinHoodHashMap<K, V, S>
+where
+    K: Hash + PartialEq,
+    S: BuildHasher,
+{
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        for (key, value) in iter {
+            self.insert(key, value);
+        }
+    }
+}
+
+impl<K, V, S> FromIterator<(K, V)> for RobinHoodHashMap<K, V, S>
+where
+    K: Hash + PartialEq,
+    S: BuildHasher + Default,
+{
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut map = RobinHoodHashMap::default();
+        map.extend(iter);
+        map
+    }
+}
+
+impl<K, V, S> IntoIterator for RobinHoodHashMap<K, V, S>
+where
+    K: Hash + PartialEq,
+    S: BuildHasher,
+{
+    type Item = (K, V);
+    type IntoIter = IntoIter<K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter {
+            remaining: self.len(),
+            buckets: self.slots.into_iter().flatten(),
+        }
+    }
+}
+
+impl<'a, K, V, S> IntoIterator for &'a RobinHoodHashMap<K, V, S>
+where
+    K: Hash + PartialEq,
+    S: BuildHasher,
+{
+    type Item = (&'a K, &'a V);
+    type IntoIter = Iter<'a, K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a, K, V, S> IntoIterator for &'a mut RobinHoodHashMap<K, V, S>
+where
+    K: Hash + PartialEq,
+    S: BuildHasher,
+{
+    type Item = (&'a K, &'a mut V);
+    type IntoIter = IterMut<'a, K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+impl<K, Q, V, S> Index<&Q> for RobinHoodHashMap<K, V, S>
+where
+    K: Hash + PartialEq + Borrow<Q>,
+    Q: Hash + PartialEq + ?Sized,
+    S: BuildHasher,
+{
+    type Output = V;
+
+    fn index(&self, key: &Q) -> &V {
+        self.get(key).expect("Key not found in RobinHoodHashMap")
+    }
+}
+
+impl<K, V, S> Debug for RobinHoodHashMap<K, V, S>
+where
+    K: Hash + PartialEq + Debug,
+    V: Debug,
+    S: BuildHasher,
+{
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<K, V, S> PartialEq for RobinHoodHashMap<K, V, S>
+where
+    K: Hash + PartialEq,
+    V: PartialEq,
+    S: BuildHasher,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len()
+            && self
+                .iter()
+                .all(|(key, value)| other.get(key) == Some(value))
+    }
+}
+
+impl<K, V, S> Eq for RobinHoodHashMap<K, V, S>
+where
+    K: Hash + Eq,
+    V: Eq,
+    S: BuildHasher,
+{
+}
+
+pub enum Entry<'a, K, V, S = RandomState>
+where
+    K: Hash + PartialEq,
+{
+    Occupied(OccupiedEntry<'a, K, V, S>),
+    Vacant(VacantEntry<'a, K, V, S>),
+}
+
+impl<'a, K, V, S> Entry<'a, K, V, S>
+where
+    K: Hash + PartialEq,
+    S: BuildHasher,
+{
+    pub fn key(&self) -> &K {
+        match self {
+            Entry::Occupied(entry) => entry.key(),
+            Entry::Vacant(entry) => entry.key(),
+        }
+    }
+
+    pub fn or_insert(self, default: V) -> &'a mut V {
+        self.or_insert_with(|| default)
+    }
+
+    pub fn or_insert_with<F: FnOnce() -> V>(self, default: F) -> &'a mut V {
+        match self {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(default()),
+        }
+    }
+
+    pub fn or_default(self) -> &'a mut V
+    where
+        V: Default,
+    {
+        self.or_insert_with(V::default)
+    }
+
+    pub fn and_modify<F: FnOnce(&mut V)>(mut self, modify: F) -> Self {
+        if let Entry::Occupied(entry) = &mut self {
+            modify(entry.get_mut());
+        }
+        self
+    }
+}
+
+/// An entry whose key is already stored in the map.
+pub struct OccupiedEntry<'a, K, V, S = RandomState>
+where
+    K: Hash + PartialEq,
+{
+    map: &'a mut RobinHoodHashMap<K, V, S>,
+    slot: usize,
+    key: K,
+}
+
+impl<'a, K, V, S> OccupiedEntry<'a, K, V, S>
+where
+    K: Hash + PartialEq,
+    S: BuildHasher,
+{
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+    pub fn get(&self) -> &V {
+        &self.map.slots[self.slot]
+            .as_ref()
+            .expect("Occupied entry should be in the map")
+            .value
+    }
+
+    pub fn get_mut(&mut self) -> &mut V {
+        &mut self.map.bucket_at_mut(self.slot).value
+    }
+
+    pub fn into_mut(self) -> &'a mut V {
+        &mut self.map.bucket_at_mut(self.slot).value
+    }
+
+    pub fn insert(&mut self, value: V) -> V {
+        mem::replace(self.get_mut(), value)
+    }
+
+    pub fn remove(self) -> V {
+        self.map.take_slot(self.slot).value
+    }
+}
+
+/// An entry whose key is not stored in the map yet.
+pub struct VacantEntry<'a, K, V, S = RandomState>
+where
+    K: Hash + PartialEq,
+{
+    map: &'a mut RobinHoodHashMap<K, V, S>,
+    hash: u64,
+    key: K,
+}
+
+impl<'a, K, V, S> VacantEntry<'a, K, V, S>
+where
+    K: Hash + PartialEq,
+    S: BuildHasher,
+{
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+    pub fn into_key(self) -> K {
+        self.key
+    }
+
+    pub fn insert(self, value: V) -> &'a mut V {
+        let map = self.map;
+        map.maybe_grow_for_one_more();
+        let slot = map.place_new_bucket(Bucket {
+            hash: self.hash,
+            key: self.key,
+            value,
+        });
+        map.number_of_values += 1;
+        &mut map.bucket_at_mut(slot).value
+    }
+}
+
+pub struct Iter<'a, K, V> {
+    buckets: Flatten<slice::Iter<'a, Option<Bucket<K, V>>>>,
+    remaining: usize,
+}
+
+impl<'a, K, V> Iterator for Iter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let bucket = self.buckets.next()?;
+        self.remaining -= 1;
+        Some((&bucket.key, &bucket.value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<K, V> ExactSizeIterator for Iter<'_, K, V> {}
+impl<K, V> FusedIterator for Iter<'_, K, V> {}
+
+pub struct IterMut<'a, K, V> {
+    buckets: Flatten<slice::IterMut<'a, Option<Bucket<K, V>>>>,
+    remaining: usize,
+}
+
+impl<'a, K, V> Iterator for IterMut<'a, K, V> {
+    type Item = (&'a K, &'a mut V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let bucket = self.buckets.next()?;
+        self.remaining -= 1;
+        Some((&bucket.key, &mut bucket.value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<K, V> ExactSizeIterator for IterMut<'_, K, V> {}
+impl<K, V> FusedIterator for IterMut<'_, K, V> {}
+
+pub struct IntoIter<K, V> {
+    buckets: Flatten<vec::IntoIter<Option<Bucket<K, V>>>>,
+    remaining: usize,
+}
+
+impl<K, V> Iterator for IntoIter<K, V> {
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let bucket = self.buckets.next()?;
+        self.remaining -= 1;
+        Some((bucket.key, bucket.value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<K, V> ExactSizeIterator for IntoIter<K, V> {}
+impl<K, V> FusedIterator for IntoIter<K, V> {}
+
+pub struct Drain<K, V>(IntoIter<K, V>);
+
+impl<K, V> Iterator for Drain<K, V> {
+    type Item = (K, V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl<K, V> ExactSizeIterator for Drain<K, V> {}
+impl<K, V> FusedIterator for Drain<K, V> {}
+
+pub struct Keys<'a, K, V>(Iter<'a, K, V>);
+
+impl<'a, K, V> Iterator for Keys<'a, K, V> {
+    type Item = &'a K;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(|(key, _)| key)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl<K, V> ExactSizeIterator for Keys<'_, K, V> {}
+impl<K, V> FusedIterator for Keys<'_, K, V> {}
+
+pub struct Values<'a, K, V>(Iter<'a, K, V>);
+
+impl<'a, K, V> Iterator for Values<'a, K, V> {
+    type Item = &'a V;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(|(_, value)| value)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl<K, V> ExactSizeIterator for Values<'_, K, V> {}
+impl<K, V> FusedIterator for Values<'_, K, V> {}
+
+pub struct ValuesMut<'a, K, V>(IterMut<'a, K, V>);
+
+impl<'a, K, V> Iterator for ValuesMut<'a, K, V> {
+    type Item = &'a mut V;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(|(_, value)| value)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl<K, V> ExactSizeIterator for ValuesMut<'_, K, V> {}
+impl<K, V> FusedIterator for ValuesMut<'_, K, V> {}
+
+#[cfg(test)]
+mod tests {
+    use crate::chapter_5_hashmap::TryReserveError;
+    use crate::chapter_5_robin_hood_hashmap::RobinHoodHashMap;
+    use std::hash::{BuildHasherDefault, Hasher};
+
+    /// Sends every key to the same slot, so every operation has to probe.
+    #[derive(Default)]
+    struct CollidingHasher;
+
+    impl Hasher for CollidingHasher {
+        fn finish(&self) -> u64 {
+            7
+        }
+
+        fn write(&mut self, _bytes: &[u8]) {}
+    }
+
+    type CollidingMap = RobinHoodHashMap<i32, i32, BuildHasherDefault<CollidingHasher>>;
+
+    #[test]
+    fn should_insert_and_get() {
+        let mut map = RobinHoodHashMap::new();
+
+        assert_eq!(map.insert("key", 1), None);
+        assert_eq!(map.insert("key", 2), Some(1));
+
+        assert_eq!(map.get(&"key"), Some(&2));
+        assert_eq!(map.len(), 1);
+    }
+
+    #[test]
+    fn should_remove() {
+        let mut map = RobinHoodHashMap::new();
+
+        map.insert("key", 1);
+
+        assert_eq!(map.remove(&"key"), Some(1));
+        assert_eq!(map.remove(&"key"), None);
+        assert!(map.is_empty());
+    }
+
+    #[test]
+    fn should_insert_get_and_remove_thousand_elements() {
+        let mut map = RobinHoodHashMap::new();
+
+        for i in 0..1000 {
+            map.insert(format!("key{i}"), i);
+        }
+        for i in 0..1000 {
+            assert_eq!(map.get(format!("key{i}").as_str()), Some(&i));
+        }
+        for i in (0..1000).step_by(2) {
+            assert_eq!(map.remove(format!("key{i}").as_str()), Some(i));
+        }
+
+        assert_eq!(map.size(), 500);
+        for i in 0..1000 {
+            assert_eq!(map.contains_key(format!("key{i}").as_str()), i % 2 == 1);
+        }
+    }
+
+    #[test]
+    fn should_shift_back_colliding_entries_on_remove() {
+        let mut map = CollidingMap::default();
+
+        for i in 0..10 {
+            map.insert(i, i * 10);
+        }
+        assert_eq!(map.remove(&3), Some(30));
+        assert_eq!(map.remove(&0), Some(0));
+        assert_eq!(map.remove(&9), Some(90));
+
+        for i in [1, 2, 4, 5, 6, 7, 8] {
+            assert_eq!(map.get(&i), Some(&(i * 10)));
+        }
+        for i in [0, 3, 9, 10] {
+            assert_eq!(map.get(&i), None);
+        }
+        // No tombstones: every remaining entry sits right after the previous one.
+        let occupied = map.slots.iter().filter(|slot| slot.is_some()).count();
+        assert_eq!(occupied, 7);
+        assert!(map.slots[7..14].iter().all(|slot| slot.is_some()));
+    }
+
+    #[test]
+    fn should_wrap_probe_sequence_around_the_table() {
+        let mut map = CollidingMap::default();
+
+        for i in 0..13 {
+            map.insert(i, i);
+        }
+        for i in 0..13 {
+            assert_eq!(map.get(&i), Some(&i));
+        }
+        for i in 0..13 {
+            assert_eq!(map.remove(&i), Some(i));
+        }
+
+        assert!(map.is_empty());
+    }
+
+    #[test]
+    fn should_grow_according_to_load_factor() {
+        let mut map = RobinHoodHashMap::new();
+        map.set_max_load_factor(0.5);
+
+        for i in 0..8 {
+            map.insert(i, i);
+        }
+        assert_eq!(map.slots.len(), 16);
+
+        map.insert(8, 8);
+        assert_eq!(map.slots.len(), 32);
+        assert!((0..9).all(|i| map[&i] == i));
+    }
+
+    #[test]
+    fn should_grow_when_load_factor_is_lowered() {
+        let mut map: RobinHoodHashMap<i32, i32> = (0..14).map(|i| (i, i)).collect();
+        assert_eq!(map.slots.len(), 16);
+
+        map.set_max_load_factor(0.25);
+
+        assert_eq!(map.slots.len(), 64);
+        assert!((0..14).all(|i| map[&i] == i));
+    }
+
+    #[test]
+    fn should_not_grow_before_reaching_capacity() {
+        let mut map = RobinHoodHashMap::with_capacity(100);
+        let capacity = map.capacity();
+
+        for i in 0..capacity {
+            map.insert(i, i);
+        }
+
+        assert!(capacity >= 100);
+        assert_eq!(map.capacity(), capacity);
+        map.insert(capacity, capacity);
+        assert!(map.capacity() > capacity);
+    }
+
+    #[test]
+    fn should_reserve_and_shrink_to_fit() {
+        let mut map: RobinHoodHashMap<i32, i32> = (0..10).map(|i| (i, i)).collect();
+
+        map.reserve(1000);
+        assert!(map.capacity() >= 1010);
+
+        map.retain(|key, _| *key < 3);
+        map.shrink_to_fit();
+        assert_eq!(map.slots.len(), 16);
+        assert_eq!(map.capacity(), 14);
+        assert!((0..3).all(|i| map[&i] == i));
+    }
+
+    #[test]
+    fn should_report_capacity_overflow() {
+        let mut map: RobinHoodHashMap<i32, i32> = (0..10).map(|i| (i, i)).collect();
+
+        assert_eq!(
+            map.try_reserve(usize::MAX),
+            Err(TryReserveError::CapacityOverflow)
+        );
+        assert_eq!(map.try_reserve(100), Ok(()));
+        assert!(map.capacity() >= 110);
+        assert!((0..10).all(|i| map[&i] == i));
+    }
+
+    #[test]
+    #[should_panic(expected = "capacity overflow")]
+    fn should_panic_when_reserving_too_much() {
+        let mut map: RobinHoodHashMap<i32, i32> = (0..10).map(|i| (i, i)).collect();
+
+        map.reserve(usize::MAX);
+    }
+
+    #[test]
+    #[should_panic]
+    fn should_reject_full_load_factor() {
+        let mut map: RobinHoodHashMap<i32, i32> = RobinHoodHashMap::new();
+
+        map.set_max_load_factor(1.0);
+    }
+
+    #[test]
+    fn should_count_words_with_entry() {
+        let mut map = RobinHoodHashMap::new();
+
+        for word in "a b a c a b".split(' ') {
+            map.entry(word).and_modify(|count| *count += 1).or_insert(1);
+        }
+
+        assert_eq!(map["a"], 3);
+        assert_eq!(map["b"], 2);
+        assert_eq!(map["c"], 1);
+    }
+
+    #[test]
+    fn should_return_reference_to_inserted_value_despite_displacement() {
+        let mut map = CollidingMap::default();
+
+        for i in 0..10 {
+            *map.entry(i).or_default() += i + 1;
+        }
+
+        assert!((0..10).all(|i| map[&i] == i + 1));
+    }
+
+    #[test]
+    fn should_retain_drain_and_iterate() {
+        let mut map: RobinHoodHashMap<i32, i32> = (0..100).map(|i| (i, i)).collect();
+
+        map.retain(|key, _| key % 3 == 0);
+        for (_, value) in map.iter_mut() {
+            *value *= 2;
+        }
+        let mut keys = map.keys().copied().collect::<Vec<_>>();
+        keys.sort();
+
+        assert_eq!(keys, (0..100).step_by(3).collect::<Vec<_>>());
+        assert_eq!(
+            map.values().sum::<i32>(),
+            2 * (0..100).step_by(3).sum::<i32>()
+        );
+        assert_eq!(map.drain().len(), 34);
+        assert!(map.is_empty());
+    }
+
+    #[test]
+    fn should_clone_compare_and_format() {
+        let map: RobinHoodHashMap<&str, i32> = [("key", 1)].into_iter().collect();
+        let mut cloned = map.clone();
+
+        assert_eq!(map, cloned);
+        assert_eq!(format!("{map:?}"), r#"{"key": 1}"#);
+        cloned.insert("other", 2);
+        assert_ne!(map, cloned);
+    }
+}
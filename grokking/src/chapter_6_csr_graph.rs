@@ -0,0 +1,440 @@
+use crate::chapter_6_graph::{
+    Distance, Graph, GraphView, Leg, Path, ShortestPathTree, Vertex, VertexId, Weight, Weighted,
+    shortest_path,
+};
+use crate::chapter_6_traversal::{
+    BreadthFirst, DfsEvent, breadth_first_search, depth_first_search, depth_first_visit,
+};
+use crate::chapter_9_bellman_ford_algorithm::{NegativeCycle, bellman_ford_tree};
+use crate::chapter_9_dijkstra_algorithm::{DijkstraAlgorithm, Search, astar};
+use crate::chapter_9_yen_k_shortest_paths::k_shortest_paths;
+use std::ops::ControlFlow;
+use std::sync::OnceLock;
+
+/// A graph that can no longer change, packed in compressed sparse row form: the edges of
+/// every vertex sit next to each other in a single array, so walking them allocates
+/// nothing and reads memory in order. Built with [`Graph::freeze`].
+pub struct CsrGraph<T, E = Weight> {
+    /// Sorted by id, so that vertices are found by binary search however sparse the ids.
+    vertices: Vec<Vertex<T>>,
+    /// Where the legs of each vertex start in `legs`, with one more entry marking the end
+    /// of the last vertex.
+    offsets: Vec<usize>,
+    legs: Vec<Leg<E>>,
+    directed: bool,
+    /// Whether some edge has a negative weight, kept from the graph when it already knew.
+    negative_weights: OnceLock<bool>,
+    /// The edges reaching each vertex, gathered on the first search that follows edges
+    /// backwards.
+    incoming: OnceLock<IncomingLegs>,
+}
+
+/// The edges reaching each vertex as `(from, weight)`, laid out like the legs.
+struct IncomingLegs {
+    offsets: Vec<usize>,
+    legs: Vec<(VertexId, Weight)>,
+}
+
+impl<T, E> From<Graph<T, E>> for CsrGraph<T, E> {
+    fn from(mut graph: Graph<T, E>) -> Self {
+        let directed = graph.is_directed();
+        let mut vertices = graph
+            .vertices
+            .drain()
+            .map(|(_, vertex)| vertex)
+            .collect::<Vec<_>>();
+        vertices.sort_by_key(Vertex::get_id);
+
+        let mut offsets = Vec::with_capacity(vertices.len() + 1);
+        let mut legs = Vec::with_capacity(graph.edge_count());
+        for vertex in &vertices {
+            offsets.push(legs.len());
+            legs.extend(graph.edges.remove(&vertex.id).into_iter().flatten());
+        }
+        offsets.push(legs.len());
+
+        CsrGraph {
+            vertices,
+            offsets,
+            legs,
+            directed,
+            negative_weights: graph.negative_weights,
+            incoming: OnceLock::new(),
+        }
+    }
+}
+
+impl<T, E> CsrGraph<T, E> {
+    pub fn is_directed(&self) -> bool {
+        self.directed
+    }
+
+    pub fn vertex_count(&self) -> usize {
+        self.vertices.len()
+    }
+
+    /// Counts the edges, where an undirected edge counts once for each direction.
+    pub fn edge_count(&self) -> usize {
+        self.legs.len()
+    }
+
+    /// The ids of every vertex, in the order they were added.
+    pub fn vertex_ids(&self) -> Vec<VertexId> {
+        self.vertices.iter().map(Vertex::get_id).collect()
+    }
+
+    pub fn contains_vertex(&self, vertex_id: &VertexId) -> bool {
+        self.position(vertex_id).is_some()
+    }
+
+    pub fn get_vertex(&self, vertex_id: &VertexId) -> Option<&Vertex<T>> {
+        Some(&self.vertices[self.position(vertex_id)?])
+    }
+
+    /// The edges leaving the vertex, in the order they were attached.
+    pub fn neighbors(&self, vertex_id: &VertexId) -> &[Leg<E>] {
+        match self.position(vertex_id) {
+            Some(position) => &self.legs[self.offsets[position]..self.offsets[position + 1]],
+            None => &[],
+        }
+    }
+
+    pub fn get_edge(&self, from: &VertexId, to: &VertexId) -> Option<&E> {
+        self.neighbors(from)
+            .iter()
+            .find(|leg| leg.get_to_id() == *to)
+            .map(Leg::get_edge)
+    }
+
+    /// The vertices reachable from the start in depth first order, see [`depth_first_search`].
+    pub fn depth_search_iterator(&self, start: &VertexId) -> impl Iterator<Item = &Vertex<T>> {
+        depth_first_search(self, start)
+    }
+
+    /// The vertices reachable from the start, the closest ones first.
+    pub fn breath_search_iterator(&self, start: &VertexId) -> impl Iterator<Item = &Vertex<T>> {
+        breadth_first_search(self, start).map(|step| step.vertex)
+    }
+
+    /// The vertices reachable from the start with their depth and parent, see
+    /// [`breadth_first_search`].
+    pub fn breadth_first_search(&self, start: &VertexId) -> BreadthFirst<'_, Self> {
+        breadth_first_search(self, start)
+    }
+
+    /// Searches depth first from each of the roots, handing every event to the visitor
+    /// until it breaks, see [`depth_first_visit`].
+    pub fn depth_first_visit<B>(
+        &self,
+        roots: impl IntoIterator<Item = VertexId>,
+        visitor: impl FnMut(DfsEvent) -> ControlFlow<B>,
+    ) -> ControlFlow<B> {
+        depth_first_visit(self, roots, visitor)
+    }
+
+    fn position(&self, vertex_id: &VertexId) -> Option<usize> {
+        self.vertices
+            .binary_search_by_key(vertex_id, Vertex::get_id)
+            .ok()
+    }
+}
+
+impl<T, E: Weighted> CsrGraph<T, E> {
+    pub fn has_negative_weights(&self) -> bool {
+        GraphView::has_negative_weights(self)
+    }
+
+    /// Finds the cheapest path between two vertices, see [`shortest_path`].
+    pub fn find_shortest_path(
+        &self,
+        departure: VertexId,
+        destination: VertexId,
+    ) -> Result<Option<Path<'_, T>>, NegativeCycle> {
+        shortest_path(self, departure, destination)
+    }
+
+    /// Finds the cheapest path between two vertices with A*, see [`astar`].
+    pub fn find_path_astar(
+        &self,
+        departure: VertexId,
+        destination: VertexId,
+        heuristic: impl Fn(&T, &T) -> Distance,
+    ) -> Search<'_, T> {
+        astar(self, departure, destination, heuristic)
+    }
+
+    /// Up to `k` loopless paths between two vertices, the cheapest first, see
+    /// [`k_shortest_paths`].
+    pub fn k_shortest_paths(
+        &self,
+        departure: VertexId,
+        destination: VertexId,
+        k: usize,
+    ) -> Result<Vec<Path<'_, T>>, NegativeCycle> {
+        k_shortest_paths(self, departure, destination, k)
+    }
+
+    /// Finds the cheapest paths from the departure to every vertex it can reach, see
+    /// [`Graph::shortest_path_tree`].
+    pub fn shortest_path_tree(
+        &self,
+        departure: VertexId,
+    ) -> Result<ShortestPathTree<'_, T, E>, NegativeCycle> {
+        if self.has_negative_weights() {
+            bellman_ford_tree(self, departure)
+        } else {
+            Ok(DijkstraAlgorithm::from_departure(self, departure).into_shortest_path_tree())
+        }
+    }
+
+    fn gather_incoming(&self) -> IncomingLegs {
+        let mut incoming = Vec::with_capacity(self.legs.len());
+        for (position, vertex) in self.vertices.iter().enumerate() {
+            for leg in &self.legs[self.offsets[position]..self.offsets[position + 1]] {
+                incoming.push((leg.get_to_id(), vertex.id, leg.get_edge().weight()));
+            }
+        }
+        // Stable, so the edges reaching a vertex keep the order of the vertices they leave.
+        incoming.sort_by_key(|(to, _, _)| *to);
+
+        let mut offsets = self
+            .vertices
+            .iter()
+            .map(|vertex| incoming.partition_point(|(to, _, _)| *to < vertex.id))
+            .collect::<Vec<_>>();
+        offsets.push(incoming.len());
+        IncomingLegs {
+            offsets,
+            legs: incoming
+                .into_iter()
+                .map(|(_, from, weight)| (from, weight))
+                .collect(),
+        }
+    }
+}
+
+impl<T, E> GraphView for CsrGraph<T, E> {
+    type Item = T;
+    type Edge = E;
+
+    fn is_directed(&self) -> bool {
+        self.directed
+    }
+
+    fn vertex_count(&self) -> usize {
+        self.vertices.len()
+    }
+
+    fn vertex_ids(&self) -> Vec<VertexId> {
+        CsrGraph::vertex_ids(self)
+    }
+
+    fn vertex(&self, vertex_id: &VertexId) -> Option<&Vertex<T>> {
+        self.get_vertex(vertex_id)
+    }
+
+    fn legs(&self, vertex_id: &VertexId) -> &[Leg<E>] {
+        self.neighbors(vertex_id)
+    }
+
+    fn has_negative_weights(&self) -> bool
+    where
+        E: Weighted,
+    {
+        *self
+            .negative_weights
+            .get_or_init(|| self.legs.iter().any(|leg| leg.get_edge().weight() < 0))
+    }
+
+    fn incoming_weights(&self, vertex_id: &VertexId) -> Option<&[(VertexId, Weight)]>
+    where
+        E: Weighted,
+    {
+        if !self.directed {
+            return None;
+        }
+        let incoming = self.incoming.get_or_init(|| self.gather_incoming());
+        Some(match self.position(vertex_id) {
+            Some(position) => {
+                &incoming.legs[incoming.offsets[position]..incoming.offsets[position + 1]]
+            }
+            None => &[],
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::chapter_6_graph::{Graph, Weight};
+    use crate::chapter_9_all_pairs_shortest_paths::johnson;
+
+    fn sample() -> Graph<&'static str> {
+        let mut graph = Graph::new();
+        let start = graph.add_vertex("Start");
+        let a = graph.add_vertex("A");
+        let gone = graph.add_vertex("Gone");
+        let b = graph.add_vertex("B");
+        let finish = graph.add_vertex("Finish");
+
+        graph.attach_weighted(&start, &a, 6);
+        graph.attach_weighted(&start, &b, 2);
+        graph.attach_weighted(&a, &finish, 1);
+        graph.attach_weighted(&b, &a, 3);
+        graph.attach_weighted(&b, &finish, 5);
+        graph.attach_weighted(&gone, &finish, 1);
+        graph.remove_vertex(&gone);
+        graph
+    }
+
+    #[test]
+    fn should_keep_vertices_and_edges_when_frozen() {
+        let graph = sample();
+        let ids = graph.vertex_ids();
+        let edges = ids
+            .iter()
+            .map(|id| {
+                graph
+                    .get_vertex(id)
+                    .unwrap()
+                    .get_neighbors()
+                    .iter()
+                    .map(|neighbor| (neighbor.get_id(), *neighbor.edge))
+                    .collect::<Vec<_>>()
+            })
+            .collect::<Vec<_>>();
+
+        let frozen = graph.freeze();
+
+        assert!(frozen.is_directed());
+        assert_eq!(frozen.vertex_ids(), ids);
+        assert_eq!(frozen.vertex_count(), 4);
+        assert_eq!(frozen.edge_count(), 5);
+        for (id, legs) in ids.iter().zip(edges) {
+            let frozen_legs = frozen
+                .neighbors(id)
+                .iter()
+                .map(|leg| (leg.get_to_id(), *leg.get_edge()))
+                .collect::<Vec<_>>();
+            assert_eq!(frozen_legs, legs);
+        }
+        assert_eq!(frozen.get_vertex(&ids[3]).unwrap().get_item(), &"Finish");
+        assert_eq!(frozen.get_edge(&ids[2], &ids[1]), Some(&3));
+    }
+
+    #[test]
+    fn should_not_find_removed_vertices() {
+        let mut graph = Graph::new();
+        let a = graph.add_vertex("A");
+        let gone = graph.add_vertex("Gone");
+        let b = graph.add_vertex("B");
+        let last = graph.add_vertex("Last");
+        graph.attach(&a, &gone);
+        graph.attach(&a, &b);
+        graph.remove_vertex(&gone);
+        graph.remove_vertex(&last);
+
+        let frozen = graph.freeze();
+
+        for removed in [gone, last] {
+            assert!(!frozen.contains_vertex(&removed));
+            assert!(frozen.get_vertex(&removed).is_none());
+            assert!(frozen.neighbors(&removed).is_empty());
+        }
+        assert_eq!(frozen.vertex_ids(), vec![a, b]);
+        assert_eq!(frozen.neighbors(&a).len(), 1);
+        assert_eq!(frozen.get_edge(&a, &b), Some(&1));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn should_freeze_graphs_with_sparse_ids() {
+        let json = r#"{"directed":true,"vertices":[
+            {"id":3,"item":"A","edges":[{"to":4000000000000,"edge":2}]},
+            {"id":4000000000000,"item":"B","edges":[]}
+        ]}"#;
+        let graph = serde_json::from_str::<Graph<String>>(json).unwrap();
+        let ids = graph.vertex_ids();
+
+        let frozen = graph.freeze();
+
+        assert_eq!(frozen.vertex_ids(), ids);
+        assert_eq!(frozen.get_vertex(&ids[1]).unwrap().get_item(), "B");
+        assert_eq!(frozen.get_edge(&ids[0], &ids[1]), Some(&2));
+        assert!(frozen.neighbors(&ids[1]).is_empty());
+        assert_eq!(
+            frozen
+                .find_shortest_path(ids[0], ids[1])
+                .unwrap()
+                .unwrap()
+                .distance,
+            2
+        );
+    }
+
+    #[test]
+    fn should_search_like_the_graph_it_came_from() {
+        let graph = sample();
+        let ids = graph.vertex_ids();
+        let (start, finish) = (ids[0], ids[3]);
+        let expected_order = graph
+            .breath_search_iterator(&start)
+            .map(|get_vertex| *get_vertex.get_item())
+            .collect::<Vec<_>>();
+        let expected_path = graph
+            .find_shortest_path(start, finish)
+            .unwrap()
+            .unwrap()
+            .waypoints
+            .iter()
+            .map(|vertex| vertex.id)
+            .collect::<Vec<_>>();
+        let expected_distances = johnson(&graph).unwrap();
+
+        let frozen = graph.freeze();
+
+        let order = frozen
+            .breath_search_iterator(&start)
+            .map(|vertex| *vertex.get_item())
+            .collect::<Vec<_>>();
+        assert_eq!(order, expected_order);
+        let path = frozen.find_shortest_path(start, finish).unwrap().unwrap();
+        assert_eq!(path.distance, 6);
+        assert_eq!(
+            path.waypoints
+                .iter()
+                .map(|vertex| vertex.id)
+                .collect::<Vec<_>>(),
+            expected_path
+        );
+        assert_eq!(johnson(&frozen).unwrap(), expected_distances);
+        let tree = frozen.shortest_path_tree(start).unwrap();
+        assert_eq!(tree.distance_to(&ids[1]), Some(5));
+        let search = frozen.find_path_astar(start, finish, |_, _| 0);
+        assert_eq!(search.path.unwrap().distance, 6);
+    }
+
+    #[test]
+    fn should_run_bellman_ford_over_negative_weights() {
+        let mut graph = Graph::<_, Weight>::undirected();
+        let a = graph.add_vertex('a');
+        let b = graph.add_vertex('b');
+        let mut frozen_graph = Graph::new();
+        let c = frozen_graph.add_vertex('c');
+        let d = frozen_graph.add_vertex('d');
+        let e = frozen_graph.add_vertex('e');
+        graph.attach_weighted(&a, &b, 1);
+        frozen_graph.attach_weighted(&c, &d, 4);
+        frozen_graph.attach_weighted(&c, &e, 1);
+        frozen_graph.attach_weighted(&e, &d, -2);
+
+        let undirected = graph.freeze();
+        let frozen = frozen_graph.freeze();
+
+        assert!(!undirected.is_directed());
+        assert_eq!(undirected.edge_count(), 2);
+        assert!(frozen.has_negative_weights());
+        let path = frozen.find_shortest_path(c, d).unwrap().unwrap();
+        assert_eq!(path.distance, -1);
+        assert_eq!(path.waypoints.len(), 3);
+    }
+}
@@ -1,19 +1,231 @@
-use crate::chapter_5_hashset::HashSet;
-use crate::chapter_9_dijkstra_algorithm::DijkstraAlgorithm;
-use std::collections::{HashMap, VecDeque};
+use crate::chapter_6_csr_graph::CsrGraph;
+use crate::chapter_6_max_flow::{MaximumFlow, bipartite_matching, max_flow};
+use crate::chapter_6_strongly_connected_components::{
+    Condensation, condensation, strongly_connected_components,
+};
+use crate::chapter_6_topological_sort::{Cycle, topological_order};
+use crate::chapter_6_traversal::{
+    BreadthFirst, DfsEvent, breadth_first_search, depth_first_search, depth_first_visit,
+};
+use crate::chapter_8_minimum_spanning_tree::{SpanningForest, kruskal, prim};
+use crate::chapter_9_all_pairs_shortest_paths::{DistanceMatrix, floyd_warshall, johnson};
+use crate::chapter_9_bellman_ford_algorithm::{NegativeCycle, bellman_ford, bellman_ford_tree};
+use crate::chapter_9_bidirectional_dijkstra::bidirectional_path;
+use crate::chapter_9_dijkstra_algorithm::{DijkstraAlgorithm, Search, astar, find_negative_edge};
+use crate::chapter_9_yen_k_shortest_paths::k_shortest_paths;
+use std::collections::HashMap;
+use std::fmt;
+use std::mem;
+use std::ops::ControlFlow;
+use std::sync::OnceLock;
+
+/// Identifies a vertex of a graph. Ids are handed out by [`Graph::add_vertex`]
+/// and are never handed out again, even after the vertex is removed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(transparent)
+)]
+pub struct VertexId(usize);
+
+impl VertexId {
+    /// The position of the vertex in the order the vertices were added.
+    pub fn index(&self) -> usize {
+        self.0
+    }
+}
 
-pub type VertexId = usize;
 pub type Weight = isize;
 pub type Distance = Weight;
 
+/// A vertex with its outgoing edges as `(id, item, [(to, payload)])`.
+pub(crate) type AdjacencyEntry<T, E> = (VertexId, T, Vec<(VertexId, E)>);
+
+/// Why an adjacency list cannot be turned back into a [`Graph`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum InvalidAdjacency {
+    DuplicateVertex(VertexId),
+    /// A vertex lists the same neighbor twice.
+    DuplicateEdge(VertexId, VertexId),
+    MissingVertex(VertexId, VertexId),
+    /// An undirected edge whose reverse is absent or carries another payload.
+    UnmirroredEdge(VertexId, VertexId),
+    /// The id is the largest possible, so no id is left for the next vertex.
+    IdOverflow(VertexId),
+}
+
+impl fmt::Display for InvalidAdjacency {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InvalidAdjacency::DuplicateVertex(id) => {
+                write!(f, "vertex {} is listed twice", id.0)
+            }
+            InvalidAdjacency::DuplicateEdge(from, to) => {
+                write!(f, "vertex {} is attached to vertex {} twice", from.0, to.0)
+            }
+            InvalidAdjacency::MissingVertex(from, to) => {
+                write!(
+                    f,
+                    "vertex {} is attached to missing vertex {}",
+                    from.0, to.0
+                )
+            }
+            InvalidAdjacency::UnmirroredEdge(from, to) => write!(
+                f,
+                "undirected edge from vertex {} to vertex {} is not listed the other way around",
+                from.0, to.0
+            ),
+            InvalidAdjacency::IdOverflow(id) => write!(f, "vertex id {} is too large", id.0),
+        }
+    }
+}
+
+/// Edge payloads that can be measured by the shortest path algorithms.
+pub trait Weighted {
+    fn weight(&self) -> Weight;
+}
+
+macro_rules! impl_weighted {
+    ($($number:ty),*) => {
+        $(impl Weighted for $number {
+            fn weight(&self) -> Weight {
+                *self as Weight
+            }
+        })*
+    };
+}
+
+impl_weighted!(isize, i8, i16, i32, i64, u8, u16, u32);
+
+/// Read access to the vertices and edges of a graph, so that searches run the same
+/// over a [`Graph`] and over its frozen [`CsrGraph`].
+pub trait GraphView {
+    type Item;
+    type Edge;
+
+    fn is_directed(&self) -> bool;
+
+    fn vertex_count(&self) -> usize;
+
+    /// The ids of every vertex, in the order they were added.
+    fn vertex_ids(&self) -> Vec<VertexId>;
+
+    fn vertex(&self, vertex_id: &VertexId) -> Option<&Vertex<Self::Item>>;
+
+    /// The edges leaving the vertex, in the order they were attached. Empty when the
+    /// vertex is not in the graph.
+    fn legs(&self, vertex_id: &VertexId) -> &[Leg<Self::Edge>];
+
+    /// Whether any edge has a negative weight, which rules out Dijkstra's algorithm.
+    /// Graphs override it to avoid going through every edge on each search.
+    fn has_negative_weights(&self) -> bool
+    where
+        Self::Edge: Weighted,
+    {
+        find_negative_edge(self).is_some()
+    }
+
+    /// The edges reaching the vertex as `(from, weight)`, for searches that follow edges
+    /// backwards. `None` when the graph keeps no index of them, which is all right for
+    /// undirected graphs since their legs already go both ways.
+    fn incoming_weights(&self, _vertex_id: &VertexId) -> Option<&[(VertexId, Weight)]>
+    where
+        Self::Edge: Weighted,
+    {
+        None
+    }
+
+    fn contains_vertex(&self, vertex_id: &VertexId) -> bool {
+        self.vertex(vertex_id).is_some()
+    }
+}
+
 pub struct Path<'a, T> {
     pub distance: Distance,
     pub waypoints: Vec<&'a Vertex<T>>,
 }
 
-pub struct Leg {
+/// The cheapest paths from one departure to every vertex it can reach, kept as the
+/// distance to each vertex and the vertex that comes right before it.
+pub struct ShortestPathTree<'a, T, E = Weight> {
+    graph: &'a dyn GraphView<Item = T, Edge = E>,
+    departure: VertexId,
+    distances: HashMap<VertexId, Distance>,
+    parents: HashMap<VertexId, VertexId>,
+}
+
+impl<'a, T, E> ShortestPathTree<'a, T, E> {
+    pub(crate) fn new(
+        graph: &'a dyn GraphView<Item = T, Edge = E>,
+        departure: VertexId,
+        distances: HashMap<VertexId, Distance>,
+        parents: HashMap<VertexId, VertexId>,
+    ) -> Self {
+        ShortestPathTree {
+            graph,
+            departure,
+            distances,
+            parents,
+        }
+    }
+
+    pub fn departure(&self) -> VertexId {
+        self.departure
+    }
+
+    pub fn reaches(&self, vertex_id: &VertexId) -> bool {
+        self.distances.contains_key(vertex_id)
+    }
+
+    pub fn distance_to(&self, vertex_id: &VertexId) -> Option<Distance> {
+        self.distances.get(vertex_id).copied()
+    }
+
+    /// The vertex right before the given one on its cheapest path. The departure has none.
+    pub fn parent_of(&self, vertex_id: &VertexId) -> Option<VertexId> {
+        self.parents.get(vertex_id).copied()
+    }
+
+    /// Every reached vertex with its distance from the departure, in no particular order.
+    pub fn distances(&self) -> impl Iterator<Item = (VertexId, Distance)> + '_ {
+        self.distances
+            .iter()
+            .map(|(vertex_id, distance)| (*vertex_id, *distance))
+    }
+
+    /// Rebuilds the cheapest path from the departure to the given vertex.
+    pub fn path_to(&self, destination: &VertexId) -> Option<Path<'a, T>> {
+        let distance = self.distance_to(destination)?;
+        let mut waypoints = vec![self.graph.vertex(destination)?];
+        let mut waypoint = *destination;
+        while waypoint != self.departure {
+            waypoint = self.parent_of(&waypoint)?;
+            waypoints.push(self.graph.vertex(&waypoint)?);
+        }
+        waypoints.reverse();
+
+        Some(Path {
+            distance,
+            waypoints,
+        })
+    }
+}
+
+pub struct Leg<E> {
     to_vertex_id: VertexId,
-    weight: Weight,
+    edge: E,
+}
+
+impl<E> Leg<E> {
+    /// The vertex the edge reaches.
+    pub fn get_to_id(&self) -> VertexId {
+        self.to_vertex_id
+    }
+
+    pub fn get_edge(&self) -> &E {
+        &self.edge
+    }
 }
 
 #[derive(Debug, PartialEq)]
@@ -32,30 +244,124 @@ impl<T> Vertex<T> {
     }
 }
 
-pub struct Graph<T> {
-    pub edges: HashMap<VertexId, Vec<Leg>>,
-    pub vertices: HashMap<VertexId, Vertex<T>>,
-    pub counter: VertexId,
+/// A graph whose vertices hold items of type `T` and whose edges hold payloads of type `E`.
+///
+/// Graphs are directed unless built with [`Graph::undirected`], in which case every
+/// edge is mirrored. There is at most one edge from one vertex to another.
+pub struct Graph<T, E = Weight> {
+    pub(crate) edges: HashMap<VertexId, Vec<Leg<E>>>,
+    pub(crate) vertices: HashMap<VertexId, Vertex<T>>,
+    counter: usize,
+    directed: bool,
+    /// Whether some edge has a negative weight, found on the first search after the
+    /// edges last changed.
+    pub(crate) negative_weights: OnceLock<bool>,
 }
 
 impl<T> Graph<T> {
     pub fn new() -> Self {
+        Self::empty_directed()
+    }
+
+    /// Creates a graph in which attaching `a` to `b` also attaches `b` to `a`.
+    pub fn undirected() -> Self {
+        Self::empty_undirected()
+    }
+}
+
+impl<T, E> Graph<T, E> {
+    pub fn empty_directed() -> Self {
         Graph {
             edges: HashMap::new(),
             vertices: HashMap::new(),
             counter: 0,
+            directed: true,
+            negative_weights: OnceLock::new(),
+        }
+    }
+
+    /// Creates a graph in which attaching `a` to `b` also attaches `b` to `a`.
+    pub fn empty_undirected() -> Self {
+        Graph {
+            directed: false,
+            ..Graph::empty_directed()
+        }
+    }
+
+    /// Rebuilds a graph keeping the ids of its vertices, checking that the adjacency
+    /// describes a graph that could have been built by adding vertices and attaching them:
+    /// every vertex and every edge is listed once, edges only reach listed vertices, and
+    /// undirected graphs list every edge in both directions with the same payload.
+    pub(crate) fn from_adjacency(
+        directed: bool,
+        adjacency: Vec<AdjacencyEntry<T, E>>,
+    ) -> Result<Self, InvalidAdjacency>
+    where
+        E: PartialEq,
+    {
+        let mut graph = Graph {
+            directed,
+            ..Graph::empty_directed()
+        };
+        for (id, item, legs) in adjacency {
+            let next_id =
+                id.0.checked_add(1)
+                    .ok_or(InvalidAdjacency::IdOverflow(id))?;
+            graph.counter = graph.counter.max(next_id);
+            if graph.vertices.insert(id, Vertex { id, item }).is_some() {
+                return Err(InvalidAdjacency::DuplicateVertex(id));
+            }
+            let mut vertex_legs = Vec::<Leg<E>>::with_capacity(legs.len());
+            for (to_vertex_id, edge) in legs {
+                if vertex_legs
+                    .iter()
+                    .any(|leg| leg.to_vertex_id == to_vertex_id)
+                {
+                    return Err(InvalidAdjacency::DuplicateEdge(id, to_vertex_id));
+                }
+                vertex_legs.push(Leg { to_vertex_id, edge });
+            }
+            graph.edges.insert(id, vertex_legs);
         }
+
+        for from in graph.vertex_ids() {
+            for leg in &graph.edges[&from] {
+                let to = leg.to_vertex_id;
+                if !graph.vertices.contains_key(&to) {
+                    return Err(InvalidAdjacency::MissingVertex(from, to));
+                }
+                if !directed && graph.get_edge(&to, &from) != Some(&leg.edge) {
+                    return Err(InvalidAdjacency::UnmirroredEdge(from, to));
+                }
+            }
+        }
+        Ok(graph)
+    }
+
+    pub fn is_directed(&self) -> bool {
+        self.directed
     }
 
     pub fn add_vertex(&mut self, item: T) -> VertexId {
-        let id = self.counter;
+        let id = VertexId(self.counter);
         self.counter += 1;
         let vertex = Vertex { item, id };
         self.vertices.insert(id, vertex);
         id
     }
 
-    pub fn get_vertex(&self, vertex_id: &VertexId) -> Option<GetVertex<T>> {
+    /// Removes the vertex along with every edge leaving or reaching it.
+    pub fn remove_vertex(&mut self, vertex_id: &VertexId) -> Option<T> {
+        let vertex = self.vertices.remove(vertex_id)?;
+        self.edges.remove(vertex_id);
+        self.negative_weights.take();
+        for legs in self.edges.values_mut() {
+            legs.retain(|leg| leg.to_vertex_id != *vertex_id);
+        }
+        Some(vertex.item)
+    }
+
+    pub fn get_vertex(&self, vertex_id: &VertexId) -> Option<GetVertex<'_, T, E>> {
         let vertex = self.vertices.get(vertex_id);
         vertex.map(|vertex| GetVertex {
             vertex,
@@ -63,50 +369,342 @@ impl<T> Graph<T> {
         })
     }
 
-    fn attach_weighted(&mut self, from: &VertexId, to: &VertexId, weight: Weight) {
-        let leg = Leg {
-            weight,
-            to_vertex_id: *to,
-        };
-        match self.edges.get_mut(from) {
+    pub fn contains_vertex(&self, vertex_id: &VertexId) -> bool {
+        self.vertices.contains_key(vertex_id)
+    }
+
+    pub fn vertex_count(&self) -> usize {
+        self.vertices.len()
+    }
+
+    /// Counts the edges, where an undirected edge counts once for each direction.
+    pub fn edge_count(&self) -> usize {
+        self.edges.values().map(Vec::len).sum()
+    }
+
+    /// The ids of every vertex, in the order they were added.
+    pub fn vertex_ids(&self) -> Vec<VertexId> {
+        let mut ids = self.vertices.keys().copied().collect::<Vec<_>>();
+        ids.sort();
+        ids
+    }
+
+    /// Every edge as `(from, to, payload)`. Undirected edges show up in both directions.
+    pub fn edges(&self) -> impl Iterator<Item = (VertexId, VertexId, &E)> {
+        self.edges.iter().flat_map(|(from, legs)| {
+            legs.iter()
+                .map(move |leg| (*from, leg.to_vertex_id, &leg.edge))
+        })
+    }
+
+    pub fn get_edge(&self, from: &VertexId, to: &VertexId) -> Option<&E> {
+        self.edges
+            .get(from)?
+            .iter()
+            .find(|leg| leg.to_vertex_id == *to)
+            .map(|leg| &leg.edge)
+    }
+
+    fn put_leg(&mut self, from: &VertexId, to: &VertexId, edge: E) -> Option<E> {
+        self.negative_weights.take();
+        let legs = self.edges.entry(*from).or_default();
+        match legs.iter_mut().find(|leg| leg.to_vertex_id == *to) {
+            Some(leg) => Some(mem::replace(&mut leg.edge, edge)),
             None => {
-                self.edges.insert(*from, vec![leg]);
-            }
-            Some(vec) => {
-                vec.push(leg);
+                legs.push(Leg {
+                    to_vertex_id: *to,
+                    edge,
+                });
+                None
             }
         }
     }
 
-    pub fn attach(&mut self, from: &VertexId, to: &VertexId) {
-        self.attach_weighted(from, to, 1 as Weight);
+    fn take_leg(&mut self, from: &VertexId, to: &VertexId) -> Option<E> {
+        let legs = self.edges.get_mut(from)?;
+        let position = legs.iter().position(|leg| leg.to_vertex_id == *to)?;
+        self.negative_weights.take();
+        Some(legs.remove(position).edge)
+    }
+
+    /// Attaches `from` to `to` with the given payload, returning the payload of the
+    /// edge it replaces, if any.
+    ///
+    /// # Panics
+    /// If any of the vertices is not in the graph.
+    pub fn attach_weighted(&mut self, from: &VertexId, to: &VertexId, weight: E) -> Option<E>
+    where
+        E: Clone,
+    {
+        assert!(
+            self.contains_vertex(from) && self.contains_vertex(to),
+            "Cannot attach vertices that are not in the graph"
+        );
+        if !self.directed && from != to {
+            self.put_leg(to, from, weight.clone());
+        }
+        self.put_leg(from, to, weight)
+    }
+
+    /// Attaches `from` to `to` with a unit weight.
+    ///
+    /// # Panics
+    /// If any of the vertices is not in the graph.
+    pub fn attach(&mut self, from: &VertexId, to: &VertexId)
+    where
+        E: Clone + From<u8>,
+    {
+        self.attach_weighted(from, to, E::from(1));
     }
 
-    fn depth_search_iterator(&self, start: &VertexId) -> impl Iterator<Item = GetVertex<T>> {
-        GraphIterator::new(start, Mode::Depth, self)
+    /// Removes the edge from `from` to `to`, returning its payload.
+    pub fn detach(&mut self, from: &VertexId, to: &VertexId) -> Option<E> {
+        if !self.directed && from != to {
+            self.take_leg(to, from);
+        }
+        self.take_leg(from, to)
     }
 
-    pub fn breath_search_iterator(&self, start: &VertexId) -> impl Iterator<Item = GetVertex<T>> {
-        GraphIterator::new(start, Mode::Breath, self)
+    /// Replaces the payload of an existing edge, returning the previous one.
+    /// Nothing happens when there is no edge from `from` to `to`.
+    pub fn update_weight(&mut self, from: &VertexId, to: &VertexId, weight: E) -> Option<E>
+    where
+        E: Clone,
+    {
+        self.get_edge(from, to)?;
+        self.attach_weighted(from, to, weight)
     }
 
-    fn find_shortest_path(&self, departure: VertexId, destination: VertexId) -> Option<Path<T>> {
-        DijkstraAlgorithm::new(self, departure, destination).into_shortest_path()
+    /// Packs the graph into a [`CsrGraph`], which cannot change anymore but is faster
+    /// to search. Vertex ids stay the same.
+    pub fn freeze(self) -> CsrGraph<T, E> {
+        CsrGraph::from(self)
+    }
+
+    /// The vertices reachable from the start in depth first order, see [`depth_first_search`].
+    pub fn depth_search_iterator(
+        &self,
+        start: &VertexId,
+    ) -> impl Iterator<Item = GetVertex<'_, T, E>> {
+        depth_first_search(self, start).map(|vertex| GetVertex {
+            vertex,
+            graph: self,
+        })
+    }
+
+    /// The vertices reachable from the start, the closest ones first.
+    pub fn breath_search_iterator(
+        &self,
+        start: &VertexId,
+    ) -> impl Iterator<Item = GetVertex<'_, T, E>> {
+        breadth_first_search(self, start).map(|step| GetVertex {
+            vertex: step.vertex,
+            graph: self,
+        })
+    }
+
+    /// The vertices reachable from the start with their depth and parent, see
+    /// [`breadth_first_search`].
+    pub fn breadth_first_search(&self, start: &VertexId) -> BreadthFirst<'_, Self> {
+        breadth_first_search(self, start)
+    }
+
+    /// Searches depth first from each of the roots, handing every event to the visitor
+    /// until it breaks, see [`depth_first_visit`].
+    pub fn depth_first_visit<B>(
+        &self,
+        roots: impl IntoIterator<Item = VertexId>,
+        visitor: impl FnMut(DfsEvent) -> ControlFlow<B>,
+    ) -> ControlFlow<B> {
+        depth_first_visit(self, roots, visitor)
+    }
+
+    /// Pairs as many vertices of the left side as possible with vertices outside of it,
+    /// see [`bipartite_matching`].
+    pub fn bipartite_matching(&self, left: &[VertexId]) -> Vec<(VertexId, VertexId)> {
+        bipartite_matching(self, left)
+    }
+
+    /// Orders the vertices so that every edge goes forward, see [`topological_order`].
+    pub fn topological_order(&self) -> Result<Vec<VertexId>, Cycle> {
+        topological_order(self)
+    }
+
+    /// Groups the vertices that reach each other, see [`strongly_connected_components`].
+    pub fn strongly_connected_components(&self) -> Vec<Vec<VertexId>> {
+        strongly_connected_components(self)
+    }
+
+    /// Shrinks every strongly connected component into a single vertex, see [`condensation`].
+    pub fn condensation(&self) -> Condensation {
+        condensation(self)
     }
 }
 
-pub struct GetVertex<'a, T> {
+impl<T, E: Weighted> Graph<T, E> {
+    pub fn has_negative_weights(&self) -> bool {
+        GraphView::has_negative_weights(self)
+    }
+
+    /// Finds the cheapest path between two vertices, see [`shortest_path`].
+    pub fn find_shortest_path(
+        &self,
+        departure: VertexId,
+        destination: VertexId,
+    ) -> Result<Option<Path<'_, T>>, NegativeCycle> {
+        shortest_path(self, departure, destination)
+    }
+
+    /// Finds the cheapest path between two vertices with A*, guided by a heuristic that
+    /// estimates the distance between two vertex items, see [`astar`].
+    pub fn find_path_astar(
+        &self,
+        departure: VertexId,
+        destination: VertexId,
+        heuristic: impl Fn(&T, &T) -> Distance,
+    ) -> Search<'_, T> {
+        astar(self, departure, destination, heuristic)
+    }
+
+    /// Finds the cheapest paths from the departure to every vertex it can reach, so they
+    /// can be rebuilt without searching again. Runs Dijkstra's algorithm, unless some
+    /// edge has a negative weight, in which case it runs the slower Bellman-Ford.
+    pub fn shortest_path_tree(
+        &self,
+        departure: VertexId,
+    ) -> Result<ShortestPathTree<'_, T, E>, NegativeCycle> {
+        if self.has_negative_weights() {
+            bellman_ford_tree(self, departure)
+        } else {
+            Ok(DijkstraAlgorithm::from_departure(self, departure).into_shortest_path_tree())
+        }
+    }
+
+    /// Up to `k` loopless paths between two vertices, the cheapest first, see
+    /// [`k_shortest_paths`].
+    pub fn k_shortest_paths(
+        &self,
+        departure: VertexId,
+        destination: VertexId,
+        k: usize,
+    ) -> Result<Vec<Path<'_, T>>, NegativeCycle> {
+        k_shortest_paths(self, departure, destination, k)
+    }
+
+    /// The cheapest edges keeping connected every vertex that was, ignoring directions.
+    /// Same as [`Graph::minimum_spanning_tree_kruskal`].
+    pub fn minimum_spanning_tree(&self) -> SpanningForest<'_, E> {
+        kruskal(self)
+    }
+
+    /// Finds a minimum spanning forest with Kruskal's algorithm, see [`kruskal`].
+    pub fn minimum_spanning_tree_kruskal(&self) -> SpanningForest<'_, E> {
+        kruskal(self)
+    }
+
+    /// Finds a minimum spanning forest with Prim's algorithm, see [`prim`].
+    pub fn minimum_spanning_tree_prim(&self) -> SpanningForest<'_, E> {
+        prim(self)
+    }
+
+    /// The most that can flow from the source to the sink when edge weights are
+    /// capacities, see [`max_flow`].
+    ///
+    /// # Panics
+    /// If any of the vertices is not in the graph, or if they are the same.
+    pub fn max_flow(&self, source: VertexId, sink: VertexId) -> MaximumFlow {
+        max_flow(self, source, sink)
+    }
+
+    /// The distances between every pair of vertices, computed with Floyd-Warshall.
+    /// Prefer it for dense graphs.
+    pub fn all_pairs_floyd_warshall(&self) -> Result<DistanceMatrix, NegativeCycle> {
+        floyd_warshall(self)
+    }
+
+    /// The distances between every pair of vertices, computed with Johnson's algorithm.
+    /// Prefer it for sparse graphs.
+    pub fn all_pairs_johnson(&self) -> Result<DistanceMatrix, NegativeCycle> {
+        johnson(self)
+    }
+}
+
+/// Finds the cheapest path between two vertices of the graph. Runs Dijkstra's algorithm
+/// from both ends, see [`bidirectional_dijkstra`], when the graph is undirected or keeps
+/// the edges reaching each vertex, and from the departure alone otherwise. When some edge
+/// has a negative weight, it runs the slower Bellman-Ford instead.
+///
+/// [`bidirectional_dijkstra`]: crate::chapter_9_bidirectional_dijkstra::bidirectional_dijkstra
+///
+/// Returns `Ok(None)` when the destination cannot be reached from the departure.
+pub fn shortest_path<G: GraphView>(
+    graph: &G,
+    departure: VertexId,
+    destination: VertexId,
+) -> Result<Option<Path<'_, G::Item>>, NegativeCycle>
+where
+    G::Edge: Weighted,
+{
+    if graph.has_negative_weights() {
+        bellman_ford(graph, departure, destination)
+    } else {
+        Ok(bidirectional_path(graph, departure, destination))
+    }
+}
+
+impl<T, E> GraphView for Graph<T, E> {
+    type Item = T;
+    type Edge = E;
+
+    fn is_directed(&self) -> bool {
+        self.directed
+    }
+
+    fn vertex_count(&self) -> usize {
+        self.vertices.len()
+    }
+
+    fn vertex_ids(&self) -> Vec<VertexId> {
+        Graph::vertex_ids(self)
+    }
+
+    fn vertex(&self, vertex_id: &VertexId) -> Option<&Vertex<T>> {
+        self.vertices.get(vertex_id)
+    }
+
+    fn legs(&self, vertex_id: &VertexId) -> &[Leg<E>] {
+        self.edges.get(vertex_id).map_or(&[], Vec::as_slice)
+    }
+
+    fn has_negative_weights(&self) -> bool
+    where
+        E: Weighted,
+    {
+        *self
+            .negative_weights
+            .get_or_init(|| self.edges().any(|(_, _, edge)| edge.weight() < 0))
+    }
+}
+
+impl<T, E> Default for Graph<T, E> {
+    fn default() -> Self {
+        Self::empty_directed()
+    }
+}
+
+pub struct GetVertex<'a, T, E = Weight> {
     pub vertex: &'a Vertex<T>,
-    graph: &'a Graph<T>,
+    graph: &'a Graph<T, E>,
 }
 
-pub struct GetNeighbor<'a, T> {
-    pub get_vertex: GetVertex<'a, T>,
-    pub weight: Weight,
+pub struct GetNeighbor<'a, T, E = Weight> {
+    pub get_vertex: GetVertex<'a, T, E>,
+    pub edge: &'a E,
 }
 
-impl<'a, T> GetVertex<'a, T> {
-    pub fn get_neighbors(&self) -> Vec<GetNeighbor<'a, T>> {
+impl<'a, T, E> GetVertex<'a, T, E> {
+    /// The vertices this one is attached to, in the order they were attached.
+    pub fn get_neighbors(&self) -> Vec<GetNeighbor<'a, T, E>> {
         let mut neighbors = Vec::new();
         let Some(legs) = self.graph.edges.get(&self.vertex.id) else {
             return neighbors;
@@ -116,11 +714,10 @@ impl<'a, T> GetVertex<'a, T> {
                 continue;
             };
             neighbors.push(GetNeighbor {
-                weight: leg.weight,
+                edge: &leg.edge,
                 get_vertex: vertex,
             })
         }
-        neighbors.sort_by(|a, b| a.weight.cmp(&b.weight));
         neighbors
     }
 
@@ -133,7 +730,7 @@ impl<'a, T> GetVertex<'a, T> {
     }
 }
 
-impl<T> GetNeighbor<'_, T> {
+impl<T, E> GetNeighbor<'_, T, E> {
     pub fn get_item(&self) -> &T {
         self.get_vertex.get_item()
     }
@@ -142,62 +739,11 @@ impl<T> GetNeighbor<'_, T> {
     }
 }
 
-enum Mode {
-    Depth,
-    Breath,
-}
-
-struct GraphIterator<'a, T> {
-    queue: VecDeque<VertexId>,
-    visited: HashSet<VertexId>,
-    mode: Mode,
-    graph: &'a Graph<T>,
-}
-
-impl<'a, T> GraphIterator<'a, T> {
-    fn new(start: &VertexId, mode: Mode, graph: &'a Graph<T>) -> Self {
-        let mut it = GraphIterator {
-            queue: VecDeque::new(),
-            visited: HashSet::new(),
-            mode,
-            graph,
-        };
-        it.put_to_queue(start);
-        it
-    }
-
-    fn put_to_queue(&mut self, vertex_id: &VertexId) {
-        if !self.visited.contains(vertex_id) {
-            self.visited.insert(*vertex_id);
-            match &self.mode {
-                Mode::Breath => self.queue.push_back(*vertex_id),
-                Mode::Depth => self.queue.push_front(*vertex_id),
-            }
-        }
-    }
-}
-
-impl<'a, T> Iterator for GraphIterator<'a, T> {
-    type Item = GetVertex<'a, T>;
-
-    fn next(&mut self) -> Option<Self::Item> {
-        let next_vertex_id = self.queue.pop_front()?;
-        let next_vertex = self.graph.get_vertex(&next_vertex_id)?;
-
-        for get_neighbor in next_vertex.get_neighbors() {
-            self.put_to_queue(&get_neighbor.get_vertex.vertex.id);
-        }
-
-        Some(GetVertex {
-            vertex: next_vertex.vertex,
-            graph: self.graph,
-        })
-    }
-}
-
 #[cfg(test)]
 mod tests {
-    use crate::chapter_6_graph::{Graph, Path};
+    use crate::chapter_6_graph::{Graph, Path, Weight, Weighted};
+    use crate::chapter_9_bellman_ford_algorithm::{bellman_ford_tree, spfa_tree};
+    use crate::chapter_9_dijkstra_algorithm::{NegativeWeight, dijkstra};
 
     #[test]
     fn should_build_node() {
@@ -264,6 +810,7 @@ mod tests {
             waypoints,
         } = graph
             .find_shortest_path(start, finish)
+            .expect("Should have no negative cycle")
             .expect("Should find a route");
 
         assert_eq!(distance, 6);
@@ -302,6 +849,7 @@ mod tests {
 
         let path = graph
             .find_shortest_path(book, piano)
+            .expect("Should have no negative cycle")
             .expect("Should find a route");
 
         assert_eq!(path.distance, 35);
@@ -343,6 +891,7 @@ mod tests {
 
         let path = graph
             .find_shortest_path(start, finish)
+            .expect("Should have no negative cycle")
             .expect("Should find a route");
 
         assert_eq!(path.distance, 8);
@@ -366,6 +915,7 @@ mod tests {
 
         let path = graph
             .find_shortest_path(start, finish)
+            .expect("Should have no negative cycle")
             .expect("Should find a route");
 
         assert_eq!(path.distance, 60);
@@ -391,8 +941,278 @@ mod tests {
 
         let path = graph
             .find_shortest_path(start, finish)
+            .expect("Should have no negative cycle")
             .expect("Should find a route");
 
         assert_eq!(path.distance, 4);
     }
+
+    #[test]
+    fn should_refuse_negative_weights_in_dijkstra() {
+        let mut graph = Graph::new();
+        let start = graph.add_vertex("Start");
+        let a = graph.add_vertex("A");
+        let finish = graph.add_vertex("Finish");
+
+        graph.attach_weighted(&start, &finish, 5);
+        graph.attach_weighted(&start, &a, 10);
+        graph.attach_weighted(&a, &finish, -8);
+
+        assert!(graph.has_negative_weights());
+        assert_eq!(
+            dijkstra(&graph, start, finish).err(),
+            Some(NegativeWeight {
+                from: a,
+                to: finish,
+                weight: -8
+            })
+        );
+        assert_eq!(
+            graph
+                .find_shortest_path(start, finish)
+                .unwrap()
+                .unwrap()
+                .distance,
+            2
+        );
+    }
+
+    #[test]
+    fn should_track_negative_weights_as_edges_change() {
+        let mut graph = Graph::new();
+        let a = graph.add_vertex("A");
+        let b = graph.add_vertex("B");
+        let c = graph.add_vertex("C");
+
+        graph.attach_weighted(&a, &b, 2);
+        assert!(!graph.has_negative_weights());
+        graph.attach_weighted(&b, &c, -1);
+        assert!(graph.has_negative_weights());
+        graph.detach(&b, &c);
+        assert!(!graph.has_negative_weights());
+        graph.update_weight(&a, &b, -3);
+        assert!(graph.has_negative_weights());
+        graph.update_weight(&a, &b, 3);
+        assert!(!graph.has_negative_weights());
+        graph.attach_weighted(&c, &a, -4);
+        assert!(graph.has_negative_weights());
+        graph.remove_vertex(&c);
+        assert!(!graph.has_negative_weights());
+        assert_eq!(graph.find_shortest_path(a, b).unwrap().unwrap().distance, 3);
+    }
+
+    #[test]
+    fn should_rebuild_paths_from_shortest_path_tree() {
+        let mut graph = Graph::new();
+        let start = graph.add_vertex("Start");
+        let a = graph.add_vertex("A");
+        let b = graph.add_vertex("B");
+        let c = graph.add_vertex("C");
+        let lost = graph.add_vertex("Lost");
+
+        graph.attach_weighted(&start, &a, 1);
+        graph.attach_weighted(&start, &b, 4);
+        graph.attach_weighted(&a, &b, 2);
+        graph.attach_weighted(&b, &c, 1);
+
+        graph.attach_weighted(&lost, &c, 1);
+
+        let trees = [
+            graph.shortest_path_tree(start).unwrap(),
+            bellman_ford_tree(&graph, start).unwrap(),
+            spfa_tree(&graph, start).unwrap(),
+        ];
+        for tree in trees {
+            assert_eq!(tree.departure(), start);
+            assert_eq!(tree.distance_to(&b), Some(3));
+            assert_eq!(tree.parent_of(&b), Some(a));
+            assert_eq!(tree.parent_of(&start), None);
+            assert!(!tree.reaches(&lost));
+            assert_eq!(tree.distances().count(), 4);
+
+            let path = tree.path_to(&c).unwrap();
+            assert_eq!(path.distance, 4);
+            assert_eq!(
+                path.waypoints
+                    .iter()
+                    .map(|vertex| *vertex.get_item())
+                    .collect::<Vec<_>>(),
+                vec!["Start", "A", "B", "C"]
+            );
+            assert!(tree.path_to(&lost).is_none());
+        }
+    }
+
+    #[test]
+    fn should_report_negative_cycle_when_finding_shortest_path() {
+        let mut graph = Graph::new();
+        let a = graph.add_vertex("A");
+        let b = graph.add_vertex("B");
+
+        graph.attach_weighted(&a, &b, 1);
+        graph.attach_weighted(&b, &a, -2);
+
+        let cycle = graph.find_shortest_path(a, b).err().unwrap();
+        assert_eq!(cycle.vertex_ids, vec![a, b]);
+    }
+
+    #[test]
+    fn should_mirror_edges_in_undirected_graph() {
+        let mut graph = Graph::undirected();
+        let a = graph.add_vertex("A");
+        let b = graph.add_vertex("B");
+
+        graph.attach_weighted(&a, &b, 3);
+
+        assert!(!graph.is_directed());
+        assert_eq!(graph.get_edge(&a, &b), Some(&3));
+        assert_eq!(graph.get_edge(&b, &a), Some(&3));
+        assert_eq!(graph.find_shortest_path(b, a).unwrap().unwrap().distance, 3);
+
+        assert_eq!(graph.update_weight(&b, &a, 5), Some(3));
+        assert_eq!(graph.get_edge(&a, &b), Some(&5));
+
+        assert_eq!(graph.detach(&a, &b), Some(5));
+        assert_eq!(graph.edge_count(), 0);
+    }
+
+    #[test]
+    fn should_replace_edge_between_same_vertices() {
+        let mut graph = Graph::new();
+        let a = graph.add_vertex("A");
+        let b = graph.add_vertex("B");
+
+        assert_eq!(graph.attach_weighted(&a, &b, 3), None);
+        assert_eq!(graph.attach_weighted(&a, &b, 4), Some(3));
+
+        assert_eq!(graph.edge_count(), 1);
+        assert_eq!(graph.get_edge(&a, &b), Some(&4));
+        assert_eq!(graph.get_edge(&b, &a), None);
+    }
+
+    #[test]
+    fn should_detach_and_update_edges() {
+        let mut graph = Graph::new();
+        let a = graph.add_vertex("A");
+        let b = graph.add_vertex("B");
+        let c = graph.add_vertex("C");
+
+        graph.attach_weighted(&a, &b, 1);
+        graph.attach_weighted(&b, &c, 1);
+        graph.attach_weighted(&a, &c, 5);
+
+        assert_eq!(graph.find_shortest_path(a, c).unwrap().unwrap().distance, 2);
+        assert_eq!(graph.update_weight(&b, &c, 10), Some(1));
+        assert_eq!(graph.find_shortest_path(a, c).unwrap().unwrap().distance, 5);
+        assert_eq!(graph.detach(&a, &c), Some(5));
+        assert_eq!(
+            graph.find_shortest_path(a, c).unwrap().unwrap().distance,
+            11
+        );
+
+        assert_eq!(graph.update_weight(&c, &a, 1), None);
+        assert_eq!(graph.detach(&c, &a), None);
+        assert_eq!(graph.get_edge(&c, &a), None);
+    }
+
+    #[test]
+    fn should_remove_vertex_and_incoming_edges() {
+        let mut graph = Graph::new();
+        let a = graph.add_vertex("A");
+        let b = graph.add_vertex("B");
+        let c = graph.add_vertex("C");
+
+        graph.attach(&a, &b);
+        graph.attach(&b, &c);
+        graph.attach(&c, &b);
+
+        assert_eq!(graph.remove_vertex(&b), Some("B"));
+        assert_eq!(graph.remove_vertex(&b), None);
+
+        assert!(graph.get_vertex(&b).is_none());
+        assert_eq!(graph.edge_count(), 0);
+        assert!(graph.get_vertex(&a).unwrap().get_neighbors().is_empty());
+        assert!(graph.find_shortest_path(a, c).unwrap().is_none());
+        assert_eq!(graph.vertex_ids(), vec![a, c]);
+    }
+
+    #[test]
+    fn should_never_reuse_removed_ids() {
+        let mut graph = Graph::new();
+        let a = graph.add_vertex("A");
+
+        graph.remove_vertex(&a);
+        let b = graph.add_vertex("B");
+
+        assert_ne!(a, b);
+        assert!(graph.get_vertex(&a).is_none());
+        assert_eq!(graph.get_vertex(&b).unwrap().get_item(), &"B");
+    }
+
+    #[test]
+    #[should_panic(expected = "not in the graph")]
+    fn should_not_attach_removed_vertex() {
+        let mut graph = Graph::new();
+        let a = graph.add_vertex("A");
+        let b = graph.add_vertex("B");
+
+        graph.remove_vertex(&b);
+        graph.attach(&a, &b);
+    }
+
+    #[test]
+    fn should_hold_custom_edge_payloads() {
+        #[derive(Clone, Debug, PartialEq)]
+        struct Road {
+            name: &'static str,
+            kilometers: u16,
+        }
+
+        impl Weighted for Road {
+            fn weight(&self) -> Weight {
+                self.kilometers as Weight
+            }
+        }
+
+        let mut graph = Graph::empty_undirected();
+        let home = graph.add_vertex("Home");
+        let work = graph.add_vertex("Work");
+        let gym = graph.add_vertex("Gym");
+
+        let highway = Road {
+            name: "Highway",
+            kilometers: 30,
+        };
+        graph.attach_weighted(&home, &work, highway.clone());
+        graph.attach_weighted(
+            &home,
+            &gym,
+            Road {
+                name: "Avenue",
+                kilometers: 5,
+            },
+        );
+        graph.attach_weighted(
+            &gym,
+            &work,
+            Road {
+                name: "Street",
+                kilometers: 10,
+            },
+        );
+
+        let path = graph.find_shortest_path(work, home).unwrap().unwrap();
+        assert_eq!(path.distance, 15);
+        assert_eq!(graph.get_edge(&work, &home), Some(&highway));
+        assert_eq!(
+            graph
+                .get_vertex(&home)
+                .unwrap()
+                .get_neighbors()
+                .iter()
+                .map(|neighbor| neighbor.edge.name)
+                .collect::<Vec<_>>(),
+            vec!["Highway", "Avenue"]
+        );
+    }
 }
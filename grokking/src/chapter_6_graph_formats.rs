@@ -0,0 +1,487 @@
+use crate::chapter_6_graph::{Graph, Path, Weight};
+use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::fmt::{self, Display, Write};
+use std::str::FromStr;
+
+const HIGHLIGHT: &str = "color=red, penwidth=2";
+
+impl<T: Display, E: Display> Graph<T, E> {
+    /// Writes the graph in the Graphviz DOT language, labelling vertices with their
+    /// items and edges with their payloads. An undirected edge is written once.
+    pub fn to_dot(&self) -> String {
+        write_dot(self, None)
+    }
+
+    /// Same as [`Graph::to_dot`], with the vertices and edges of the path highlighted.
+    pub fn to_dot_with_path(&self, path: &Path<'_, T>) -> String {
+        write_dot(self, Some(path))
+    }
+}
+
+fn write_dot<T: Display, E: Display>(graph: &Graph<T, E>, path: Option<&Path<'_, T>>) -> String {
+    let waypoints = path.map_or_else(Vec::new, |path| {
+        path.waypoints
+            .iter()
+            .map(|vertex| vertex.get_id())
+            .collect::<Vec<_>>()
+    });
+    let on_path = waypoints.iter().copied().collect::<HashSet<_>>();
+    let mut legs_on_path = waypoints
+        .windows(2)
+        .map(|pair| (pair[0], pair[1]))
+        .collect::<HashSet<_>>();
+    if !graph.is_directed() {
+        let mirrored = legs_on_path
+            .iter()
+            .map(|(from, to)| (*to, *from))
+            .collect::<Vec<_>>();
+        legs_on_path.extend(mirrored);
+    }
+
+    let (keyword, arrow) = if graph.is_directed() {
+        ("digraph", "->")
+    } else {
+        ("graph", "--")
+    };
+    let mut dot = format!("{keyword} {{\n");
+    for vertex_id in graph.vertex_ids() {
+        let Some(get_vertex) = graph.get_vertex(&vertex_id) else {
+            continue;
+        };
+        let highlight = if on_path.contains(&vertex_id) {
+            format!(", {HIGHLIGHT}")
+        } else {
+            String::new()
+        };
+        let _ = writeln!(
+            dot,
+            "    {} [label=\"{}\"{highlight}];",
+            vertex_id.index(),
+            escape(get_vertex.get_item())
+        );
+    }
+    for from in graph.vertex_ids() {
+        let Some(get_vertex) = graph.get_vertex(&from) else {
+            continue;
+        };
+        for get_neighbor in get_vertex.get_neighbors() {
+            let to = get_neighbor.get_id();
+            if !graph.is_directed() && to < from {
+                continue;
+            }
+            let highlight = if legs_on_path.contains(&(from, to)) {
+                format!(", {HIGHLIGHT}")
+            } else {
+                String::new()
+            };
+            let _ = writeln!(
+                dot,
+                "    {} {arrow} {} [label=\"{}\"{highlight}];",
+                from.index(),
+                to.index(),
+                escape(get_neighbor.edge)
+            );
+        }
+    }
+    dot.push_str("}\n");
+    dot
+}
+
+fn escape(label: impl Display) -> String {
+    label.to_string().replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+/// The error returned when a line of an edge list cannot be read. Lines count from one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseEdgeListError {
+    MissingTarget { line: usize },
+    InvalidWeight { line: usize, weight: String },
+    TrailingTokens { line: usize },
+}
+
+impl Display for ParseEdgeListError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseEdgeListError::MissingTarget { line } => {
+                write!(f, "line {line}: the edge has no target vertex")
+            }
+            ParseEdgeListError::InvalidWeight { line, weight } => {
+                write!(f, "line {line}: `{weight}` is not a valid weight")
+            }
+            ParseEdgeListError::TrailingTokens { line } => {
+                write!(f, "line {line}: expected `from to [weight]`")
+            }
+        }
+    }
+}
+
+impl Error for ParseEdgeListError {}
+
+impl Graph<String> {
+    /// Reads edges written one per line as `from to [weight]`, separated by whitespace.
+    /// The weight defaults to one, and everything after a `#` is a comment.
+    ///
+    /// Vertices are named by their items: names already in the graph are reused, and
+    /// new names are added as new vertices. Nothing is added if any line is invalid.
+    pub fn read_edge_list(&mut self, input: &str) -> Result<(), ParseEdgeListError> {
+        let edges = parse_edge_list(input)?;
+
+        // Going backwards, the first vertex with a given name is the one that stays.
+        let mut vertex_ids = self
+            .vertex_ids()
+            .into_iter()
+            .rev()
+            .filter_map(|vertex_id| {
+                let item = self.get_vertex(&vertex_id)?.get_item().clone();
+                Some((item, vertex_id))
+            })
+            .collect::<HashMap<_, _>>();
+        for (from, to, weight) in edges {
+            let from = *vertex_ids
+                .entry(from.to_string())
+                .or_insert_with(|| self.add_vertex(from.to_string()));
+            let to = *vertex_ids
+                .entry(to.to_string())
+                .or_insert_with(|| self.add_vertex(to.to_string()));
+            self.attach_weighted(&from, &to, weight);
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Graph<String> {
+    type Err = ParseEdgeListError;
+
+    /// Builds a directed graph from an edge list, see [`Graph::read_edge_list`].
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        let mut graph = Graph::new();
+        graph.read_edge_list(input)?;
+        Ok(graph)
+    }
+}
+
+fn parse_edge_list(input: &str) -> Result<Vec<(&str, &str, Weight)>, ParseEdgeListError> {
+    let mut edges = Vec::new();
+    for (index, line) in input.lines().enumerate() {
+        let line_number = index + 1;
+        let content = line.split('#').next().unwrap_or_default();
+        let mut tokens = content.split_whitespace();
+        let Some(from) = tokens.next() else {
+            continue;
+        };
+        let to = tokens
+            .next()
+            .ok_or(ParseEdgeListError::MissingTarget { line: line_number })?;
+        let weight = match tokens.next() {
+            None => 1,
+            Some(weight) => weight
+                .parse()
+                .map_err(|_| ParseEdgeListError::InvalidWeight {
+                    line: line_number,
+                    weight: weight.to_string(),
+                })?,
+        };
+        if tokens.next().is_some() {
+            return Err(ParseEdgeListError::TrailingTokens { line: line_number });
+        }
+        edges.push((from, to, weight));
+    }
+    Ok(edges)
+}
+
+/// Graphs serialize as their adjacency lists, keeping vertex ids so that paths and
+/// other results computed before still apply after a round trip:
+///
+/// ```json
+/// {"directed": true, "vertices": [{"id": 0, "item": "A", "edges": [{"to": 1, "edge": 7}]}]}
+/// ```
+#[cfg(feature = "serde")]
+mod adjacency {
+    use crate::chapter_6_graph::{Graph, VertexId};
+    use serde::de::Error;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    #[derive(Serialize, Deserialize)]
+    struct Adjacency<V> {
+        directed: bool,
+        vertices: Vec<V>,
+    }
+
+    #[derive(Serialize)]
+    struct BorrowedVertex<'a, T, E> {
+        id: VertexId,
+        item: &'a T,
+        edges: Vec<BorrowedEdge<'a, E>>,
+    }
+
+    #[derive(Serialize)]
+    struct BorrowedEdge<'a, E> {
+        to: VertexId,
+        edge: &'a E,
+    }
+
+    #[derive(Deserialize)]
+    struct OwnedVertex<T, E> {
+        id: VertexId,
+        item: T,
+        edges: Vec<OwnedEdge<E>>,
+    }
+
+    #[derive(Deserialize)]
+    struct OwnedEdge<E> {
+        to: VertexId,
+        edge: E,
+    }
+
+    impl<T: Serialize, E: Serialize> Serialize for Graph<T, E> {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            let vertices = self
+                .vertex_ids()
+                .into_iter()
+                .filter_map(|id| self.get_vertex(&id))
+                .map(|get_vertex| BorrowedVertex {
+                    id: get_vertex.vertex.get_id(),
+                    item: &get_vertex.vertex.item,
+                    edges: get_vertex
+                        .get_neighbors()
+                        .into_iter()
+                        .map(|get_neighbor| BorrowedEdge {
+                            to: get_neighbor.get_id(),
+                            edge: get_neighbor.edge,
+                        })
+                        .collect(),
+                })
+                .collect();
+            Adjacency {
+                directed: self.is_directed(),
+                vertices,
+            }
+            .serialize(serializer)
+        }
+    }
+
+    impl<'de, T: Deserialize<'de>, E: Deserialize<'de> + PartialEq> Deserialize<'de> for Graph<T, E> {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            let adjacency = Adjacency::<OwnedVertex<T, E>>::deserialize(deserializer)?;
+
+            let vertices = adjacency
+                .vertices
+                .into_iter()
+                .map(|vertex| {
+                    let edges = vertex
+                        .edges
+                        .into_iter()
+                        .map(|edge| (edge.to, edge.edge))
+                        .collect();
+                    (vertex.id, vertex.item, edges)
+                })
+                .collect();
+            Graph::from_adjacency(adjacency.directed, vertices).map_err(D::Error::custom)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ParseEdgeListError;
+    use crate::chapter_6_graph::Graph;
+
+    #[test]
+    fn should_write_dot_with_highlighted_path() {
+        let mut graph = Graph::new();
+        let start = graph.add_vertex("Start");
+        let middle = graph.add_vertex("Middle \"M\"");
+        let finish = graph.add_vertex("Finish");
+
+        graph.attach_weighted(&start, &middle, 2);
+        graph.attach_weighted(&middle, &finish, 3);
+        graph.attach_weighted(&start, &finish, 9);
+
+        let path = graph.find_shortest_path(start, finish).unwrap().unwrap();
+
+        assert_eq!(
+            graph.to_dot_with_path(&path),
+            r#"digraph {
+    0 [label="Start", color=red, penwidth=2];
+    1 [label="Middle \"M\"", color=red, penwidth=2];
+    2 [label="Finish", color=red, penwidth=2];
+    0 -> 1 [label="2", color=red, penwidth=2];
+    0 -> 2 [label="9"];
+    1 -> 2 [label="3", color=red, penwidth=2];
+}
+"#
+        );
+    }
+
+    #[test]
+    fn should_write_undirected_edges_once() {
+        let mut graph = Graph::undirected();
+        let a = graph.add_vertex("A");
+        let b = graph.add_vertex("B");
+
+        graph.attach_weighted(&b, &a, 4);
+
+        assert_eq!(
+            graph.to_dot(),
+            r#"graph {
+    0 [label="A"];
+    1 [label="B"];
+    0 -- 1 [label="4"];
+}
+"#
+        );
+    }
+
+    #[test]
+    fn should_read_edge_list() {
+        let graph = "
+            # from to weight
+            Start A 6
+            Start B 2
+            B A 3   # cheaper through B
+            A Finish
+            B Finish 5
+        "
+        .parse::<Graph<String>>()
+        .expect("Should parse the edge list");
+
+        assert_eq!(graph.vertex_count(), 4);
+        assert_eq!(graph.edge_count(), 5);
+        let ids = graph.vertex_ids();
+        let path = graph.find_shortest_path(ids[0], ids[3]).unwrap().unwrap();
+        assert_eq!(path.distance, 6);
+        assert_eq!(
+            path.waypoints
+                .iter()
+                .map(|vertex| vertex.get_item().as_str())
+                .collect::<Vec<_>>(),
+            vec!["Start", "B", "A", "Finish"]
+        );
+    }
+
+    #[test]
+    fn should_reuse_vertices_when_reading_into_graph() {
+        let mut graph = Graph::undirected();
+        let a = graph.add_vertex("A".to_string());
+
+        graph.read_edge_list("A B -2\nB C").unwrap();
+
+        assert_eq!(graph.vertex_count(), 3);
+        assert_eq!(graph.get_vertex(&a).unwrap().get_neighbors().len(), 1);
+        assert_eq!(graph.edge_count(), 4);
+    }
+
+    #[test]
+    fn should_report_invalid_lines() {
+        let mut graph = Graph::new();
+
+        assert_eq!(
+            graph.read_edge_list("A B\nC\n"),
+            Err(ParseEdgeListError::MissingTarget { line: 2 })
+        );
+        assert_eq!(
+            graph.read_edge_list("A B ten"),
+            Err(ParseEdgeListError::InvalidWeight {
+                line: 1,
+                weight: "ten".to_string()
+            })
+        );
+        assert_eq!(
+            graph.read_edge_list("\n\nA B 1 2").unwrap_err().to_string(),
+            "line 3: expected `from to [weight]`"
+        );
+        assert_eq!(graph.vertex_count(), 0);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn should_round_trip_adjacency_json() {
+        let mut graph = Graph::undirected();
+        let a = graph.add_vertex("A".to_string());
+        let removed = graph.add_vertex("Removed".to_string());
+        let b = graph.add_vertex("B".to_string());
+        graph.attach_weighted(&a, &b, 7);
+        graph.remove_vertex(&removed);
+
+        let json = serde_json::to_string(&graph).unwrap();
+        assert_eq!(
+            json,
+            r#"{"directed":false,"vertices":[{"id":0,"item":"A","edges":[{"to":2,"edge":7}]},{"id":2,"item":"B","edges":[{"to":0,"edge":7}]}]}"#
+        );
+
+        let mut restored = serde_json::from_str::<Graph<String>>(&json).unwrap();
+        assert!(!restored.is_directed());
+        assert_eq!(restored.vertex_ids(), vec![a, b]);
+        assert_eq!(restored.get_edge(&b, &a), Some(&7));
+        assert_eq!(restored.edge_count(), 2);
+        assert!(restored.add_vertex("C".to_string()) > b);
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn should_reject_edges_to_missing_vertices() {
+        let json =
+            r#"{"directed":true,"vertices":[{"id":0,"item":"A","edges":[{"to":3,"edge":1}]}]}"#;
+
+        let error = serde_json::from_str::<Graph<String>>(json).err().unwrap();
+
+        assert!(error.to_string().contains("missing vertex 3"));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn should_reject_edges_listed_twice() {
+        let json = r#"{"directed":true,"vertices":[
+            {"id":0,"item":"A","edges":[{"to":1,"edge":1},{"to":1,"edge":7}]},
+            {"id":1,"item":"B","edges":[]}
+        ]}"#;
+
+        let error = serde_json::from_str::<Graph<String>>(json).err().unwrap();
+
+        assert!(
+            error
+                .to_string()
+                .contains("vertex 0 is attached to vertex 1 twice")
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn should_reject_undirected_edges_listed_one_way() {
+        let one_way = r#"{"directed":false,"vertices":[
+            {"id":0,"item":"A","edges":[{"to":1,"edge":1}]},
+            {"id":1,"item":"B","edges":[]}
+        ]}"#;
+        let different_payloads = r#"{"directed":false,"vertices":[
+            {"id":0,"item":"A","edges":[{"to":1,"edge":1}]},
+            {"id":1,"item":"B","edges":[{"to":0,"edge":2}]}
+        ]}"#;
+        let self_loop = r#"{"directed":false,"vertices":[
+            {"id":0,"item":"A","edges":[{"to":0,"edge":1}]}
+        ]}"#;
+
+        for json in [one_way, different_payloads] {
+            let error = serde_json::from_str::<Graph<String>>(json).err().unwrap();
+            assert!(error.to_string().contains("from vertex 0 to vertex 1"));
+        }
+        let graph = serde_json::from_str::<Graph<String>>(self_loop).unwrap();
+        assert_eq!(
+            graph.get_edge(&graph.vertex_ids()[0], &graph.vertex_ids()[0]),
+            Some(&1)
+        );
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn should_reject_the_largest_vertex_id() {
+        let json = format!(
+            r#"{{"directed":true,"vertices":[{{"id":{},"item":"A","edges":[]}}]}}"#,
+            usize::MAX
+        );
+
+        let error = serde_json::from_str::<Graph<String>>(&json).err().unwrap();
+
+        assert!(error.to_string().contains("is too large"));
+    }
+}
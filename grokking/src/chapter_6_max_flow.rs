@@ -0,0 +1,413 @@
+use crate::chapter_6_graph::{Graph, VertexId, Weight, Weighted};
+use std::collections::{HashMap, HashSet, VecDeque};
+
+/// The most that can flow from a source to a sink when every edge carries at most
+/// its weight, along with how it flows and what holds it back.
+#[derive(Debug, Clone)]
+pub struct MaximumFlow {
+    pub value: Weight,
+    flows: HashMap<(VertexId, VertexId), Weight>,
+    /// The vertices the source can still push flow to. Every edge leaving them for the
+    /// sink side is saturated, and their capacities add up to the value of the flow.
+    pub source_side: HashSet<VertexId>,
+    pub sink_side: HashSet<VertexId>,
+}
+
+impl MaximumFlow {
+    /// How much flows along the edge from `from` to `to`.
+    pub fn flow_on(&self, from: &VertexId, to: &VertexId) -> Weight {
+        self.flows.get(&(*from, *to)).copied().unwrap_or(0)
+    }
+
+    /// Every edge that carries some flow, as `(from, to, flow)`, in no particular order.
+    pub fn edge_flows(&self) -> impl Iterator<Item = (VertexId, VertexId, Weight)> + '_ {
+        self.flows
+            .iter()
+            .filter(|(_, flow)| **flow > 0)
+            .map(|((from, to), flow)| (*from, *to, *flow))
+    }
+
+    /// The edges going from the source side to the sink side of the minimum cut.
+    pub fn cut_edges<'a, T, E>(
+        &'a self,
+        graph: &'a Graph<T, E>,
+    ) -> impl Iterator<Item = (VertexId, VertexId)> + 'a {
+        graph
+            .edges()
+            .map(|(from, to, _)| (from, to))
+            .filter(|(from, to)| self.source_side.contains(from) && self.sink_side.contains(to))
+    }
+}
+
+/// Computes the maximum flow from the source to the sink with Dinic's algorithm,
+/// treating edge weights as capacities. Negative weights count as no capacity.
+/// Each direction of an undirected edge can carry up to its weight.
+///
+/// # Panics
+/// If any of the vertices is not in the graph, or if they are the same.
+pub fn max_flow<T, E: Weighted>(
+    graph: &Graph<T, E>,
+    source: VertexId,
+    sink: VertexId,
+) -> MaximumFlow {
+    assert!(
+        graph.contains_vertex(&source) && graph.contains_vertex(&sink),
+        "Cannot find the flow between vertices that are not in the graph"
+    );
+    assert_ne!(source, sink, "The source and the sink must be different");
+
+    let mut network = ResidualNetwork::new(graph);
+    let source_position = network.positions[&source];
+    let sink_position = network.positions[&sink];
+    let mut value = 0;
+    while network.build_levels(source_position, sink_position) {
+        network.next_arcs.iter_mut().for_each(|next| *next = 0);
+        while let Some(pushed) = network.augment(source_position, sink_position) {
+            value += pushed;
+        }
+    }
+
+    network.build_levels(source_position, sink_position);
+    let (source_side, sink_side) = network
+        .vertex_ids
+        .iter()
+        .enumerate()
+        .partition::<Vec<_>, _>(|(position, _)| network.levels[*position].is_some());
+
+    MaximumFlow {
+        value,
+        flows: network.net_flows(),
+        source_side: source_side.into_iter().map(|(_, id)| *id).collect(),
+        sink_side: sink_side.into_iter().map(|(_, id)| *id).collect(),
+    }
+}
+
+/// Pairs as many vertices of the left side as possible with vertices outside of it,
+/// using each edge between both sides in either direction, and each vertex at most once.
+/// Runs on [`max_flow`], with every edge carrying a single unit.
+pub fn bipartite_matching<T, E>(
+    graph: &Graph<T, E>,
+    left: &[VertexId],
+) -> Vec<(VertexId, VertexId)> {
+    let left = left
+        .iter()
+        .copied()
+        .filter(|vertex_id| graph.contains_vertex(vertex_id))
+        .collect::<HashSet<_>>();
+    // Mirrors every vertex, with a source before the left side and a sink after the right one.
+    let mut network = Graph::<Option<VertexId>>::new();
+    let source = network.add_vertex(None);
+    let sink = network.add_vertex(None);
+    let mut mirrors = HashMap::new();
+    for vertex_id in graph.vertex_ids() {
+        let mirror = network.add_vertex(Some(vertex_id));
+        mirrors.insert(vertex_id, mirror);
+        if left.contains(&vertex_id) {
+            network.attach(&source, &mirror);
+        } else {
+            network.attach(&mirror, &sink);
+        }
+    }
+    for (from, to, _) in graph.edges() {
+        match (left.contains(&from), left.contains(&to)) {
+            (true, false) => network.attach(&mirrors[&from], &mirrors[&to]),
+            (false, true) => network.attach(&mirrors[&to], &mirrors[&from]),
+            _ => {}
+        }
+    }
+
+    let flow = max_flow(&network, source, sink);
+    let item = |mirror: VertexId| *network.get_vertex(&mirror)?.get_item();
+    let mut pairs = flow
+        .edge_flows()
+        .filter_map(|(from, to, _)| Some((item(from)?, item(to)?)))
+        .collect::<Vec<_>>();
+    pairs.sort();
+    pairs
+}
+
+/// An edge of the residual network. Arcs come in pairs, so the reverse of arc `i` is
+/// arc `i ^ 1`, and pushing flow through one gives the same capacity back to the other.
+struct Arc {
+    to: usize,
+    capacity: Weight,
+    flow: Weight,
+}
+
+impl Arc {
+    fn residual(&self) -> Weight {
+        self.capacity - self.flow
+    }
+}
+
+struct ResidualNetwork {
+    vertex_ids: Vec<VertexId>,
+    positions: HashMap<VertexId, usize>,
+    arcs: Vec<Arc>,
+    /// The arcs leaving each vertex.
+    adjacency: Vec<Vec<usize>>,
+    /// How many arcs away from the source each vertex is, if it can be reached at all.
+    levels: Vec<Option<usize>>,
+    /// The first arc of each vertex that may still lead to the sink in this phase.
+    next_arcs: Vec<usize>,
+    /// The arc standing for each edge of the graph.
+    edges: Vec<(VertexId, VertexId, usize)>,
+}
+
+impl ResidualNetwork {
+    fn new<T, E: Weighted>(graph: &Graph<T, E>) -> Self {
+        let vertex_ids = graph.vertex_ids();
+        let positions = vertex_ids
+            .iter()
+            .enumerate()
+            .map(|(position, vertex_id)| (*vertex_id, position))
+            .collect::<HashMap<_, _>>();
+        let mut network = ResidualNetwork {
+            arcs: Vec::with_capacity(graph.edge_count() * 2),
+            adjacency: vec![Vec::new(); vertex_ids.len()],
+            levels: vec![None; vertex_ids.len()],
+            next_arcs: vec![0; vertex_ids.len()],
+            edges: Vec::with_capacity(graph.edge_count()),
+            vertex_ids,
+            positions,
+        };
+        for from in network.vertex_ids.clone() {
+            let Some(get_vertex) = graph.get_vertex(&from) else {
+                continue;
+            };
+            for get_neighbor in get_vertex.get_neighbors() {
+                let to = get_neighbor.get_id();
+                let arc = network.add_arcs(from, to, get_neighbor.edge.weight().max(0));
+                network.edges.push((from, to, arc));
+            }
+        }
+        network
+    }
+
+    fn add_arcs(&mut self, from: VertexId, to: VertexId, capacity: Weight) -> usize {
+        let (from, to) = (self.positions[&from], self.positions[&to]);
+        let arc = self.arcs.len();
+        self.arcs.push(Arc {
+            to,
+            capacity,
+            flow: 0,
+        });
+        self.arcs.push(Arc {
+            to: from,
+            capacity: 0,
+            flow: 0,
+        });
+        self.adjacency[from].push(arc);
+        self.adjacency[to].push(arc ^ 1);
+        arc
+    }
+
+    /// Levels the vertices with a breadth first search over arcs with capacity left,
+    /// returning whether the sink can still be reached.
+    fn build_levels(&mut self, source: usize, sink: usize) -> bool {
+        self.levels.iter_mut().for_each(|level| *level = None);
+        self.levels[source] = Some(0);
+        let mut queue = VecDeque::from([source]);
+        while let Some(vertex) = queue.pop_front() {
+            let level = self.levels[vertex].map(|level| level + 1);
+            for arc in &self.adjacency[vertex] {
+                let arc = &self.arcs[*arc];
+                if arc.residual() > 0 && self.levels[arc.to].is_none() {
+                    self.levels[arc.to] = level;
+                    queue.push_back(arc.to);
+                }
+            }
+        }
+        self.levels[sink].is_some()
+    }
+
+    /// Pushes flow along one path from the source to the sink that only goes one level
+    /// up at a time, returning how much, or `None` when there is no such path left.
+    fn augment(&mut self, source: usize, sink: usize) -> Option<Weight> {
+        let mut path: Vec<usize> = Vec::new();
+        let mut vertex = source;
+        while vertex != sink {
+            match self.next_useful_arc(vertex) {
+                Some(arc) => {
+                    path.push(arc);
+                    vertex = self.arcs[arc].to;
+                }
+                None => {
+                    // Nothing leads to the sink from here anymore, so stop coming here.
+                    self.levels[vertex] = None;
+                    let arc = path.pop()?;
+                    vertex = self.arcs[arc ^ 1].to;
+                    self.next_arcs[vertex] += 1;
+                }
+            }
+        }
+
+        let pushed = path.iter().map(|arc| self.arcs[*arc].residual()).min()?;
+        for arc in path {
+            self.arcs[arc].flow += pushed;
+            self.arcs[arc ^ 1].flow -= pushed;
+        }
+        Some(pushed)
+    }
+
+    fn next_useful_arc(&mut self, vertex: usize) -> Option<usize> {
+        let next_level = self.levels[vertex]? + 1;
+        while let Some(arc) = self.adjacency[vertex].get(self.next_arcs[vertex]).copied() {
+            let candidate = &self.arcs[arc];
+            if candidate.residual() > 0 && self.levels[candidate.to] == Some(next_level) {
+                return Some(arc);
+            }
+            self.next_arcs[vertex] += 1;
+        }
+        None
+    }
+
+    /// The flow along every edge of the graph, where flow going both ways between two
+    /// vertices cancels out.
+    fn net_flows(&self) -> HashMap<(VertexId, VertexId), Weight> {
+        let mut flows = self
+            .edges
+            .iter()
+            .map(|(from, to, arc)| ((*from, *to), self.arcs[*arc].flow))
+            .collect::<HashMap<_, _>>();
+        for (from, to, _) in &self.edges {
+            let forward = flows[&(*from, *to)];
+            let Some(backward) = flows.get_mut(&(*to, *from)) else {
+                continue;
+            };
+            let cancelled = forward.min(*backward);
+            *backward -= cancelled;
+            *flows.get_mut(&(*from, *to)).unwrap() -= cancelled;
+        }
+        flows
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{bipartite_matching, max_flow};
+    use crate::chapter_6_graph::Graph;
+    use std::collections::HashSet;
+
+    #[test]
+    fn should_find_maximum_flow_and_minimum_cut() {
+        let mut graph = Graph::new();
+        let source = graph.add_vertex("S");
+        let a = graph.add_vertex("A");
+        let b = graph.add_vertex("B");
+        let c = graph.add_vertex("C");
+        let d = graph.add_vertex("D");
+        let sink = graph.add_vertex("T");
+
+        graph.attach_weighted(&source, &a, 10);
+        graph.attach_weighted(&source, &c, 10);
+        graph.attach_weighted(&a, &b, 4);
+        graph.attach_weighted(&a, &c, 2);
+        graph.attach_weighted(&a, &d, 8);
+        graph.attach_weighted(&c, &d, 9);
+        graph.attach_weighted(&d, &b, 6);
+        graph.attach_weighted(&b, &sink, 10);
+        graph.attach_weighted(&d, &sink, 10);
+
+        let flow = max_flow(&graph, source, sink);
+
+        assert_eq!(flow.value, 19);
+        assert!(flow.source_side.contains(&source));
+        assert!(flow.sink_side.contains(&sink));
+        assert_eq!(flow.source_side.len() + flow.sink_side.len(), 6);
+        let cut_capacity = flow
+            .cut_edges(&graph)
+            .map(|(from, to)| {
+                let capacity = *graph.get_edge(&from, &to).unwrap();
+                assert_eq!(flow.flow_on(&from, &to), capacity);
+                capacity
+            })
+            .sum::<isize>();
+        assert_eq!(cut_capacity, 19);
+
+        for vertex_id in [a, b, c, d] {
+            let incoming = flow
+                .edge_flows()
+                .filter(|(_, to, _)| *to == vertex_id)
+                .map(|(_, _, flow)| flow)
+                .sum::<isize>();
+            let outgoing = flow
+                .edge_flows()
+                .filter(|(from, _, _)| *from == vertex_id)
+                .map(|(_, _, flow)| flow)
+                .sum::<isize>();
+            assert_eq!(incoming, outgoing);
+        }
+        for (from, to, edge) in graph.edges() {
+            assert!((0..=*edge).contains(&flow.flow_on(&from, &to)));
+        }
+        assert_eq!(flow.flow_on(&b, &sink) + flow.flow_on(&d, &sink), 19);
+    }
+
+    #[test]
+    fn should_have_no_flow_between_disconnected_vertices() {
+        let mut graph = Graph::new();
+        let source = graph.add_vertex("S");
+        let sink = graph.add_vertex("T");
+        graph.attach_weighted(&sink, &source, 5);
+
+        let flow = max_flow(&graph, source, sink);
+
+        assert_eq!(flow.value, 0);
+        assert_eq!(flow.edge_flows().count(), 0);
+        assert_eq!(flow.source_side, HashSet::from([source]));
+    }
+
+    #[test]
+    fn should_send_flow_both_ways_in_undirected_graph() {
+        let mut graph = Graph::undirected();
+        let source = graph.add_vertex("S");
+        let a = graph.add_vertex("A");
+        let b = graph.add_vertex("B");
+        let sink = graph.add_vertex("T");
+
+        graph.attach_weighted(&source, &a, 3);
+        graph.attach_weighted(&source, &b, 2);
+        graph.attach_weighted(&a, &b, 5);
+        graph.attach_weighted(&b, &sink, 4);
+
+        let flow = max_flow(&graph, source, sink);
+
+        assert_eq!(flow.value, 4);
+        assert_eq!(flow.flow_on(&b, &a), 0);
+        assert!(flow.flow_on(&a, &b) >= 2);
+    }
+
+    #[test]
+    #[should_panic(expected = "must be different")]
+    fn should_not_find_flow_from_vertex_to_itself() {
+        let mut graph = Graph::new();
+        let a = graph.add_vertex("A");
+
+        max_flow(&graph, a, a);
+    }
+
+    #[test]
+    fn should_match_bipartite_graph() {
+        let mut graph = Graph::undirected();
+        let workers = ["Ana", "Bia", "Caio"].map(|name| graph.add_vertex(name));
+        let jobs = ["Cook", "Drive", "Sell"].map(|name| graph.add_vertex(name));
+
+        graph.attach(&workers[0], &jobs[0]);
+        graph.attach(&workers[0], &jobs[1]);
+        graph.attach(&workers[1], &jobs[0]);
+        graph.attach(&workers[2], &jobs[0]);
+        graph.attach(&workers[2], &jobs[2]);
+
+        let pairs = bipartite_matching(&graph, &workers);
+
+        assert_eq!(
+            pairs,
+            vec![
+                (workers[0], jobs[1]),
+                (workers[1], jobs[0]),
+                (workers[2], jobs[2])
+            ]
+        );
+    }
+}
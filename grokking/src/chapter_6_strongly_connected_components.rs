@@ -0,0 +1,216 @@
+use crate::chapter_6_graph::{Graph, VertexId};
+use crate::chapter_6_topological_sort::successors;
+use std::collections::{HashMap, HashSet};
+
+/// Splits the vertices into groups where every vertex reaches every other one, using
+/// Tarjan's algorithm without recursion.
+///
+/// The components come in topological order: edges between two components always
+/// go from an earlier one to a later one. The vertices of each component are sorted.
+pub fn strongly_connected_components<T, E>(graph: &Graph<T, E>) -> Vec<Vec<VertexId>> {
+    let mut tarjan = Tarjan::default();
+    for root in graph.vertex_ids() {
+        if !tarjan.indices.contains_key(&root) {
+            tarjan.search_from(graph, root);
+        }
+    }
+
+    // Tarjan finds a component only after every component it reaches.
+    tarjan.components.reverse();
+    tarjan.components
+}
+
+#[derive(Default)]
+struct Tarjan {
+    /// The order in which each vertex was discovered.
+    indices: HashMap<VertexId, usize>,
+    /// The lowest index reachable from the search tree under each vertex.
+    lowlinks: HashMap<VertexId, usize>,
+    stack: Vec<VertexId>,
+    on_stack: HashSet<VertexId>,
+    components: Vec<Vec<VertexId>>,
+}
+
+impl Tarjan {
+    fn search_from<T, E>(&mut self, graph: &Graph<T, E>, root: VertexId) {
+        // Each frame holds a vertex, its successors and how many of them were visited.
+        let mut frames = vec![self.discover(graph, root)];
+
+        while let Some((vertex_id, successors, visited)) = frames.last_mut() {
+            let vertex_id = *vertex_id;
+            if let Some(successor) = successors.get(*visited).copied() {
+                *visited += 1;
+                if !self.indices.contains_key(&successor) {
+                    frames.push(self.discover(graph, successor));
+                } else if self.on_stack.contains(&successor) {
+                    self.lower(vertex_id, self.indices[&successor]);
+                }
+                continue;
+            }
+
+            frames.pop();
+            if self.lowlinks[&vertex_id] == self.indices[&vertex_id] {
+                self.take_component(vertex_id);
+            }
+            if let Some((parent, _, _)) = frames.last() {
+                self.lower(*parent, self.lowlinks[&vertex_id]);
+            }
+        }
+    }
+
+    fn discover<T, E>(
+        &mut self,
+        graph: &Graph<T, E>,
+        vertex_id: VertexId,
+    ) -> (VertexId, Vec<VertexId>, usize) {
+        let index = self.indices.len();
+        self.indices.insert(vertex_id, index);
+        self.lowlinks.insert(vertex_id, index);
+        self.stack.push(vertex_id);
+        self.on_stack.insert(vertex_id);
+        (vertex_id, successors(graph, vertex_id), 0)
+    }
+
+    fn lower(&mut self, vertex_id: VertexId, lowlink: usize) {
+        let known = self.lowlinks.entry(vertex_id).or_insert(lowlink);
+        *known = lowlink.min(*known);
+    }
+
+    fn take_component(&mut self, root: VertexId) {
+        let mut component = Vec::new();
+        while let Some(vertex_id) = self.stack.pop() {
+            self.on_stack.remove(&vertex_id);
+            component.push(vertex_id);
+            if vertex_id == root {
+                break;
+            }
+        }
+        component.sort();
+        self.components.push(component);
+    }
+}
+
+/// The graph that results from shrinking every strongly connected component into a
+/// single vertex, which is always acyclic.
+pub struct Condensation {
+    /// Holds the vertices of each component, added in topological order. Each edge
+    /// holds how many edges of the original graph go between the two components.
+    pub graph: Graph<Vec<VertexId>, usize>,
+    components: HashMap<VertexId, VertexId>,
+}
+
+impl Condensation {
+    /// The vertex of the condensation standing for the component of the given vertex.
+    pub fn component_of(&self, vertex_id: &VertexId) -> Option<VertexId> {
+        self.components.get(vertex_id).copied()
+    }
+}
+
+/// Shrinks every strongly connected component of the graph into a single vertex.
+pub fn condensation<T, E>(graph: &Graph<T, E>) -> Condensation {
+    let mut condensed = Graph::empty_directed();
+    let mut components = HashMap::new();
+    for component in strongly_connected_components(graph) {
+        let members = component.clone();
+        let component_id = condensed.add_vertex(component);
+        for vertex_id in members {
+            components.insert(vertex_id, component_id);
+        }
+    }
+
+    for from in graph.vertex_ids() {
+        for to in successors(graph, from) {
+            let (from, to) = (components[&from], components[&to]);
+            if from != to {
+                let count = condensed.get_edge(&from, &to).copied().unwrap_or(0);
+                condensed.attach_weighted(&from, &to, count + 1);
+            }
+        }
+    }
+
+    Condensation {
+        graph: condensed,
+        components,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{condensation, strongly_connected_components};
+    use crate::chapter_6_graph::Graph;
+
+    #[test]
+    fn should_find_strongly_connected_components() {
+        let mut graph = Graph::new();
+        let ids = (0..8).map(|i| graph.add_vertex(i)).collect::<Vec<_>>();
+        for (from, to) in [
+            (0, 1),
+            (1, 2),
+            (2, 0),
+            (2, 3),
+            (3, 4),
+            (4, 5),
+            (5, 3),
+            (6, 5),
+            (6, 7),
+            (7, 6),
+        ] {
+            graph.attach(&ids[from], &ids[to]);
+        }
+
+        let components = strongly_connected_components(&graph);
+
+        assert_eq!(components.len(), 3);
+        assert!(components.contains(&vec![ids[0], ids[1], ids[2]]));
+        assert!(components.contains(&vec![ids[3], ids[4], ids[5]]));
+        assert!(components.contains(&vec![ids[6], ids[7]]));
+        let position = |id| components.iter().position(|c| c.contains(&id)).unwrap();
+        assert!(position(ids[0]) < position(ids[3]));
+        assert!(position(ids[6]) < position(ids[3]));
+    }
+
+    #[test]
+    fn should_survive_long_paths() {
+        let mut graph = Graph::new();
+        let ids = (0..100_000)
+            .map(|i| graph.add_vertex(i))
+            .collect::<Vec<_>>();
+        for pair in ids.windows(2) {
+            graph.attach(&pair[0], &pair[1]);
+        }
+        graph.attach(&ids[ids.len() - 1], &ids[0]);
+
+        assert_eq!(strongly_connected_components(&graph), vec![ids]);
+    }
+
+    #[test]
+    fn should_condense_components() {
+        let mut graph = Graph::new();
+        let a = graph.add_vertex("A");
+        let b = graph.add_vertex("B");
+        let c = graph.add_vertex("C");
+        let d = graph.add_vertex("D");
+
+        graph.attach(&a, &b);
+        graph.attach(&b, &a);
+        graph.attach(&a, &c);
+        graph.attach(&b, &c);
+        graph.attach(&c, &d);
+
+        let condensation = condensation(&graph);
+        let ab = condensation.component_of(&a).unwrap();
+        let c = condensation.component_of(&c).unwrap();
+        let d = condensation.component_of(&d).unwrap();
+
+        assert_eq!(condensation.component_of(&b), Some(ab));
+        assert_eq!(condensation.graph.vertex_count(), 3);
+        assert_eq!(
+            condensation.graph.get_vertex(&ab).unwrap().get_item(),
+            &vec![a, b]
+        );
+        assert_eq!(condensation.graph.get_edge(&ab, &c), Some(&2));
+        assert_eq!(condensation.graph.get_edge(&c, &d), Some(&1));
+        assert_eq!(condensation.graph.edge_count(), 2);
+        assert_eq!(condensation.graph.topological_order(), Ok(vec![ab, c, d]));
+    }
+}
@@ -0,0 +1,175 @@
+use crate::chapter_6_graph::{Graph, VertexId};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::error::Error;
+use std::fmt;
+
+/// The error returned when a graph that should be acyclic is not.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Cycle {
+    /// The vertices of the cycle in the direction of its edges, starting from the lowest id.
+    pub vertex_ids: Vec<VertexId>,
+}
+
+impl fmt::Display for Cycle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "the graph has a cycle: ")?;
+        for vertex_id in &self.vertex_ids {
+            write!(f, "{} -> ", vertex_id.index())?;
+        }
+        write!(f, "{}", self.vertex_ids[0].index())
+    }
+}
+
+impl Error for Cycle {}
+
+/// Orders the vertices so that every edge goes from a vertex to a later one, using
+/// Kahn's algorithm: repeatedly takes out a vertex that no remaining edge reaches.
+/// Among the vertices ready at the same time, the ones added first come first.
+///
+/// Fails with one of the cycles when there is no such order. Every edge of an
+/// undirected graph makes a cycle.
+pub fn topological_order<T, E>(graph: &Graph<T, E>) -> Result<Vec<VertexId>, Cycle> {
+    let vertex_ids = graph.vertex_ids();
+    let mut in_degrees = vertex_ids
+        .iter()
+        .map(|vertex_id| (*vertex_id, 0))
+        .collect::<HashMap<_, usize>>();
+    for (_, to, _) in graph.edges() {
+        *in_degrees.entry(to).or_default() += 1;
+    }
+
+    let mut ready = vertex_ids
+        .iter()
+        .copied()
+        .filter(|vertex_id| in_degrees[vertex_id] == 0)
+        .collect::<VecDeque<_>>();
+    let mut order = Vec::with_capacity(vertex_ids.len());
+    while let Some(vertex_id) = ready.pop_front() {
+        order.push(vertex_id);
+        for to in successors(graph, vertex_id) {
+            let in_degree = in_degrees.entry(to).or_default();
+            *in_degree -= 1;
+            if *in_degree == 0 {
+                ready.push_back(to);
+            }
+        }
+    }
+
+    if order.len() == vertex_ids.len() {
+        Ok(order)
+    } else {
+        let remaining = in_degrees
+            .into_iter()
+            .filter(|(_, in_degree)| *in_degree > 0)
+            .map(|(vertex_id, _)| vertex_id)
+            .collect();
+        Err(find_cycle(graph, &remaining))
+    }
+}
+
+/// Finds a cycle among vertices that are each reached by an edge from another one of
+/// them, by walking those edges backwards until a vertex shows up twice.
+fn find_cycle<T, E>(graph: &Graph<T, E>, remaining: &HashSet<VertexId>) -> Cycle {
+    let mut predecessors = HashMap::new();
+    for (from, to, _) in graph.edges() {
+        if remaining.contains(&from) && remaining.contains(&to) {
+            predecessors.entry(to).or_insert(from);
+        }
+    }
+
+    let mut seen = HashSet::new();
+    let mut vertex_id = *remaining
+        .iter()
+        .min()
+        .expect("There are vertices left when Kahn's algorithm finds a cycle");
+    while seen.insert(vertex_id) {
+        vertex_id = predecessors[&vertex_id];
+    }
+
+    let mut vertex_ids = vec![vertex_id];
+    let mut predecessor = predecessors[&vertex_id];
+    while predecessor != vertex_id {
+        vertex_ids.push(predecessor);
+        predecessor = predecessors[&predecessor];
+    }
+    vertex_ids.reverse();
+
+    let lowest = (0..vertex_ids.len())
+        .min_by_key(|i| vertex_ids[*i])
+        .unwrap_or_default();
+    vertex_ids.rotate_left(lowest);
+    Cycle { vertex_ids }
+}
+
+pub(crate) fn successors<T, E>(graph: &Graph<T, E>, vertex_id: VertexId) -> Vec<VertexId> {
+    graph
+        .get_vertex(&vertex_id)
+        .map_or_else(Vec::new, |get_vertex| {
+            get_vertex
+                .get_neighbors()
+                .iter()
+                .map(|get_neighbor| get_neighbor.get_id())
+                .collect()
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Cycle, topological_order};
+    use crate::chapter_6_graph::Graph;
+
+    #[test]
+    fn should_order_tasks_after_their_dependencies() {
+        let mut graph = Graph::new();
+        let compile = graph.add_vertex("compile");
+        let link = graph.add_vertex("link");
+        let fetch = graph.add_vertex("fetch");
+        let test = graph.add_vertex("test");
+        let lint = graph.add_vertex("lint");
+
+        graph.attach(&fetch, &compile);
+        graph.attach(&compile, &link);
+        graph.attach(&link, &test);
+        graph.attach(&fetch, &lint);
+
+        let order = topological_order(&graph).expect("Should be acyclic");
+
+        assert_eq!(order, vec![fetch, compile, lint, link, test]);
+    }
+
+    #[test]
+    fn should_report_cycle() {
+        let mut graph = Graph::new();
+        let a = graph.add_vertex("A");
+        let b = graph.add_vertex("B");
+        let c = graph.add_vertex("C");
+        let d = graph.add_vertex("D");
+        let e = graph.add_vertex("E");
+
+        graph.attach(&a, &b);
+        graph.attach(&b, &c);
+        graph.attach(&c, &d);
+        graph.attach(&d, &b);
+        graph.attach(&d, &e);
+
+        let cycle = topological_order(&graph).err().unwrap();
+
+        assert_eq!(
+            cycle,
+            Cycle {
+                vertex_ids: vec![b, c, d]
+            }
+        );
+        assert_eq!(cycle.to_string(), "the graph has a cycle: 1 -> 2 -> 3 -> 1");
+    }
+
+    #[test]
+    fn should_report_self_loop() {
+        let mut graph = Graph::new();
+        let a = graph.add_vertex("A");
+
+        graph.attach(&a, &a);
+
+        assert_eq!(topological_order(&graph).err().unwrap().vertex_ids, vec![a]);
+    }
+}
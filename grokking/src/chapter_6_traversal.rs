@@ -0,0 +1,398 @@
+use crate::chapter_6_graph::{GraphView, Vertex, VertexId};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::ops::ControlFlow;
+use std::vec;
+
+/// What a depth first search runs into, in the order it runs into it.
+///
+/// Every edge that leads to a vertex not discovered yet is a tree edge. The others are
+/// classified by the state of the vertex they reach: one still being explored makes a
+/// back edge, which closes a cycle, and one already finished makes a forward edge when
+/// it was discovered from the current vertex, or a cross edge otherwise.
+///
+/// In undirected graphs, the edge back to the parent is not reported, and every other
+/// edge outside of the tree is reported once, as a back edge.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DfsEvent {
+    Discover(VertexId),
+    TreeEdge {
+        from: VertexId,
+        to: VertexId,
+    },
+    BackEdge {
+        from: VertexId,
+        to: VertexId,
+    },
+    ForwardEdge {
+        from: VertexId,
+        to: VertexId,
+    },
+    CrossEdge {
+        from: VertexId,
+        to: VertexId,
+    },
+    /// Every vertex reachable from this one has been discovered and finished.
+    Finish(VertexId),
+}
+
+/// Walks a graph depth first, yielding [`DfsEvent`]s as they happen. Built with
+/// [`depth_first_events`].
+pub struct DepthFirstEvents<'a, G: GraphView> {
+    graph: &'a G,
+    roots: vec::IntoIter<VertexId>,
+    /// Each frame holds a vertex, the vertex it was discovered from and how many of its
+    /// legs were followed.
+    stack: Vec<(VertexId, Option<VertexId>, usize)>,
+    /// The order in which each vertex was discovered.
+    discovered: HashMap<VertexId, usize>,
+    finished: HashSet<VertexId>,
+    /// Discovering a vertex takes two events: the tree edge and the discovery.
+    pending: Option<DfsEvent>,
+}
+
+/// Searches the graph depth first from each of the roots in turn, skipping the ones an
+/// earlier root reached. Pass [`GraphView::vertex_ids`] to search the whole graph.
+///
+/// The search keeps its own stack, so it does not overflow on long paths.
+pub fn depth_first_events<G: GraphView>(
+    graph: &G,
+    roots: impl IntoIterator<Item = VertexId>,
+) -> DepthFirstEvents<'_, G> {
+    DepthFirstEvents {
+        graph,
+        roots: roots.into_iter().collect::<Vec<_>>().into_iter(),
+        stack: Vec::new(),
+        discovered: HashMap::new(),
+        finished: HashSet::new(),
+        pending: None,
+    }
+}
+
+/// Searches the graph depth first like [`depth_first_events`], handing every event to
+/// the visitor. The search stops as soon as the visitor breaks, returning its value.
+pub fn depth_first_visit<G: GraphView, B>(
+    graph: &G,
+    roots: impl IntoIterator<Item = VertexId>,
+    mut visitor: impl FnMut(DfsEvent) -> ControlFlow<B>,
+) -> ControlFlow<B> {
+    for event in depth_first_events(graph, roots) {
+        visitor(event)?;
+    }
+    ControlFlow::Continue(())
+}
+
+/// The vertices reachable from the start, in the order a depth first search discovers
+/// them: each vertex comes right after the one it was first reached from, once every
+/// vertex reachable through the earlier legs of that one has come.
+pub fn depth_first_search<'a, G: GraphView>(
+    graph: &'a G,
+    start: &VertexId,
+) -> impl Iterator<Item = &'a Vertex<G::Item>> {
+    depth_first_events(graph, [*start]).filter_map(|event| match event {
+        DfsEvent::Discover(vertex_id) => graph.vertex(&vertex_id),
+        _ => None,
+    })
+}
+
+impl<G: GraphView> DepthFirstEvents<'_, G> {
+    fn discover(&mut self, vertex_id: VertexId, parent: Option<VertexId>) -> DfsEvent {
+        self.discovered.insert(vertex_id, self.discovered.len());
+        self.stack.push((vertex_id, parent, 0));
+        DfsEvent::Discover(vertex_id)
+    }
+
+    /// Classifies an edge to a vertex that was already discovered, if it is reported.
+    fn classify(&self, from: VertexId, to: VertexId, parent: Option<VertexId>) -> Option<DfsEvent> {
+        let directed = self.graph.is_directed();
+        if !self.finished.contains(&to) {
+            if !directed && parent == Some(to) {
+                return None;
+            }
+            Some(DfsEvent::BackEdge { from, to })
+        } else if !directed {
+            // Already reported from the other end, as a back edge.
+            None
+        } else if self.discovered[&to] > self.discovered[&from] {
+            Some(DfsEvent::ForwardEdge { from, to })
+        } else {
+            Some(DfsEvent::CrossEdge { from, to })
+        }
+    }
+}
+
+impl<G: GraphView> Iterator for DepthFirstEvents<'_, G> {
+    type Item = DfsEvent;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(event) = self.pending.take() {
+            return Some(event);
+        }
+
+        loop {
+            let Some((from, parent, followed)) = self.stack.last_mut() else {
+                let graph = self.graph;
+                let discovered = &self.discovered;
+                let root = self
+                    .roots
+                    .find(|root| graph.contains_vertex(root) && !discovered.contains_key(root))?;
+                return Some(self.discover(root, None));
+            };
+            let (from, parent) = (*from, *parent);
+
+            let Some(leg) = self.graph.legs(&from).get(*followed) else {
+                self.stack.pop();
+                self.finished.insert(from);
+                return Some(DfsEvent::Finish(from));
+            };
+            *followed += 1;
+
+            let to = leg.get_to_id();
+            if !self.discovered.contains_key(&to) {
+                self.pending = Some(self.discover(to, Some(from)));
+                return Some(DfsEvent::TreeEdge { from, to });
+            }
+            if let Some(event) = self.classify(from, to, parent) {
+                return Some(event);
+            }
+        }
+    }
+}
+
+/// A vertex reached by a breadth first search, with how it was reached.
+pub struct BfsStep<'a, T> {
+    pub vertex: &'a Vertex<T>,
+    /// How many edges separate the vertex from the start.
+    pub depth: usize,
+    /// The vertex it was first reached from. The start has none.
+    pub parent: Option<VertexId>,
+}
+
+/// Walks a graph breadth first, yielding [`BfsStep`]s. Built with [`breadth_first_search`].
+pub struct BreadthFirst<'a, G: GraphView> {
+    graph: &'a G,
+    queue: VecDeque<(VertexId, usize, Option<VertexId>)>,
+    visited: HashSet<VertexId>,
+}
+
+/// The vertices reachable from the start, the closest ones first. Vertices at the same
+/// depth come in the order their parents came, then in the order they were attached.
+pub fn breadth_first_search<'a, G: GraphView>(
+    graph: &'a G,
+    start: &VertexId,
+) -> BreadthFirst<'a, G> {
+    let mut queue = VecDeque::new();
+    let mut visited = HashSet::new();
+    if graph.contains_vertex(start) {
+        queue.push_back((*start, 0, None));
+        visited.insert(*start);
+    }
+    BreadthFirst {
+        graph,
+        queue,
+        visited,
+    }
+}
+
+impl<'a, G: GraphView> Iterator for BreadthFirst<'a, G> {
+    type Item = BfsStep<'a, G::Item>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (vertex_id, depth, parent) = self.queue.pop_front()?;
+        for leg in self.graph.legs(&vertex_id) {
+            let to = leg.get_to_id();
+            if self.visited.insert(to) {
+                self.queue.push_back((to, depth + 1, Some(vertex_id)));
+            }
+        }
+
+        Some(BfsStep {
+            vertex: self.graph.vertex(&vertex_id)?,
+            depth,
+            parent,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DfsEvent, breadth_first_search, depth_first_events, depth_first_search};
+    use crate::chapter_6_graph::{Graph, VertexId};
+    use std::collections::HashSet;
+    use std::ops::ControlFlow;
+
+    #[test]
+    fn should_discover_deepest_vertices_first() {
+        let mut graph = Graph::new();
+        let a = graph.add_vertex("A");
+        let b = graph.add_vertex("B");
+        let c = graph.add_vertex("C");
+        let d = graph.add_vertex("D");
+        let e = graph.add_vertex("E");
+
+        graph.attach(&a, &b);
+        graph.attach(&a, &c);
+        graph.attach(&b, &d);
+        graph.attach(&c, &e);
+        graph.attach(&d, &c);
+
+        let order = graph
+            .depth_search_iterator(&a)
+            .map(|get_vertex| *get_vertex.get_item())
+            .collect::<Vec<_>>();
+
+        assert_eq!(order, vec!["A", "B", "D", "C", "E"]);
+    }
+
+    #[test]
+    fn should_classify_directed_edges() {
+        let mut graph = Graph::new();
+        let a = graph.add_vertex("A");
+        let b = graph.add_vertex("B");
+        let c = graph.add_vertex("C");
+        let d = graph.add_vertex("D");
+
+        graph.attach(&a, &b);
+        graph.attach(&b, &c);
+        graph.attach(&c, &a);
+        graph.attach(&a, &c);
+        graph.attach(&d, &b);
+
+        let events = depth_first_events(&graph, graph.vertex_ids()).collect::<Vec<_>>();
+
+        assert_eq!(
+            events,
+            vec![
+                DfsEvent::Discover(a),
+                DfsEvent::TreeEdge { from: a, to: b },
+                DfsEvent::Discover(b),
+                DfsEvent::TreeEdge { from: b, to: c },
+                DfsEvent::Discover(c),
+                DfsEvent::BackEdge { from: c, to: a },
+                DfsEvent::Finish(c),
+                DfsEvent::Finish(b),
+                DfsEvent::ForwardEdge { from: a, to: c },
+                DfsEvent::Finish(a),
+                DfsEvent::Discover(d),
+                DfsEvent::CrossEdge { from: d, to: b },
+                DfsEvent::Finish(d),
+            ]
+        );
+    }
+
+    #[test]
+    fn should_report_undirected_cycles_once() {
+        let mut graph = Graph::undirected();
+        let a = graph.add_vertex("A");
+        let b = graph.add_vertex("B");
+        let c = graph.add_vertex("C");
+
+        graph.attach(&a, &b);
+        graph.attach(&b, &c);
+        graph.attach(&c, &a);
+
+        let non_tree_edges = depth_first_events(&graph, [a])
+            .filter(|event| {
+                !matches!(
+                    event,
+                    DfsEvent::Discover(_) | DfsEvent::TreeEdge { .. } | DfsEvent::Finish(_)
+                )
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!(non_tree_edges, vec![DfsEvent::BackEdge { from: c, to: a }]);
+    }
+
+    #[test]
+    fn should_stop_visiting_when_asked_to() {
+        let mut graph = Graph::new();
+        let ids = (0..6).map(|i| graph.add_vertex(i)).collect::<Vec<_>>();
+        graph.attach(&ids[4], &ids[2]);
+        for pair in ids.windows(2) {
+            graph.attach(&pair[0], &pair[1]);
+        }
+
+        let mut discovered = 0;
+        let cycle = graph.depth_first_visit(graph.vertex_ids(), |event| match event {
+            DfsEvent::Discover(_) => {
+                discovered += 1;
+                ControlFlow::Continue(())
+            }
+            DfsEvent::BackEdge { from, to } => ControlFlow::Break((from, to)),
+            _ => ControlFlow::Continue(()),
+        });
+
+        assert_eq!(cycle, ControlFlow::Break((ids[4], ids[2])));
+        assert_eq!(discovered, 5);
+    }
+
+    #[test]
+    fn should_survive_long_paths() {
+        let mut graph = Graph::new();
+        let ids = (0..100_000)
+            .map(|i| graph.add_vertex(i))
+            .collect::<Vec<_>>();
+        for pair in ids.windows(2) {
+            graph.attach(&pair[0], &pair[1]);
+        }
+
+        let reached = depth_first_search(&graph, &ids[0])
+            .map(|vertex| vertex.id)
+            .collect::<Vec<_>>();
+
+        assert_eq!(reached, ids);
+    }
+
+    #[test]
+    fn should_report_depth_and_parent() {
+        let mut graph = Graph::new();
+        let a = graph.add_vertex("A");
+        let b = graph.add_vertex("B");
+        let c = graph.add_vertex("C");
+        let d = graph.add_vertex("D");
+        let unreachable = graph.add_vertex("Unreachable");
+
+        graph.attach(&a, &b);
+        graph.attach(&a, &c);
+        graph.attach(&b, &d);
+        graph.attach(&c, &d);
+        graph.attach(&unreachable, &a);
+
+        let steps = breadth_first_search(&graph, &a)
+            .map(|step| (step.vertex.id, step.depth, step.parent))
+            .collect::<Vec<_>>();
+
+        assert_eq!(
+            steps,
+            vec![
+                (a, 0, None),
+                (b, 1, Some(a)),
+                (c, 1, Some(a)),
+                (d, 2, Some(b))
+            ]
+        );
+    }
+
+    #[test]
+    fn should_find_reachable_vertices_of_frozen_graph() {
+        let mut graph = Graph::new();
+        let ids = (0..5).map(|i| graph.add_vertex(i)).collect::<Vec<_>>();
+        graph.attach(&ids[0], &ids[1]);
+        graph.attach(&ids[1], &ids[2]);
+        graph.attach(&ids[3], &ids[4]);
+        let frozen = graph.freeze();
+
+        let reachable = |start: VertexId| {
+            breadth_first_search(&frozen, &start)
+                .map(|step| step.vertex.id)
+                .collect::<HashSet<_>>()
+        };
+
+        assert_eq!(reachable(ids[0]), HashSet::from([ids[0], ids[1], ids[2]]));
+        assert_eq!(reachable(ids[3]), HashSet::from([ids[3], ids[4]]));
+        assert_eq!(
+            frozen.depth_search_iterator(&ids[0]).count(),
+            frozen.breadth_first_search(&ids[0]).count()
+        );
+        assert!(frozen.is_directed());
+    }
+}
@@ -1,46 +1,139 @@
 use std::cmp::Ordering;
 use std::collections::LinkedList;
+use std::error::Error;
+use std::fmt;
+use std::marker::PhantomData;
 use std::mem;
+use std::ops::{Bound, RangeBounds};
+
+/// The key extractor a tree uses unless built with a closure.
+pub type ExtractKey<K, T> = fn(&T) -> &K;
+
+/// What adding an item does when the tree already holds one with the same key.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DuplicateKeys {
+    /// Keeps every item, like a multiset. Items with the same key iterate in no
+    /// particular order.
+    #[default]
+    Keep,
+    /// Keeps the item already there and gives the new one back.
+    Reject,
+    /// Puts the new item in place of the one already there and gives that one back.
+    Replace,
+}
 
-type ExtractKey<K, T> = fn(&T) -> &K;
+/// A broken invariant found by [`AVLTree::debug_validate`], at the node with the
+/// given position in key order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InvariantViolation {
+    /// The key of the node comes before the key of the node preceding it.
+    OutOfOrder { position: usize },
+    WrongHeight {
+        position: usize,
+        cached: u16,
+        actual: u16,
+    },
+    /// The heights of the children differ by more than one.
+    Unbalanced {
+        position: usize,
+        balance_factor: i32,
+    },
+    WrongSize {
+        position: usize,
+        cached: usize,
+        actual: usize,
+    },
+}
+
+impl fmt::Display for InvariantViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InvariantViolation::OutOfOrder { position } => {
+                write!(f, "node {position}: its key comes before the previous one")
+            }
+            InvariantViolation::WrongHeight {
+                position,
+                cached,
+                actual,
+            } => write!(
+                f,
+                "node {position}: cached height {cached}, actual {actual}"
+            ),
+            InvariantViolation::Unbalanced {
+                position,
+                balance_factor,
+            } => write!(f, "node {position}: balance factor {balance_factor}"),
+            InvariantViolation::WrongSize {
+                position,
+                cached,
+                actual,
+            } => write!(f, "node {position}: cached size {cached}, actual {actual}"),
+        }
+    }
+}
+
+impl Error for InvariantViolation {}
 
 #[derive(Clone)]
-struct Node<K: Ord, T> {
+struct Node<T> {
     item: T,
     height: u16,
-    left: Option<Box<Node<K, T>>>,
-    right: Option<Box<Node<K, T>>>,
-    extract_key: ExtractKey<K, T>,
+    /// How many items the subtree rooted here holds, this one included.
+    size: usize,
+    left: Option<Box<Node<T>>>,
+    right: Option<Box<Node<T>>>,
 }
 
-impl<K: Ord, T> Node<K, T> {
-    fn new(item: T, extract_key: ExtractKey<K, T>) -> Box<Self> {
+impl<T> Node<T> {
+    fn new(item: T) -> Box<Self> {
         Box::new(Node {
             item,
             left: None,
             right: None,
-            height: 0,
-            extract_key,
+            height: 1,
+            size: 1,
         })
     }
 
+    /// Updates the height and the size after the children changed.
     fn update_height(&mut self) {
         let left_height = self.left.as_ref().map_or(0, |node| node.height);
         let right_height = self.right.as_ref().map_or(0, |node| node.height);
         self.height = 1 + left_height.max(right_height);
+        self.size = 1 + size_of(&self.left) + size_of(&self.right);
     }
 
-    fn find(&self, lookup_key: &K) -> Option<&Node<K, T>> {
-        let extract_key = self.extract_key;
+    fn find<K: Ord>(&self, extract_key: &impl Fn(&T) -> &K, lookup_key: &K) -> Option<&Node<T>> {
         let self_key = extract_key(&self.item);
 
         match self_key.cmp(lookup_key) {
             Ordering::Equal => Some(self),
-            Ordering::Less => self.right.as_ref().and_then(|node| node.find(lookup_key)),
-            Ordering::Greater => self.left.as_ref().and_then(|node| node.find(lookup_key)),
+            Ordering::Less => self
+                .right
+                .as_ref()
+                .and_then(|node| node.find(extract_key, lookup_key)),
+            Ordering::Greater => self
+                .left
+                .as_ref()
+                .and_then(|node| node.find(extract_key, lookup_key)),
         }
     }
 
+    fn find_mut<K: Ord>(
+        &mut self,
+        extract_key: &impl Fn(&T) -> &K,
+        lookup_key: &K,
+    ) -> Option<&mut Node<T>> {
+        let child = match extract_key(&self.item).cmp(lookup_key) {
+            Ordering::Equal => return Some(self),
+            Ordering::Less => &mut self.right,
+            Ordering::Greater => &mut self.left,
+        };
+        child
+            .as_mut()
+            .and_then(|node| node.find_mut(extract_key, lookup_key))
+    }
+
     fn rotate_right(&mut self) {
         let Some(mut picked) = self.left.take() else {
             return;
@@ -52,6 +145,7 @@ impl<K: Ord, T> Node<K, T> {
         picked.update_height();
 
         self.right = Some(picked);
+        self.update_height();
     }
 
     fn rotate_left(&mut self) {
@@ -65,24 +159,38 @@ impl<K: Ord, T> Node<K, T> {
         picked.update_height();
 
         self.left = Some(picked);
+        self.update_height();
+    }
+
+    /// How much taller the right subtree is than the left one.
+    fn balance_factor(&self) -> i32 {
+        height_of(&self.right) as i32 - height_of(&self.left) as i32
     }
 
     fn balance(&mut self) {
-        let left_height = self.left.as_ref().map_or(0, |node| node.height);
-        let right_height = self.right.as_ref().map_or(0, |node| node.height);
-        let balance_factor = right_height as i16 - left_height as i16;
+        let balance_factor = self.balance_factor();
 
         if balance_factor <= -2 {
+            // A left child leaning right would only lean left after a single rotation.
+            if let Some(left) = self.left.as_mut().filter(|left| left.balance_factor() > 0) {
+                left.rotate_left();
+            }
             self.rotate_right();
         } else if balance_factor >= 2 {
+            if let Some(right) = self
+                .right
+                .as_mut()
+                .filter(|right| right.balance_factor() < 0)
+            {
+                right.rotate_right();
+            }
             self.rotate_left();
         }
 
         self.update_height();
     }
 
-    fn add(&mut self, neighbor: Box<Self>) {
-        let extract_key = self.extract_key;
+    fn add<K: Ord>(&mut self, extract_key: &impl Fn(&T) -> &K, neighbor: Box<Self>) {
         let self_key = extract_key(&self.item);
         let lookup_key = extract_key(&neighbor.item);
 
@@ -92,7 +200,7 @@ impl<K: Ord, T> Node<K, T> {
         };
 
         match child {
-            Some(child) => child.add(neighbor),
+            Some(child) => child.add(extract_key, neighbor),
             None => *child = Some(neighbor),
         }
 
@@ -101,7 +209,8 @@ impl<K: Ord, T> Node<K, T> {
 
     fn pop_min(mut this: Box<Self>) -> (Option<Box<Self>>, Box<Self>) {
         match this.left.take() {
-            None => (None, this),
+            // The minimum has no left child, but its right child must stay in the tree.
+            None => (this.right.take(), this),
             Some(left) => {
                 let (new_left, popped) = Node::pop_min(left);
                 this.left = new_left;
@@ -111,8 +220,11 @@ impl<K: Ord, T> Node<K, T> {
         }
     }
 
-    fn pop(mut this: Box<Self>, lookup_key: &K) -> (Option<Box<Self>>, Option<Box<Self>>) {
-        let extract_key = this.extract_key;
+    fn pop<K: Ord>(
+        mut this: Box<Self>,
+        extract_key: &impl Fn(&T) -> &K,
+        lookup_key: &K,
+    ) -> (Option<Box<Self>>, Option<Box<Self>>) {
         let self_key = extract_key(&this.item);
 
         match self_key.cmp(lookup_key) {
@@ -135,7 +247,7 @@ impl<K: Ord, T> Node<K, T> {
                         let (right, mut min_node) = Node::pop_min(right);
                         min_node.left = Some(left);
                         min_node.right = right;
-                        min_node.update_height();
+                        min_node.balance();
                         (Some(min_node), Some(this))
                     }
                 }
@@ -144,7 +256,7 @@ impl<K: Ord, T> Node<K, T> {
                 let Some(right) = this.right.take() else {
                     return (Some(this), None);
                 };
-                let (new_right, popped) = Node::pop(right, lookup_key);
+                let (new_right, popped) = Node::pop(right, extract_key, lookup_key);
                 this.right = new_right;
                 this.balance();
 
@@ -154,7 +266,7 @@ impl<K: Ord, T> Node<K, T> {
                 let Some(left) = this.left.take() else {
                     return (Some(this), None);
                 };
-                let (new_left, popped) = Node::pop(left, lookup_key);
+                let (new_left, popped) = Node::pop(left, extract_key, lookup_key);
                 this.left = new_left;
                 this.balance();
 
@@ -162,98 +274,635 @@ impl<K: Ord, T> Node<K, T> {
             }
         }
     }
+
+    /// Builds a balanced subtree out of the next `len` items, keeping their order.
+    fn build(items: &mut impl Iterator<Item = T>, len: usize) -> Option<Box<Self>> {
+        if len == 0 {
+            return None;
+        }
+        let left = Node::build(items, len / 2);
+        let mut node = Node::new(items.next()?);
+        node.left = left;
+        node.right = Node::build(items, len - len / 2 - 1);
+        node.update_height();
+        Some(node)
+    }
+
+    /// Puts the node between two subtrees, where every item on the left comes before
+    /// it and every item on the right after it. Walks down the taller subtree until
+    /// the heights match, so it takes `O(log n)`.
+    fn join(left: Option<Box<Self>>, mut middle: Box<Self>, right: Option<Box<Self>>) -> Box<Self> {
+        match (left, right) {
+            (Some(mut left), right) if left.height > height_of(&right) + 1 => {
+                left.right = Some(Node::join(left.right.take(), middle, right));
+                left.balance();
+                left
+            }
+            (left, Some(mut right)) if right.height > height_of(&left) + 1 => {
+                right.left = Some(Node::join(left, middle, right.left.take()));
+                right.balance();
+                right
+            }
+            (left, right) => {
+                middle.left = left;
+                middle.right = right;
+                middle.update_height();
+                middle
+            }
+        }
+    }
+
+    /// Joins two subtrees where every item on the left comes before every item on
+    /// the right.
+    fn join_two(left: Option<Box<Self>>, right: Option<Box<Self>>) -> Option<Box<Self>> {
+        let Some(right) = right else {
+            return left;
+        };
+        let (right, min_node) = Node::pop_min(right);
+        Some(Node::join(left, min_node, right))
+    }
+
+    /// Splits the subtree in the items that go left and the ones that do not, for a
+    /// test that passes for every item up to some point and for none after it.
+    fn split(
+        node: Option<Box<Self>>,
+        goes_left: &impl Fn(&T) -> bool,
+    ) -> (Option<Box<Self>>, Option<Box<Self>>) {
+        let Some(mut node) = node else {
+            return (None, None);
+        };
+        let left = node.left.take();
+        let right = node.right.take();
+
+        if goes_left(&node.item) {
+            let (lower, upper) = Node::split(right, goes_left);
+            (Some(Node::join(left, node, lower)), upper)
+        } else {
+            let (lower, upper) = Node::split(left, goes_left);
+            (lower, Some(Node::join(upper, node, right)))
+        }
+    }
+
+    /// Takes the subtree apart into its items in order, without recursing.
+    fn into_items(node: Option<Box<Self>>) -> Vec<T> {
+        let mut items = Vec::with_capacity(size_of(&node));
+        let mut stack = Vec::new();
+        let mut node = node;
+        loop {
+            while let Some(mut current) = node {
+                node = current.left.take();
+                stack.push(current);
+            }
+            let Some(mut current) = stack.pop() else {
+                return items;
+            };
+            node = current.right.take();
+            items.push(current.item);
+        }
+    }
+}
+
+fn size_of<T>(node: &Option<Box<Node<T>>>) -> usize {
+    node.as_ref().map_or(0, |node| node.size)
 }
 
-struct AVLTree<K: Ord, T> {
-    root: Option<Box<Node<K, T>>>,
-    extract_key: ExtractKey<K, T>,
+fn height_of<T>(node: &Option<Box<Node<T>>>) -> u16 {
+    node.as_ref().map_or(0, |node| node.height)
+}
+
+/// A balanced binary search tree of items ordered by the key `extract_key` reads from
+/// them, which can be a function or a closure.
+pub struct AVLTree<K: Ord, T, F = ExtractKey<K, T>> {
+    root: Option<Box<Node<T>>>,
+    extract_key: F,
+    duplicate_keys: DuplicateKeys,
+    key: PhantomData<fn(&T) -> &K>,
 }
 
 impl<T: Ord> AVLTree<T, T> {
-    fn empty() -> Self {
-        AVLTree {
-            root: None,
-            extract_key: |v| v,
-        }
+    pub fn empty() -> Self {
+        AVLTree::new(|v| v)
     }
 }
 
-impl<K: Ord, T> AVLTree<K, T> {
-    fn new(extract_key: ExtractKey<K, T>) -> Self {
+impl<K: Ord, T, F: Fn(&T) -> &K> AVLTree<K, T, F> {
+    pub fn new(extract_key: F) -> Self {
         AVLTree {
             root: None,
             extract_key,
+            duplicate_keys: DuplicateKeys::default(),
+            key: PhantomData,
         }
     }
 
-    fn add(&mut self, item: T) {
-        if let Some(root) = &mut self.root {
-            let neighbor = Node::new(item, self.extract_key);
-            root.add(neighbor);
-        } else {
-            self.root = Some(Node::new(item, self.extract_key));
+    /// Sets what adding an item whose key is already in the tree does. Items already in
+    /// the tree stay as they are, so only trees that keep duplicates can hold equal keys.
+    pub fn with_duplicate_keys(mut self, duplicate_keys: DuplicateKeys) -> Self {
+        self.duplicate_keys = duplicate_keys;
+        self
+    }
+
+    pub fn duplicate_keys(&self) -> DuplicateKeys {
+        self.duplicate_keys
+    }
+
+    /// Adds the item, returning the one left out of the tree, if any: the item replaced
+    /// or the one rejected, depending on the [`DuplicateKeys`] policy.
+    pub fn add(&mut self, item: T) -> Option<T> {
+        let key = (self.extract_key)(&item);
+        match self.duplicate_keys {
+            DuplicateKeys::Keep => {}
+            DuplicateKeys::Reject if self.contains(key) => return Some(item),
+            DuplicateKeys::Reject => {}
+            DuplicateKeys::Replace => {
+                if let Some(existing) = self.find_mut(key) {
+                    return Some(mem::replace(existing, item));
+                }
+            }
         }
+
+        match &mut self.root {
+            Some(root) => root.add(&self.extract_key, Node::new(item)),
+            None => self.root = Some(Node::new(item)),
+        }
+        None
     }
 
-    fn contains(&self, key: &K) -> bool {
+    pub fn contains(&self, key: &K) -> bool {
         self.find(key).is_some()
     }
 
-    fn find_node(&self, key: &K) -> Option<&Node<K, T>> {
-        self.root.as_ref().and_then(|root| root.find(key))
+    fn find_node(&self, key: &K) -> Option<&Node<T>> {
+        self.root
+            .as_ref()
+            .and_then(|root| root.find(&self.extract_key, key))
     }
 
-    fn find(&self, key: &K) -> Option<&T> {
+    pub fn find(&self, key: &K) -> Option<&T> {
         self.find_node(key).map(|node| &node.item)
     }
 
-    fn height(&self) -> u16 {
+    /// The item with the given key, to change anything but its key.
+    pub(crate) fn find_mut(&mut self, key: &K) -> Option<&mut T> {
+        let extract_key = &self.extract_key;
+        self.root
+            .as_mut()
+            .and_then(|root| root.find_mut(extract_key, key))
+            .map(|node| &mut node.item)
+    }
+
+    /// How many items have the given key, which is at most one unless duplicate keys
+    /// are kept.
+    pub fn count(&self, key: &K) -> usize {
+        self.count_where(|item_key| item_key <= key) - self.rank(key)
+    }
+
+    pub fn height(&self) -> u16 {
         self.root.as_ref().map_or(0, |root| root.height)
     }
 
-    fn iter(&self) -> AVLItemIterator<K, T> {
-        AVLItemIterator::new(AVLNodeIterator::new(self))
+    pub fn len(&self) -> usize {
+        size_of(&self.root)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.root.is_none()
     }
 
-    fn pop(&mut self, key: &K) -> Option<T> {
-        let (root, popped) = Node::pop(self.root.take()?, key);
+    /// Iterates over the items in key order. Iterates backwards with `rev`.
+    pub fn iter(&self) -> AVLItemIterator<'_, T> {
+        AVLItemIterator::new(AVLNodeIterator::new(self, ..))
+    }
+
+    /// Iterates over the items whose keys fall within the range, in key order.
+    ///
+    /// ```
+    /// use grokking::collections::AVLTree;
+    ///
+    /// let tree = (0..10).collect::<AVLTree<_, _>>();
+    /// assert_eq!(tree.range(3..6).copied().collect::<Vec<_>>(), vec![3, 4, 5]);
+    /// assert_eq!(tree.range(..=2).rev().copied().collect::<Vec<_>>(), vec![2, 1, 0]);
+    /// ```
+    pub fn range<R: RangeBounds<K>>(&self, range: R) -> AVLItemIterator<'_, T> {
+        AVLItemIterator::new(AVLNodeIterator::new(self, range))
+    }
+
+    /// The item with the lowest key.
+    pub fn first(&self) -> Option<&T> {
+        self.iter().next()
+    }
+
+    /// The item with the highest key.
+    pub fn last(&self) -> Option<&T> {
+        self.iter().next_back()
+    }
+
+    /// The item with the highest key not above the given one.
+    pub fn floor(&self, key: &K) -> Option<&T> {
+        self.last_where(|item_key| item_key <= key)
+    }
+
+    /// The item with the lowest key not below the given one.
+    pub fn ceiling(&self, key: &K) -> Option<&T> {
+        self.first_where(|item_key| item_key >= key)
+    }
+
+    /// The item with the highest key below the given one.
+    pub fn predecessor(&self, key: &K) -> Option<&T> {
+        self.last_where(|item_key| item_key < key)
+    }
+
+    /// The item with the lowest key above the given one.
+    pub fn successor(&self, key: &K) -> Option<&T> {
+        self.first_where(|item_key| item_key > key)
+    }
+
+    /// How many items have a key below the given one, which is the position the key
+    /// has or would have in key order. Takes `O(log n)`.
+    pub fn rank(&self, key: &K) -> usize {
+        self.count_where(|item_key| item_key < key)
+    }
+
+    /// The item at the given position in key order, counting from zero. Takes `O(log n)`.
+    pub fn select(&self, mut position: usize) -> Option<&T> {
+        let mut node = self.root.as_deref()?;
+        loop {
+            let left_size = size_of(&node.left);
+            node = match position.cmp(&left_size) {
+                Ordering::Equal => return Some(&node.item),
+                Ordering::Less => node.left.as_deref()?,
+                Ordering::Greater => {
+                    position -= left_size + 1;
+                    node.right.as_deref()?
+                }
+            };
+        }
+    }
+
+    /// The item at the given position in key order, to change anything but its key.
+    pub(crate) fn select_mut(&mut self, mut position: usize) -> Option<&mut T> {
+        let mut node = self.root.as_deref_mut()?;
+        loop {
+            let left_size = size_of(&node.left);
+            node = match position.cmp(&left_size) {
+                Ordering::Equal => return Some(&mut node.item),
+                Ordering::Less => node.left.as_deref_mut()?,
+                Ordering::Greater => {
+                    position -= left_size + 1;
+                    node.right.as_deref_mut()?
+                }
+            };
+        }
+    }
+
+    fn key_of<'n>(&self, node: &'n Node<T>) -> &'n K {
+        (self.extract_key)(&node.item)
+    }
+
+    /// Counts the items whose key passes a test that passes for every key up to some
+    /// point and for none after it.
+    fn count_where(&self, before: impl Fn(&K) -> bool) -> usize {
+        let mut count = 0;
+        let mut node = self.root.as_deref();
+        while let Some(current) = node {
+            if before(self.key_of(current)) {
+                count += size_of(&current.left) + 1;
+                node = current.right.as_deref();
+            } else {
+                node = current.left.as_deref();
+            }
+        }
+        count
+    }
+
+    /// The last item whose key passes a test that passes for every key up to some point
+    /// and for none after it.
+    fn last_where(&self, before: impl Fn(&K) -> bool) -> Option<&T> {
+        let mut found = None;
+        let mut node = self.root.as_deref();
+        while let Some(current) = node {
+            if before(self.key_of(current)) {
+                found = Some(&current.item);
+                node = current.right.as_deref();
+            } else {
+                node = current.left.as_deref();
+            }
+        }
+        found
+    }
+
+    /// The first item whose key passes a test that fails for every key up to some point
+    /// and passes for all after it.
+    fn first_where(&self, after: impl Fn(&K) -> bool) -> Option<&T> {
+        let mut found = None;
+        let mut node = self.root.as_deref();
+        while let Some(current) = node {
+            if after(self.key_of(current)) {
+                found = Some(&current.item);
+                node = current.left.as_deref();
+            } else {
+                node = current.right.as_deref();
+            }
+        }
+        found
+    }
+
+    pub fn pop(&mut self, key: &K) -> Option<T> {
+        let (root, popped) = Node::pop(self.root.take()?, &self.extract_key, key);
         self.root = root;
         Some(popped?.item)
     }
+
+    /// Checks that the items are in key order and that every node caches the right
+    /// height and size and has children whose heights differ by one at most.
+    /// Takes `O(n)`, so it is meant for tests and debugging.
+    pub fn debug_validate(&self) -> Result<(), InvariantViolation> {
+        let mut previous_key = None;
+        let mut position = 0;
+        self.validate_node(self.root.as_deref(), &mut previous_key, &mut position)
+            .map(|_| ())
+    }
+
+    /// Validates the subtree in order, returning its actual height and size.
+    fn validate_node<'a>(
+        &'a self,
+        node: Option<&'a Node<T>>,
+        previous_key: &mut Option<&'a K>,
+        position: &mut usize,
+    ) -> Result<(u16, usize), InvariantViolation> {
+        let Some(node) = node else {
+            return Ok((0, 0));
+        };
+        let (left_height, left_size) =
+            self.validate_node(node.left.as_deref(), previous_key, position)?;
+
+        let node_position = *position;
+        let key = self.key_of(node);
+        if previous_key.is_some_and(|previous_key| previous_key > key) {
+            return Err(InvariantViolation::OutOfOrder {
+                position: node_position,
+            });
+        }
+        *previous_key = Some(key);
+        *position += 1;
+
+        let (right_height, right_size) =
+            self.validate_node(node.right.as_deref(), previous_key, position)?;
+
+        let height = 1 + left_height.max(right_height);
+        if node.height != height {
+            return Err(InvariantViolation::WrongHeight {
+                position: node_position,
+                cached: node.height,
+                actual: height,
+            });
+        }
+        let balance_factor = right_height as i32 - left_height as i32;
+        if balance_factor.abs() > 1 {
+            return Err(InvariantViolation::Unbalanced {
+                position: node_position,
+                balance_factor,
+            });
+        }
+        let size = 1 + left_size + right_size;
+        if node.size != size {
+            return Err(InvariantViolation::WrongSize {
+                position: node_position,
+                cached: node.size,
+                actual: size,
+            });
+        }
+        Ok((height, size))
+    }
+
+    /// Builds a tree out of items already in key order in `O(n)`, without a single
+    /// rotation.
+    ///
+    /// Panics when an item comes before the one preceding it.
+    pub fn from_sorted_iter<I: IntoIterator<Item = T>>(extract_key: F, items: I) -> Self {
+        let items = items.into_iter().collect::<Vec<_>>();
+        assert!(
+            items
+                .windows(2)
+                .all(|pair| extract_key(&pair[0]) <= extract_key(&pair[1])),
+            "Items should come in key order"
+        );
+        let mut tree = AVLTree::new(extract_key);
+        let len = items.len();
+        tree.root = Node::build(&mut items.into_iter(), len);
+        tree
+    }
+
+    /// Moves the items whose key is at least the given one into a new tree, which
+    /// keeps the same duplicate keys policy. Takes `O(log n)`.
+    pub fn split_off(&mut self, key: &K) -> Self
+    where
+        F: Clone,
+    {
+        let extract_key = &self.extract_key;
+        let (lower, upper) = Node::split(self.root.take(), &|item| extract_key(item) < key);
+        self.root = lower;
+
+        let mut upper_tree =
+            AVLTree::new(self.extract_key.clone()).with_duplicate_keys(self.duplicate_keys);
+        upper_tree.root = upper;
+        upper_tree
+    }
+
+    /// Moves every item of the other tree into this one, leaving the other empty.
+    ///
+    /// Takes `O(log n)` when all the keys of one tree come before the keys of the
+    /// other, and a merge in `O(n + m)` otherwise, where equal keys follow the
+    /// duplicate keys policy of this tree.
+    pub fn append<G: Fn(&T) -> &K>(&mut self, other: &mut AVLTree<K, T, G>) {
+        if self.duplicate_keys != DuplicateKeys::Keep && other.duplicate_keys == DuplicateKeys::Keep
+        {
+            // Only the other tree may hold equal keys, which this one has to collapse first.
+            let items = self.collapse_equal_keys(Node::into_items(other.root.take()));
+            let len = items.len();
+            other.root = Node::build(&mut items.into_iter(), len);
+        }
+
+        let extract_key = &self.extract_key;
+        let comes_before = |lower: Option<&T>, upper: Option<&T>| match (lower, upper) {
+            (Some(lower), Some(upper)) => extract_key(lower) < extract_key(upper),
+            _ => true,
+        };
+
+        if comes_before(self.last(), other.first()) {
+            self.root = Node::join_two(self.root.take(), other.root.take());
+        } else if comes_before(other.last(), self.first()) {
+            self.root = Node::join_two(other.root.take(), self.root.take());
+        } else {
+            let items = Node::into_items(self.root.take());
+            let items = self.merge(items, Node::into_items(other.root.take()));
+            let len = items.len();
+            self.root = Node::build(&mut items.into_iter(), len);
+        }
+    }
+
+    /// The items of both trees, where equal keys follow the duplicate keys policy of
+    /// this tree. Takes `O(n + m)`.
+    pub fn union<G: Fn(&T) -> &K>(mut self, mut other: AVLTree<K, T, G>) -> Self {
+        self.append(&mut other);
+        self
+    }
+
+    /// The items of this tree whose key is in the other tree. Takes `O(n + m)`.
+    pub fn intersection<U, G: Fn(&U) -> &K>(self, other: &AVLTree<K, U, G>) -> Self {
+        self.retain_by_keys(other, true)
+    }
+
+    /// The items of this tree whose key is not in the other tree. Takes `O(n + m)`.
+    pub fn difference<U, G: Fn(&U) -> &K>(self, other: &AVLTree<K, U, G>) -> Self {
+        self.retain_by_keys(other, false)
+    }
+
+    /// Keeps the items whose key is in the other tree, or those whose key is not,
+    /// walking both trees in order at once.
+    fn retain_by_keys<U, G: Fn(&U) -> &K>(mut self, other: &AVLTree<K, U, G>, found: bool) -> Self {
+        let mut other_keys = other.iter().map(&other.extract_key).peekable();
+        let items = Node::into_items(self.root.take())
+            .into_iter()
+            .filter(|item| {
+                let key = (self.extract_key)(item);
+                while other_keys.next_if(|other_key| *other_key < key).is_some() {}
+                (other_keys.peek() == Some(&key)) == found
+            })
+            .collect::<Vec<_>>();
+        let len = items.len();
+        self.root = Node::build(&mut items.into_iter(), len);
+        self
+    }
+
+    /// Keeps one item out of every run with equal keys, following the duplicate keys
+    /// policy: the first one of the run when rejecting, the last one when replacing.
+    fn collapse_equal_keys(&self, items: Vec<T>) -> Vec<T> {
+        let mut collapsed = Vec::<T>::with_capacity(items.len());
+        for item in items {
+            match collapsed.last_mut() {
+                Some(last) if (self.extract_key)(last) == (self.extract_key)(&item) => {
+                    if self.duplicate_keys == DuplicateKeys::Replace {
+                        *last = item;
+                    }
+                }
+                _ => collapsed.push(item),
+            }
+        }
+        collapsed
+    }
+
+    /// Merges two runs of items in key order into one.
+    fn merge(&self, items: Vec<T>, other_items: Vec<T>) -> Vec<T> {
+        let mut merged = Vec::with_capacity(items.len() + other_items.len());
+        let mut items = items.into_iter().peekable();
+        let mut other_items = other_items.into_iter().peekable();
+
+        while let (Some(item), Some(other_item)) = (items.peek(), other_items.peek()) {
+            let ordering = (self.extract_key)(item).cmp((self.extract_key)(other_item));
+            match (ordering, self.duplicate_keys) {
+                (Ordering::Less, _) | (Ordering::Equal, DuplicateKeys::Keep) => {
+                    merged.extend(items.next());
+                }
+                (Ordering::Greater, _) => merged.extend(other_items.next()),
+                (Ordering::Equal, DuplicateKeys::Reject) => {
+                    other_items.next();
+                }
+                (Ordering::Equal, DuplicateKeys::Replace) => {
+                    items.next();
+                }
+            }
+        }
+        merged.extend(items);
+        merged.extend(other_items);
+        merged
+    }
 }
 
-struct AVLNodeIterator<'a, K: Ord, T> {
-    stack: Vec<&'a Node<K, T>>,
+/// Walks the nodes within a range from both ends, keeping the path to the next node
+/// of each end on a stack.
+struct AVLNodeIterator<'a, T> {
+    front: Vec<&'a Node<T>>,
+    back: Vec<&'a Node<T>>,
+    /// How many nodes are left between both ends, so that they stop when they meet.
+    remaining: usize,
 }
 
-impl<'a, K: Ord, T> AVLNodeIterator<'a, K, T> {
-    fn new(tree: &'a AVLTree<K, T>) -> Self {
+impl<'a, T> AVLNodeIterator<'a, T> {
+    fn new<K: Ord, F: Fn(&T) -> &K, R: RangeBounds<K>>(
+        tree: &'a AVLTree<K, T, F>,
+        range: R,
+    ) -> Self {
+        let below_start = |key: &K| match range.start_bound() {
+            Bound::Included(start) => key < start,
+            Bound::Excluded(start) => key <= start,
+            Bound::Unbounded => false,
+        };
+        let up_to_end = |key: &K| match range.end_bound() {
+            Bound::Included(end) => key <= end,
+            Bound::Excluded(end) => key < end,
+            Bound::Unbounded => true,
+        };
+
         let mut iterator = AVLNodeIterator {
-            stack: Vec::with_capacity(tree.height() as usize),
+            front: Vec::with_capacity(tree.height() as usize),
+            back: Vec::with_capacity(tree.height() as usize),
+            remaining: tree
+                .count_where(up_to_end)
+                .saturating_sub(tree.count_where(below_start)),
         };
 
-        if let Some(root) = &tree.root {
-            iterator.push_left_leg(root.as_ref());
+        // Keeps the nodes from which the first node of the range is reached going left.
+        let mut node = tree.root.as_deref();
+        while let Some(current) = node {
+            if below_start(tree.key_of(current)) {
+                node = current.right.as_deref();
+            } else {
+                iterator.front.push(current);
+                node = current.left.as_deref();
+            }
+        }
+
+        let mut node = tree.root.as_deref();
+        while let Some(current) = node {
+            if up_to_end(tree.key_of(current)) {
+                iterator.back.push(current);
+                node = current.right.as_deref();
+            } else {
+                node = current.left.as_deref();
+            }
         }
 
         iterator
     }
 
     // Helper method to push all nodes along the left branch onto the stack
-    fn push_left_leg(&mut self, mut node: &'a Node<K, T>) {
-        self.stack.push(node);
+    fn push_left_leg(&mut self, mut node: &'a Node<T>) {
+        self.front.push(node);
         while let Some(left) = node.left.as_deref() {
-            self.stack.push(left);
+            self.front.push(left);
             node = left;
         }
     }
+
+    fn push_right_leg(&mut self, mut node: &'a Node<T>) {
+        self.back.push(node);
+        while let Some(right) = node.right.as_deref() {
+            self.back.push(right);
+            node = right;
+        }
+    }
 }
 
-impl<'a, K: Ord, T> Iterator for AVLNodeIterator<'a, K, T> {
-    type Item = &'a Node<K, T>;
+impl<'a, T> Iterator for AVLNodeIterator<'a, T> {
+    type Item = &'a Node<T>;
 
     fn next(&mut self) -> Option<Self::Item> {
-        let node = self.stack.pop()?;
+        if self.remaining == 0 {
+            return None;
+        }
+        let node = self.front.pop()?;
+        self.remaining -= 1;
 
         if let Some(right) = node.right.as_deref() {
             self.push_left_leg(right);
@@ -261,29 +910,92 @@ impl<'a, K: Ord, T> Iterator for AVLNodeIterator<'a, K, T> {
 
         Some(node)
     }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<T> DoubleEndedIterator for AVLNodeIterator<'_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.remaining == 0 {
+            return None;
+        }
+        let node = self.back.pop()?;
+        self.remaining -= 1;
+
+        if let Some(left) = node.left.as_deref() {
+            self.push_right_leg(left);
+        }
+
+        Some(node)
+    }
 }
 
-struct AVLItemIterator<'a, K: Ord, T> {
-    avl_node_iterator: AVLNodeIterator<'a, K, T>,
+pub struct AVLItemIterator<'a, T> {
+    avl_node_iterator: AVLNodeIterator<'a, T>,
 }
 
-impl<'a, K: Ord, T> AVLItemIterator<'a, K, T> {
-    fn new(avl_node_iterator: AVLNodeIterator<'a, K, T>) -> Self {
+impl<'a, T> AVLItemIterator<'a, T> {
+    fn new(avl_node_iterator: AVLNodeIterator<'a, T>) -> Self {
         AVLItemIterator { avl_node_iterator }
     }
 }
 
-impl<'a, K: Ord, T> Iterator for AVLItemIterator<'a, K, T> {
+impl<'a, T> Iterator for AVLItemIterator<'a, T> {
     type Item = &'a T;
 
     fn next(&mut self) -> Option<Self::Item> {
         self.avl_node_iterator.next().map(|node| &node.item)
     }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.avl_node_iterator.size_hint()
+    }
+}
+
+impl<T> DoubleEndedIterator for AVLItemIterator<'_, T> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.avl_node_iterator.next_back().map(|node| &node.item)
+    }
+}
+
+impl<T> ExactSizeIterator for AVLItemIterator<'_, T> {}
+
+impl<T: Ord> Default for AVLTree<T, T> {
+    fn default() -> Self {
+        Self::empty()
+    }
+}
+
+impl<K: Ord, T, F: Fn(&T) -> &K> Extend<T> for AVLTree<K, T, F> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for item in iter {
+            self.add(item);
+        }
+    }
+}
+
+impl<T: Ord> FromIterator<T> for AVLTree<T, T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut tree = AVLTree::empty();
+        tree.extend(iter);
+        tree
+    }
+}
+
+impl<'a, K: Ord, T, F: Fn(&T) -> &K> IntoIterator for &'a AVLTree<K, T, F> {
+    type Item = &'a T;
+    type IntoIter = AVLItemIterator<'a, T>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
 }
 
 /// Manual implementation of Drop to avoid the language's default behavior
 /// which could overflow the call stack.
-impl<K: Ord, T> Drop for AVLTree<K, T> {
+impl<K: Ord, T, F> Drop for AVLTree<K, T, F> {
     fn drop(&mut self) {
         let mut list = LinkedList::new();
         if let Some(root) = self.root.take() {
@@ -303,10 +1015,11 @@ impl<K: Ord, T> Drop for AVLTree<K, T> {
 
 #[cfg(test)]
 mod tests {
-    use crate::chapter_8_avl_binary_tree::AVLTree;
+    use crate::chapter_8_avl_binary_tree::{AVLTree, DuplicateKeys};
     use rand::SeedableRng;
     use rand::prelude::SliceRandom;
     use rand::rngs::StdRng;
+    use std::ops::Bound;
 
     #[test]
     fn should_add_and_check_element() {
@@ -321,7 +1034,8 @@ mod tests {
         assert!(tree.contains(&0));
         assert!(!tree.contains(&3));
 
-        assert_eq!(tree.height(), 1);
+        assert_eq!(tree.height(), 2);
+        assert_eq!(tree.debug_validate(), Ok(()));
     }
 
     #[test]
@@ -412,6 +1126,7 @@ mod tests {
             assert!(tree.contains(&i));
         }
         assert_eq!(tree.height(), 8);
+        assert_eq!(tree.debug_validate(), Ok(()));
     }
 
     #[test]
@@ -434,6 +1149,7 @@ mod tests {
         assert!(!tree.contains(&9));
 
         assert_eq!(tree.height(), 4);
+        assert_eq!(tree.debug_validate(), Ok(()));
 
         // Try to pop non-existent elements
         assert_eq!(tree.pop(&20), None);
@@ -449,4 +1165,254 @@ mod tests {
         // Tree should be empty now
         assert_eq!(tree.height(), 0);
     }
+
+    #[test]
+    fn should_iter_within_ranges() {
+        let tree = (0..20).map(|item| item * 2).collect::<AVLTree<_, _>>();
+        let collect =
+            |iterator: super::AVLItemIterator<'_, i32>| iterator.copied().collect::<Vec<_>>();
+
+        assert_eq!(collect(tree.range(5..11)), vec![6, 8, 10]);
+        assert_eq!(collect(tree.range(6..=10)), vec![6, 8, 10]);
+        assert_eq!(collect(tree.range(..3)), vec![0, 2]);
+        assert_eq!(collect(tree.range(35..)), vec![36, 38]);
+        assert_eq!(
+            collect(tree.range((Bound::Excluded(6), Bound::Excluded(12)))),
+            vec![8, 10]
+        );
+        assert_eq!(collect(tree.range(11..11)), Vec::<i32>::new());
+        assert_eq!(collect(tree.range(50..)), Vec::<i32>::new());
+        assert_eq!(tree.range(..).len(), 20);
+    }
+
+    #[test]
+    fn should_iter_from_both_ends() {
+        let tree = (0..100).collect::<AVLTree<_, _>>();
+
+        assert_eq!(
+            tree.iter().rev().copied().collect::<Vec<_>>(),
+            (0..100).rev().collect::<Vec<_>>()
+        );
+
+        let mut range = tree.range(10..15);
+        assert_eq!(range.next(), Some(&10));
+        assert_eq!(range.next_back(), Some(&14));
+        assert_eq!(range.next_back(), Some(&13));
+        assert_eq!(range.next(), Some(&11));
+        assert_eq!(range.len(), 1);
+        assert_eq!(range.next_back(), Some(&12));
+        assert_eq!(range.next(), None);
+        assert_eq!(range.next_back(), None);
+    }
+
+    #[test]
+    fn should_find_neighboring_keys() {
+        let tree = [10, 20, 30, 40].into_iter().collect::<AVLTree<_, _>>();
+
+        assert_eq!(tree.first(), Some(&10));
+        assert_eq!(tree.last(), Some(&40));
+        assert_eq!(tree.floor(&25), Some(&20));
+        assert_eq!(tree.floor(&20), Some(&20));
+        assert_eq!(tree.floor(&5), None);
+        assert_eq!(tree.ceiling(&25), Some(&30));
+        assert_eq!(tree.ceiling(&30), Some(&30));
+        assert_eq!(tree.ceiling(&45), None);
+        assert_eq!(tree.predecessor(&20), Some(&10));
+        assert_eq!(tree.predecessor(&10), None);
+        assert_eq!(tree.successor(&20), Some(&30));
+        assert_eq!(tree.successor(&40), None);
+        assert_eq!(AVLTree::<i32, i32>::empty().first(), None);
+    }
+
+    #[test]
+    fn should_rank_and_select_after_adding_and_popping() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut numbers = (0..300).collect::<Vec<i32>>();
+        numbers.shuffle(&mut rng);
+
+        let mut tree = AVLTree::empty();
+        tree.extend(numbers.iter().copied());
+        for number in numbers.iter().filter(|number| *number % 3 == 0) {
+            tree.pop(number);
+        }
+
+        let expected = (0..300)
+            .filter(|number| number % 3 != 0)
+            .collect::<Vec<_>>();
+        assert_eq!(tree.len(), expected.len());
+        for (position, number) in expected.iter().enumerate() {
+            assert_eq!(tree.select(position), Some(number));
+            assert_eq!(tree.rank(number), position);
+        }
+        assert_eq!(tree.select(expected.len()), None);
+        assert_eq!(tree.rank(&3), 2);
+        assert_eq!(tree.rank(&1000), expected.len());
+    }
+
+    #[test]
+    fn should_rank_duplicate_keys_before_them() {
+        let tree = [1, 2, 2, 2, 3].into_iter().collect::<AVLTree<_, _>>();
+
+        assert_eq!(tree.rank(&2), 1);
+        assert_eq!(tree.rank(&3), 4);
+        assert_eq!(tree.range(2..=2).count(), 3);
+        assert_eq!(tree.select(3), Some(&2));
+    }
+
+    #[test]
+    fn should_follow_duplicate_keys_policy() {
+        let pairs = [(1, 'a'), (2, 'b'), (1, 'c')];
+        let build = |policy| {
+            let mut tree = AVLTree::new(|pair: &(i32, char)| &pair.0).with_duplicate_keys(policy);
+            let left_out = pairs.map(|pair| tree.add(pair));
+            (tree, left_out)
+        };
+
+        let (kept, left_out) = build(DuplicateKeys::Keep);
+        assert_eq!(left_out, [None, None, None]);
+        assert_eq!(kept.count(&1), 2);
+        assert_eq!(kept.len(), 3);
+
+        let (rejected, left_out) = build(DuplicateKeys::Reject);
+        assert_eq!(left_out, [None, None, Some((1, 'c'))]);
+        assert_eq!(rejected.find(&1), Some(&(1, 'a')));
+
+        let (replaced, left_out) = build(DuplicateKeys::Replace);
+        assert_eq!(left_out, [None, None, Some((1, 'a'))]);
+        assert_eq!(replaced.find(&1), Some(&(1, 'c')));
+        assert_eq!(replaced.count(&1), 1);
+    }
+
+    #[test]
+    fn should_extract_keys_with_capturing_closure() {
+        let names = ["carol", "alice", "bob"];
+        let mut tree = AVLTree::new(|index: &usize| &names[*index]);
+        tree.extend([0, 1, 2]);
+
+        assert_eq!(tree.iter().copied().collect::<Vec<_>>(), vec![1, 2, 0]);
+        assert_eq!(tree.find(&"bob"), Some(&2));
+    }
+
+    #[test]
+    fn should_build_balanced_tree_from_sorted_items() {
+        let tree = AVLTree::<i32, i32>::from_sorted_iter(|item| item, 0..100);
+
+        assert_eq!(tree.height(), 7);
+        assert_eq!(tree.len(), 100);
+        assert!(tree.iter().copied().eq(0..100));
+        assert_eq!(tree.select(42), Some(&42));
+    }
+
+    #[test]
+    #[should_panic(expected = "Items should come in key order")]
+    fn should_refuse_to_build_from_unsorted_items() {
+        AVLTree::<i32, i32>::from_sorted_iter(|item| item, [1, 3, 2]);
+    }
+
+    #[test]
+    fn should_split_off_upper_part() {
+        let mut tree = (0..100).collect::<AVLTree<_, _>>();
+
+        let upper = tree.split_off(&40);
+
+        assert!(tree.iter().copied().eq(0..40));
+        assert!(upper.iter().copied().eq(40..100));
+        assert_eq!(upper.rank(&50), 10);
+        assert_eq!(tree.debug_validate(), Ok(()));
+        assert_eq!(upper.debug_validate(), Ok(()));
+
+        let mut tree = [1, 2, 2, 3].into_iter().collect::<AVLTree<_, _>>();
+        let upper = tree.split_off(&2);
+        assert_eq!(tree.iter().collect::<Vec<_>>(), vec![&1]);
+        assert_eq!(upper.iter().collect::<Vec<_>>(), vec![&2, &2, &3]);
+        assert!(tree.split_off(&0).iter().eq(&[1]));
+        assert!(tree.is_empty());
+    }
+
+    #[test]
+    fn should_append_trees() {
+        let mut tree = (0..100).collect::<AVLTree<_, _>>();
+        let mut higher = (100..110).collect::<AVLTree<_, _>>();
+        let mut lower = (-5..0).collect::<AVLTree<_, _>>();
+
+        tree.append(&mut higher);
+        tree.append(&mut lower);
+
+        assert!(higher.is_empty() && lower.is_empty());
+        assert!(tree.iter().copied().eq(-5..110));
+        assert_eq!(tree.select(5), Some(&0));
+        assert_eq!(tree.debug_validate(), Ok(()));
+
+        fn by_key(pair: &(i32, char)) -> &i32 {
+            &pair.0
+        }
+        let mut replacing = AVLTree::new(by_key).with_duplicate_keys(DuplicateKeys::Replace);
+        replacing.extend([(1, 'a'), (3, 'a'), (5, 'a')]);
+        let mut overlapping = AVLTree::new(by_key);
+        overlapping.extend([(2, 'b'), (3, 'b'), (4, 'b')]);
+
+        replacing.append(&mut overlapping);
+        assert_eq!(
+            replacing.iter().map(|pair| pair.1).collect::<String>(),
+            "abbba"
+        );
+    }
+
+    #[test]
+    fn should_collapse_equal_keys_of_a_multiset_when_appending() {
+        fn by_key(pair: &(i32, char)) -> &i32 {
+            &pair.0
+        }
+        let multiset = || {
+            let mut multiset = AVLTree::new(by_key);
+            multiset.extend([(3, 'x'), (5, 'x'), (5, 'y'), (3, 'y'), (5, 'z')]);
+            multiset
+        };
+        let fives = multiset()
+            .iter()
+            .filter(|pair| pair.0 == 5)
+            .map(|pair| pair.1)
+            .collect::<Vec<_>>();
+
+        let mut rejecting = AVLTree::new(by_key).with_duplicate_keys(DuplicateKeys::Reject);
+        rejecting.add((1, 'a'));
+        rejecting.append(&mut multiset());
+        assert_eq!(rejecting.count(&3), 1);
+        assert_eq!(rejecting.find(&5), Some(&(5, fives[0])));
+
+        let mut replacing = AVLTree::new(by_key).with_duplicate_keys(DuplicateKeys::Replace);
+        replacing.extend([(1, 'a'), (3, 'a')]);
+        replacing.append(&mut multiset());
+        assert_eq!(replacing.count(&3), 1);
+        assert_eq!(replacing.find(&5), Some(&(5, fives[2])));
+
+        let union = AVLTree::new(by_key)
+            .with_duplicate_keys(DuplicateKeys::Reject)
+            .union(multiset());
+        assert_eq!(union.len(), 2);
+        assert_eq!(union.debug_validate(), Ok(()));
+    }
+
+    #[test]
+    fn should_combine_trees_as_sets() {
+        let evens = || (0..20).step_by(2).collect::<AVLTree<_, _>>();
+        let threes = || (0..20).step_by(3).collect::<AVLTree<_, _>>();
+
+        assert!(
+            evens()
+                .with_duplicate_keys(DuplicateKeys::Reject)
+                .union(threes())
+                .iter()
+                .eq(&[0, 2, 3, 4, 6, 8, 9, 10, 12, 14, 15, 16, 18])
+        );
+        assert_eq!(evens().union(threes()).count(&6), 2);
+        assert!(evens().intersection(&threes()).iter().eq(&[0, 6, 12, 18]));
+        assert!(
+            evens()
+                .difference(&threes())
+                .iter()
+                .eq(&[2, 4, 8, 10, 14, 16])
+        );
+        assert!(evens().difference(&evens()).is_empty());
+    }
 }
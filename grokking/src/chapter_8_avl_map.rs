@@ -0,0 +1,284 @@
+use crate::chapter_8_avl_binary_tree::{AVLItemIterator, AVLTree, DuplicateKeys};
+use std::mem;
+
+/// An ordered map kept in an [`AVLTree`] of key and value pairs, where adding a key
+/// already in the map replaces its value.
+pub struct AVLMap<K: Ord, V> {
+    tree: AVLTree<K, (K, V)>,
+}
+
+fn key_of<K, V>(entry: &(K, V)) -> &K {
+    &entry.0
+}
+
+impl<K: Ord, V> AVLMap<K, V> {
+    pub fn new() -> Self {
+        AVLMap {
+            tree: AVLTree::new(key_of as fn(&(K, V)) -> &K)
+                .with_duplicate_keys(DuplicateKeys::Replace),
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.tree.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tree.is_empty()
+    }
+
+    /// Inserts the value, returning the one previously stored under the same key.
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        self.tree.add((key, value)).map(|(_, previous)| previous)
+    }
+
+    pub fn entry(&mut self, key: K) -> Entry<'_, K, V> {
+        if self.contains_key(&key) {
+            Entry::Occupied(OccupiedEntry { map: self, key })
+        } else {
+            Entry::Vacant(VacantEntry { map: self, key })
+        }
+    }
+
+    pub fn get(&self, key: &K) -> Option<&V> {
+        self.tree.find(key).map(|(_, value)| value)
+    }
+
+    pub fn get_mut(&mut self, key: &K) -> Option<&mut V> {
+        self.tree.find_mut(key).map(|(_, value)| value)
+    }
+
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.tree.contains(key)
+    }
+
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        self.tree.pop(key).map(|(_, value)| value)
+    }
+
+    /// Iterates the entries in key order.
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        Iter {
+            items: self.tree.iter(),
+        }
+    }
+}
+
+impl<K: Ord, V> Default for AVLMap<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K: Ord, V> Extend<(K, V)> for AVLMap<K, V> {
+    fn extend<I: IntoIterator<Item = (K, V)>>(&mut self, iter: I) {
+        for (key, value) in iter {
+            self.insert(key, value);
+        }
+    }
+}
+
+impl<K: Ord, V> FromIterator<(K, V)> for AVLMap<K, V> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        let mut map = AVLMap::new();
+        map.extend(iter);
+        map
+    }
+}
+
+impl<'a, K: Ord, V> IntoIterator for &'a AVLMap<K, V> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = Iter<'a, K, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+pub struct Iter<'a, K, V> {
+    items: AVLItemIterator<'a, (K, V)>,
+}
+
+impl<'a, K, V> Iterator for Iter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.items.next().map(|(key, value)| (key, value))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.items.size_hint()
+    }
+}
+
+impl<K, V> DoubleEndedIterator for Iter<'_, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.items.next_back().map(|(key, value)| (key, value))
+    }
+}
+
+impl<K, V> ExactSizeIterator for Iter<'_, K, V> {}
+
+pub enum Entry<'a, K: Ord, V> {
+    Occupied(OccupiedEntry<'a, K, V>),
+    Vacant(VacantEntry<'a, K, V>),
+}
+
+impl<'a, K: Ord, V> Entry<'a, K, V> {
+    pub fn key(&self) -> &K {
+        match self {
+            Entry::Occupied(entry) => entry.key(),
+            Entry::Vacant(entry) => entry.key(),
+        }
+    }
+
+    pub fn or_insert(self, default: V) -> &'a mut V {
+        self.or_insert_with(|| default)
+    }
+
+    pub fn or_insert_with<F: FnOnce() -> V>(self, default: F) -> &'a mut V {
+        match self {
+            Entry::Occupied(entry) => entry.into_mut(),
+            Entry::Vacant(entry) => entry.insert(default()),
+        }
+    }
+
+    pub fn or_default(self) -> &'a mut V
+    where
+        V: Default,
+    {
+        self.or_insert_with(V::default)
+    }
+
+    pub fn and_modify<F: FnOnce(&mut V)>(mut self, modify: F) -> Self {
+        if let Entry::Occupied(entry) = &mut self {
+            modify(entry.get_mut());
+        }
+        self
+    }
+}
+
+/// An entry whose key is already stored in the map.
+pub struct OccupiedEntry<'a, K: Ord, V> {
+    map: &'a mut AVLMap<K, V>,
+    key: K,
+}
+
+impl<'a, K: Ord, V> OccupiedEntry<'a, K, V> {
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+    pub fn get(&self) -> &V {
+        self.map
+            .get(&self.key)
+            .expect("Occupied entry should be in the map")
+    }
+
+    pub fn get_mut(&mut self) -> &mut V {
+        self.map
+            .get_mut(&self.key)
+            .expect("Occupied entry should be in the map")
+    }
+
+    pub fn into_mut(self) -> &'a mut V {
+        self.map
+            .get_mut(&self.key)
+            .expect("Occupied entry should be in the map")
+    }
+
+    pub fn insert(&mut self, value: V) -> V {
+        mem::replace(self.get_mut(), value)
+    }
+
+    pub fn remove(self) -> V {
+        self.map
+            .remove(&self.key)
+            .expect("Occupied entry should be in the map")
+    }
+}
+
+/// An entry whose key is not stored in the map yet.
+pub struct VacantEntry<'a, K: Ord, V> {
+    map: &'a mut AVLMap<K, V>,
+    key: K,
+}
+
+impl<'a, K: Ord, V> VacantEntry<'a, K, V> {
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+    pub fn into_key(self) -> K {
+        self.key
+    }
+
+    pub fn insert(self, value: V) -> &'a mut V {
+        // The key moves into the tree, so the new entry is found again by its position.
+        let position = self.map.tree.rank(&self.key);
+        self.map.tree.add((self.key, value));
+        let (_, value) = self
+            .map
+            .tree
+            .select_mut(position)
+            .expect("Entry was just inserted");
+        value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::AVLMap;
+
+    #[test]
+    fn should_insert_and_replace_values() {
+        let mut map = AVLMap::new();
+
+        assert_eq!(map.insert("b", 2), None);
+        assert_eq!(map.insert("a", 1), None);
+        assert_eq!(map.insert("b", 20), Some(2));
+
+        assert_eq!(map.len(), 2);
+        assert_eq!(map.get(&"b"), Some(&20));
+        assert_eq!(map.get(&"c"), None);
+        assert_eq!(
+            map.iter().collect::<Vec<_>>(),
+            vec![(&"a", &1), (&"b", &20)]
+        );
+
+        *map.get_mut(&"a").unwrap() += 10;
+        assert_eq!(map.remove(&"a"), Some(11));
+        assert!(!map.contains_key(&"a"));
+    }
+
+    #[test]
+    fn should_count_words_through_entries() {
+        let mut map = AVLMap::new();
+        for word in "the cat saw the dog and the cat ran".split(' ') {
+            *map.entry(word).or_insert(0) += 1;
+        }
+
+        assert_eq!(
+            map.iter()
+                .map(|(word, count)| (*word, *count))
+                .collect::<Vec<_>>(),
+            vec![
+                ("and", 1),
+                ("cat", 2),
+                ("dog", 1),
+                ("ran", 1),
+                ("saw", 1),
+                ("the", 3)
+            ]
+        );
+
+        map.entry("dog")
+            .and_modify(|count| *count *= 10)
+            .or_default();
+        map.entry("fox")
+            .and_modify(|count| *count *= 10)
+            .or_default();
+        assert_eq!(map.get(&"dog"), Some(&10));
+        assert_eq!(map.get(&"fox"), Some(&0));
+    }
+}
@@ -0,0 +1,164 @@
+/// A union-find structure over the elements `0..len`, which starts with every element
+/// in a set of its own and merges sets together.
+///
+/// Finding compresses the path to the representative, and merging hangs the tree
+/// of lower rank under the other one, so both take amortized near constant time.
+#[derive(Debug, Clone)]
+pub struct DisjointSet {
+    parents: Vec<usize>,
+    /// An upper bound on the height of the tree under each representative.
+    ranks: Vec<u8>,
+    /// The number of elements in the set of each representative.
+    sizes: Vec<usize>,
+    set_count: usize,
+}
+
+impl DisjointSet {
+    pub fn new(len: usize) -> Self {
+        DisjointSet {
+            parents: (0..len).collect(),
+            ranks: vec![0; len],
+            sizes: vec![1; len],
+            set_count: len,
+        }
+    }
+
+    /// Adds a new element in a set of its own, returning it.
+    pub fn make_set(&mut self) -> usize {
+        let element = self.parents.len();
+        self.parents.push(element);
+        self.ranks.push(0);
+        self.sizes.push(1);
+        self.set_count += 1;
+        element
+    }
+
+    /// How many elements there are, across every set.
+    pub fn len(&self) -> usize {
+        self.parents.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.parents.is_empty()
+    }
+
+    /// How many disjoint sets the elements form.
+    pub fn set_count(&self) -> usize {
+        self.set_count
+    }
+
+    /// The representative of the set holding the element.
+    ///
+    /// # Panics
+    /// If the element is out of bounds.
+    pub fn find(&mut self, element: usize) -> usize {
+        let mut root = element;
+        while self.parents[root] != root {
+            root = self.parents[root];
+        }
+
+        let mut current = element;
+        while current != root {
+            let parent = self.parents[current];
+            self.parents[current] = root;
+            current = parent;
+        }
+        root
+    }
+
+    /// Merges the sets holding the two elements, returning whether they were apart.
+    ///
+    /// # Panics
+    /// If any of the elements is out of bounds.
+    pub fn union(&mut self, a: usize, b: usize) -> bool {
+        let a = self.find(a);
+        let b = self.find(b);
+        if a == b {
+            return false;
+        }
+
+        let (root, child) = if self.ranks[a] < self.ranks[b] {
+            (b, a)
+        } else {
+            (a, b)
+        };
+        self.parents[child] = root;
+        self.sizes[root] += self.sizes[child];
+        if self.ranks[root] == self.ranks[child] {
+            self.ranks[root] += 1;
+        }
+        self.set_count -= 1;
+        true
+    }
+
+    /// Whether both elements are in the same set.
+    ///
+    /// # Panics
+    /// If any of the elements is out of bounds.
+    pub fn connected(&mut self, a: usize, b: usize) -> bool {
+        self.find(a) == self.find(b)
+    }
+
+    /// How many elements the set holding the element has.
+    ///
+    /// # Panics
+    /// If the element is out of bounds.
+    pub fn size_of(&mut self, element: usize) -> usize {
+        let root = self.find(element);
+        self.sizes[root]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::DisjointSet;
+
+    #[test]
+    fn should_start_with_singletons() {
+        let mut set = DisjointSet::new(3);
+
+        assert_eq!(set.len(), 3);
+        assert_eq!(set.set_count(), 3);
+        assert!(!set.connected(0, 1));
+        assert_eq!(set.size_of(2), 1);
+    }
+
+    #[test]
+    fn should_merge_sets_and_track_sizes() {
+        let mut set = DisjointSet::new(6);
+
+        assert!(set.union(0, 1));
+        assert!(set.union(2, 3));
+        assert!(set.union(1, 3));
+        assert!(!set.union(0, 2));
+
+        assert!(set.connected(0, 3));
+        assert!(!set.connected(0, 4));
+        assert_eq!(set.size_of(2), 4);
+        assert_eq!(set.size_of(5), 1);
+        assert_eq!(set.set_count(), 3);
+
+        let element = set.make_set();
+        assert_eq!(element, 6);
+        assert!(set.union(element, 4));
+        assert_eq!(set.size_of(4), 2);
+        assert_eq!(set.set_count(), 3);
+    }
+
+    #[test]
+    fn should_compress_paths() {
+        let mut set = DisjointSet::new(100);
+        for element in 1..100 {
+            set.union(element - 1, element);
+        }
+
+        let root = set.find(99);
+        assert_eq!(set.parents[99], root);
+        for element in 0..100 {
+            set.find(element);
+        }
+        assert!(set.parents.iter().all(|parent| *parent == root));
+        assert_eq!(set.size_of(0), 100);
+        assert!(set.ranks[root] <= 7);
+    }
+}
@@ -0,0 +1,230 @@
+use crate::chapter_6_graph::{Graph, VertexId, Weight, Weighted};
+use crate::chapter_8_disjoint_set::DisjointSet;
+use priority_queue::PriorityQueue;
+use std::collections::{HashMap, HashSet};
+
+/// An edge as `(from, to, payload)`.
+pub type Edge<'a, E> = (VertexId, VertexId, &'a E);
+
+/// The cheapest set of edges that keeps every vertex connected to the ones it could
+/// already reach. On a disconnected graph it holds one tree per connected component.
+///
+/// Edge directions are ignored, and every edge is given as `(from, to, payload)` the
+/// way it is stored in the graph.
+pub struct SpanningForest<'a, E> {
+    pub edges: Vec<Edge<'a, E>>,
+    pub total_weight: Weight,
+}
+
+impl<'a, E: Weighted> SpanningForest<'a, E> {
+    fn new() -> Self {
+        SpanningForest {
+            edges: Vec::new(),
+            total_weight: 0,
+        }
+    }
+
+    fn push(&mut self, edge: Edge<'a, E>) {
+        self.total_weight += edge.2.weight();
+        self.edges.push(edge);
+    }
+}
+
+/// Finds a minimum spanning forest with Kruskal's algorithm: goes through the edges
+/// from the cheapest up, keeping each one that joins two trees not joined yet.
+pub fn kruskal<T, E: Weighted>(graph: &Graph<T, E>) -> SpanningForest<'_, E> {
+    let vertex_ids = graph.vertex_ids();
+    let positions = vertex_ids
+        .iter()
+        .enumerate()
+        .map(|(position, vertex_id)| (*vertex_id, position))
+        .collect::<HashMap<_, _>>();
+    let mut edges = sorted_edges(graph);
+    edges.sort_by_key(|(_, _, edge)| edge.weight());
+
+    let mut trees = DisjointSet::new(vertex_ids.len());
+    let mut forest = SpanningForest::new();
+    for (from, to, edge) in edges {
+        if trees.set_count() == 1 {
+            break;
+        }
+        if trees.union(positions[&from], positions[&to]) {
+            forest.push((from, to, edge));
+        }
+    }
+    forest
+}
+
+/// Finds a minimum spanning forest with Prim's algorithm: grows a tree from a vertex,
+/// always through the cheapest edge leaving it, then starts over from a vertex the
+/// tree could not reach.
+pub fn prim<T, E: Weighted>(graph: &Graph<T, E>) -> SpanningForest<'_, E> {
+    let mut legs: HashMap<VertexId, Vec<(VertexId, Edge<'_, E>)>> = HashMap::new();
+    for (from, to, edge) in sorted_edges(graph) {
+        legs.entry(from).or_default().push((to, (from, to, edge)));
+        legs.entry(to).or_default().push((from, (from, to, edge)));
+    }
+
+    let mut forest = SpanningForest::new();
+    let mut in_forest = HashSet::new();
+    // Stores the vertex id with its priority, which is minus the weight of the cheapest
+    // edge known to reach it from the tree.
+    let mut priority_queue = PriorityQueue::new();
+    let mut cheapest_edges = HashMap::new();
+
+    for root in graph.vertex_ids() {
+        if in_forest.contains(&root) {
+            continue;
+        }
+        priority_queue.push(root, 0);
+
+        while let Some((vertex_id, _)) = priority_queue.pop() {
+            in_forest.insert(vertex_id);
+            if let Some(edge) = cheapest_edges.remove(&vertex_id) {
+                forest.push(edge);
+            }
+
+            for (neighbor, edge) in legs.get(&vertex_id).into_iter().flatten() {
+                if in_forest.contains(neighbor) {
+                    continue;
+                }
+                let priority = -edge.2.weight();
+                if priority_queue
+                    .get_priority(neighbor)
+                    .is_none_or(|known| priority > *known)
+                {
+                    priority_queue.push(*neighbor, priority);
+                    cheapest_edges.insert(*neighbor, *edge);
+                }
+            }
+        }
+    }
+    forest
+}
+
+/// Every edge, leaving the vertices in the order they were added, so that ties are
+/// broken the same way on every run.
+fn sorted_edges<T, E>(graph: &Graph<T, E>) -> Vec<Edge<'_, E>> {
+    let mut edges = Vec::with_capacity(graph.edge_count());
+    for from in graph.vertex_ids() {
+        let Some(get_vertex) = graph.get_vertex(&from) else {
+            continue;
+        };
+        for get_neighbor in get_vertex.get_neighbors() {
+            edges.push((from, get_neighbor.get_id(), get_neighbor.edge));
+        }
+    }
+    edges
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{SpanningForest, kruskal, prim};
+    use crate::chapter_6_graph::{Graph, VertexId};
+
+    fn sorted_pairs<E>(forest: &SpanningForest<'_, E>) -> Vec<(VertexId, VertexId)> {
+        let mut pairs = forest
+            .edges
+            .iter()
+            .map(|(from, to, _)| (*from.min(to), *from.max(to)))
+            .collect::<Vec<_>>();
+        pairs.sort();
+        pairs
+    }
+
+    #[test]
+    fn should_find_minimum_spanning_tree() {
+        let mut graph = Graph::undirected();
+        let a = graph.add_vertex("A");
+        let b = graph.add_vertex("B");
+        let c = graph.add_vertex("C");
+        let d = graph.add_vertex("D");
+        let e = graph.add_vertex("E");
+
+        graph.attach_weighted(&a, &b, 4);
+        graph.attach_weighted(&a, &c, 1);
+        graph.attach_weighted(&b, &c, 2);
+        graph.attach_weighted(&b, &d, 5);
+        graph.attach_weighted(&c, &d, 8);
+        graph.attach_weighted(&d, &e, 3);
+        graph.attach_weighted(&c, &e, 9);
+
+        for algorithm in [
+            Graph::minimum_spanning_tree_kruskal,
+            Graph::minimum_spanning_tree_prim,
+        ] {
+            let forest = algorithm(&graph);
+
+            assert_eq!(forest.total_weight, 11);
+            assert_eq!(sorted_pairs(&forest), vec![(a, c), (b, c), (b, d), (d, e)]);
+        }
+    }
+
+    #[test]
+    fn should_find_spanning_forest_of_disconnected_graph() {
+        let mut graph = Graph::undirected();
+        let a = graph.add_vertex("A");
+        let b = graph.add_vertex("B");
+        let c = graph.add_vertex("C");
+        let d = graph.add_vertex("D");
+        let e = graph.add_vertex("E");
+        let lonely = graph.add_vertex("Lonely");
+
+        graph.attach_weighted(&a, &b, 2);
+        graph.attach_weighted(&b, &c, -1);
+        graph.attach_weighted(&a, &c, 3);
+        graph.attach_weighted(&d, &e, 7);
+        graph.remove_vertex(&lonely);
+
+        for algorithm in [kruskal, prim] {
+            let forest = algorithm(&graph);
+
+            assert_eq!(forest.total_weight, 8);
+            assert_eq!(sorted_pairs(&forest), vec![(a, b), (b, c), (d, e)]);
+        }
+    }
+
+    #[test]
+    fn should_ignore_edge_directions() {
+        let mut graph = Graph::new();
+        let a = graph.add_vertex("A");
+        let b = graph.add_vertex("B");
+        let c = graph.add_vertex("C");
+
+        graph.attach_weighted(&b, &a, 1);
+        graph.attach_weighted(&c, &a, 5);
+        graph.attach_weighted(&c, &b, 2);
+
+        for algorithm in [kruskal, prim] {
+            let forest = algorithm(&graph);
+
+            assert_eq!(forest.total_weight, 3);
+            let mut edges = forest
+                .edges
+                .iter()
+                .map(|(from, to, edge)| (*from, *to, **edge))
+                .collect::<Vec<_>>();
+            edges.sort();
+            assert_eq!(edges, vec![(b, a, 1), (c, b, 2)]);
+        }
+    }
+
+    #[test]
+    fn should_agree_on_total_weight() {
+        let mut graph = Graph::undirected();
+        let ids = (0..30).map(|i| graph.add_vertex(i)).collect::<Vec<_>>();
+        for (i, from) in ids.iter().enumerate() {
+            for (j, to) in ids.iter().enumerate().skip(i + 1) {
+                if (i * 11 + j * 5) % 7 < 3 {
+                    graph.attach_weighted(from, to, ((i * 13 + j * 29) % 17) as isize);
+                }
+            }
+        }
+
+        let kruskal = kruskal(&graph);
+        let prim = prim(&graph);
+
+        assert_eq!(kruskal.total_weight, prim.total_weight);
+        assert_eq!(kruskal.edges.len(), prim.edges.len());
+    }
+}
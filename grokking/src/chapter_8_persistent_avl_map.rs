@@ -0,0 +1,550 @@
+use crate::chapter_4_persistent_stack::{ArcPointer, PointerKind, RcPointer};
+use std::borrow::Borrow;
+use std::cmp::Ordering;
+use std::fmt;
+
+type Link<K, V, P> = Option<<P as PointerKind>::Pointer<Node<K, V, P>>>;
+type SharedEntry<K, V, P> = <P as PointerKind>::Pointer<(K, V)>;
+
+/// Nodes are never changed once built: every version gets new nodes along the path to
+/// the change and shares all the others. Entries sit behind their own pointer, so
+/// copying a node never copies a key or a value.
+struct Node<K, V, P: PointerKind> {
+    entry: SharedEntry<K, V, P>,
+    height: u16,
+    size: usize,
+    left: Link<K, V, P>,
+    right: Link<K, V, P>,
+}
+
+fn height_of<K, V, P: PointerKind>(node: Option<&Node<K, V, P>>) -> u16 {
+    node.map_or(0, |node| node.height)
+}
+
+fn size_of<K, V, P: PointerKind>(node: Option<&Node<K, V, P>>) -> usize {
+    node.map_or(0, |node| node.size)
+}
+
+impl<K: Ord, V, P: PointerKind> Node<K, V, P> {
+    fn new(
+        left: Link<K, V, P>,
+        entry: SharedEntry<K, V, P>,
+        right: Link<K, V, P>,
+    ) -> P::Pointer<Self> {
+        P::new(Node {
+            entry,
+            height: 1 + height_of(left.as_deref()).max(height_of(right.as_deref())),
+            size: 1 + size_of(left.as_deref()) + size_of(right.as_deref()),
+            left,
+            right,
+        })
+    }
+
+    /// Builds a node out of subtrees whose heights differ by two at most, rotating
+    /// once or twice when they differ by two.
+    fn balanced(
+        left: Link<K, V, P>,
+        entry: SharedEntry<K, V, P>,
+        right: Link<K, V, P>,
+    ) -> P::Pointer<Self> {
+        let (left_height, right_height) = (height_of(left.as_deref()), height_of(right.as_deref()));
+
+        match (left, right) {
+            (Some(left), right) if left.height > right_height + 1 => match &left.right {
+                Some(inner) if inner.height > height_of(left.left.as_deref()) => Self::new(
+                    Some(Self::new(
+                        left.left.clone(),
+                        left.entry.clone(),
+                        inner.left.clone(),
+                    )),
+                    inner.entry.clone(),
+                    Some(Self::new(inner.right.clone(), entry, right)),
+                ),
+                _ => Self::new(
+                    left.left.clone(),
+                    left.entry.clone(),
+                    Some(Self::new(left.right.clone(), entry, right)),
+                ),
+            },
+            (left, Some(right)) if right.height > left_height + 1 => match &right.left {
+                Some(inner) if inner.height > height_of(right.right.as_deref()) => Self::new(
+                    Some(Self::new(left, entry, inner.left.clone())),
+                    inner.entry.clone(),
+                    Some(Self::new(
+                        inner.right.clone(),
+                        right.entry.clone(),
+                        right.right.clone(),
+                    )),
+                ),
+                _ => Self::new(
+                    Some(Self::new(left, entry, right.left.clone())),
+                    right.entry.clone(),
+                    right.right.clone(),
+                ),
+            },
+            (left, right) => Self::new(left, entry, right),
+        }
+    }
+
+    fn insert(link: &Link<K, V, P>, entry: SharedEntry<K, V, P>) -> P::Pointer<Self> {
+        let Some(node) = link else {
+            return Self::new(None, entry, None);
+        };
+
+        match entry.0.cmp(&node.entry.0) {
+            Ordering::Less => Self::balanced(
+                Some(Self::insert(&node.left, entry)),
+                node.entry.clone(),
+                node.right.clone(),
+            ),
+            Ordering::Equal => Self::new(node.left.clone(), entry, node.right.clone()),
+            Ordering::Greater => Self::balanced(
+                node.left.clone(),
+                node.entry.clone(),
+                Some(Self::insert(&node.right, entry)),
+            ),
+        }
+    }
+
+    /// The subtree without the key, or `None` when the key is not in it.
+    fn remove<Q>(link: &Link<K, V, P>, key: &Q) -> Option<Link<K, V, P>>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let node = link.as_ref()?;
+
+        Some(match key.cmp(node.entry.0.borrow()) {
+            Ordering::Less => Some(Self::balanced(
+                Self::remove(&node.left, key)?,
+                node.entry.clone(),
+                node.right.clone(),
+            )),
+            Ordering::Greater => Some(Self::balanced(
+                node.left.clone(),
+                node.entry.clone(),
+                Self::remove(&node.right, key)?,
+            )),
+            Ordering::Equal => match (&node.left, &node.right) {
+                (None, right) => right.clone(),
+                (left, None) => left.clone(),
+                (left, Some(right)) => {
+                    let (right, min_entry) = Self::remove_min(right);
+                    Some(Self::balanced(left.clone(), min_entry, right))
+                }
+            },
+        })
+    }
+
+    fn remove_min(node: &P::Pointer<Self>) -> (Link<K, V, P>, SharedEntry<K, V, P>) {
+        match &node.left {
+            None => (node.right.clone(), node.entry.clone()),
+            Some(left) => {
+                let (left, min_entry) = Self::remove_min(left);
+                let node = Self::balanced(left, node.entry.clone(), node.right.clone());
+                (Some(node), min_entry)
+            }
+        }
+    }
+}
+
+/// An ordered map that is never changed in place: inserting and removing return a
+/// new version in `O(log n)`, sharing every subtree the change did not touch with the
+/// version it came from. Keeping old versions around is cheap, and so is cloning.
+///
+/// Nodes are reference counted with `Rc`, or with `Arc` through [`ArcPersistentAVLMap`]
+/// so that versions can be sent to other threads.
+pub struct PersistentAVLMap<K, V, P: PointerKind = RcPointer> {
+    root: Link<K, V, P>,
+}
+
+/// A persistent map that is `Send` and `Sync` when its keys and values are.
+pub type ArcPersistentAVLMap<K, V> = PersistentAVLMap<K, V, ArcPointer>;
+
+impl<K: Ord, V> PersistentAVLMap<K, V> {
+    pub fn new() -> Self {
+        PersistentAVLMap::empty()
+    }
+}
+
+impl<K: Ord, V, P: PointerKind> PersistentAVLMap<K, V, P> {
+    /// An empty map, for any kind of pointer.
+    pub fn empty() -> Self {
+        PersistentAVLMap { root: None }
+    }
+
+    pub fn len(&self) -> usize {
+        size_of(self.root.as_deref())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.root.is_none()
+    }
+
+    pub fn get<Q>(&self, key: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        let mut node = self.root.as_deref();
+        while let Some(current) = node {
+            node = match key.cmp(current.entry.0.borrow()) {
+                Ordering::Equal => return Some(&current.entry.1),
+                Ordering::Less => current.left.as_deref(),
+                Ordering::Greater => current.right.as_deref(),
+            };
+        }
+        None
+    }
+
+    pub fn contains_key<Q>(&self, key: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        self.get(key).is_some()
+    }
+
+    /// A version with the value stored under the key, in place of any value there.
+    pub fn insert(&self, key: K, value: V) -> Self {
+        PersistentAVLMap {
+            root: Some(Node::<K, V, P>::insert(&self.root, P::new((key, value)))),
+        }
+    }
+
+    /// A version without the key. When the key is not in the map, the new version
+    /// shares the whole tree.
+    pub fn remove<Q>(&self, key: &Q) -> Self
+    where
+        K: Borrow<Q>,
+        Q: Ord + ?Sized,
+    {
+        PersistentAVLMap {
+            root: Node::<K, V, P>::remove(&self.root, key).unwrap_or_else(|| self.root.clone()),
+        }
+    }
+
+    /// Iterates the entries in key order.
+    pub fn iter(&self) -> Iter<'_, K, V, P> {
+        let mut iter = Iter {
+            stack: Vec::new(),
+            remaining: self.len(),
+        };
+        iter.push_left_leg(self.root.as_deref());
+        iter
+    }
+
+    /// The changes that turn this version into the other one, in key order.
+    ///
+    /// Subtrees both versions share are skipped without being walked, so comparing a
+    /// version with one derived from it takes time in proportion to the changes made
+    /// in between, times `log n`, rather than to the size of the map.
+    pub fn diff<'a>(&'a self, other: &'a Self) -> Diff<'a, K, V, P> {
+        Diff {
+            before: self.root.iter().map(Part::Subtree).collect(),
+            after: other.root.iter().map(Part::Subtree).collect(),
+        }
+    }
+}
+
+impl<K, V, P: PointerKind> Clone for PersistentAVLMap<K, V, P> {
+    fn clone(&self) -> Self {
+        PersistentAVLMap {
+            root: self.root.clone(),
+        }
+    }
+}
+
+impl<K: Ord, V, P: PointerKind> Default for PersistentAVLMap<K, V, P> {
+    fn default() -> Self {
+        Self::empty()
+    }
+}
+
+impl<K: Ord, V, P: PointerKind> FromIterator<(K, V)> for PersistentAVLMap<K, V, P> {
+    fn from_iter<I: IntoIterator<Item = (K, V)>>(iter: I) -> Self {
+        iter.into_iter()
+            .fold(PersistentAVLMap::empty(), |map, (key, value)| {
+                map.insert(key, value)
+            })
+    }
+}
+
+impl<K: Ord + fmt::Debug, V: fmt::Debug, P: PointerKind> fmt::Debug for PersistentAVLMap<K, V, P> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.iter()).finish()
+    }
+}
+
+impl<'a, K: Ord, V, P: PointerKind> IntoIterator for &'a PersistentAVLMap<K, V, P> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = Iter<'a, K, V, P>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+pub struct Iter<'a, K, V, P: PointerKind = RcPointer> {
+    stack: Vec<&'a Node<K, V, P>>,
+    remaining: usize,
+}
+
+impl<'a, K, V, P: PointerKind> Iter<'a, K, V, P> {
+    fn push_left_leg(&mut self, mut node: Option<&'a Node<K, V, P>>) {
+        while let Some(current) = node {
+            self.stack.push(current);
+            node = current.left.as_deref();
+        }
+    }
+}
+
+impl<'a, K, V, P: PointerKind> Iterator for Iter<'a, K, V, P> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.stack.pop()?;
+        self.push_left_leg(node.right.as_deref());
+        self.remaining -= 1;
+        Some((&node.entry.0, &node.entry.1))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<K, V, P: PointerKind> ExactSizeIterator for Iter<'_, K, V, P> {}
+
+/// A difference between two versions of a map, see [`PersistentAVLMap::diff`].
+#[derive(Debug, PartialEq, Eq)]
+pub enum Change<'a, K, V> {
+    Added(&'a K, &'a V),
+    Removed(&'a K, &'a V),
+    Updated { key: &'a K, old: &'a V, new: &'a V },
+}
+
+/// What is left to compare of one version: whole subtrees and single entries, with
+/// the next one in key order on top.
+enum Part<'a, K, V, P: PointerKind> {
+    Subtree(&'a P::Pointer<Node<K, V, P>>),
+    Entry(&'a SharedEntry<K, V, P>),
+}
+
+impl<K, V, P: PointerKind> Clone for Part<'_, K, V, P> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<K, V, P: PointerKind> Copy for Part<'_, K, V, P> {}
+
+pub struct Diff<'a, K, V, P: PointerKind = RcPointer> {
+    before: Vec<Part<'a, K, V, P>>,
+    after: Vec<Part<'a, K, V, P>>,
+}
+
+/// Replaces the subtree on top with its left subtree, its entry and its right subtree.
+fn open<'a, K: 'a, V: 'a, P: PointerKind>(
+    parts: &mut Vec<Part<'a, K, V, P>>,
+    node: &'a P::Pointer<Node<K, V, P>>,
+) {
+    parts.pop();
+    parts.extend(node.right.iter().map(Part::Subtree));
+    parts.push(Part::Entry(&node.entry));
+    parts.extend(node.left.iter().map(Part::Subtree));
+}
+
+impl<'a, K: Ord + 'a, V: PartialEq + 'a, P: PointerKind> Iterator for Diff<'a, K, V, P> {
+    type Item = Change<'a, K, V>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            match (self.before.last().copied(), self.after.last().copied()) {
+                (None, None) => return None,
+                (Some(Part::Subtree(before)), Some(Part::Subtree(after)))
+                    if P::ptr_eq(before, after) =>
+                {
+                    self.before.pop();
+                    self.after.pop();
+                }
+                (Some(Part::Entry(before)), Some(Part::Entry(after)))
+                    if P::ptr_eq(before, after) =>
+                {
+                    self.before.pop();
+                    self.after.pop();
+                }
+                // Opening the taller subtree first brings the two sides down to
+                // subtrees they may share.
+                (Some(Part::Subtree(before)), Some(Part::Subtree(after))) => {
+                    if before.height >= after.height {
+                        open(&mut self.before, before);
+                    } else {
+                        open(&mut self.after, after);
+                    }
+                }
+                (Some(Part::Subtree(before)), _) => open(&mut self.before, before),
+                (_, Some(Part::Subtree(after))) => open(&mut self.after, after),
+                (Some(Part::Entry(before)), None) => {
+                    self.before.pop();
+                    return Some(Change::Removed(&before.0, &before.1));
+                }
+                (None, Some(Part::Entry(after))) => {
+                    self.after.pop();
+                    return Some(Change::Added(&after.0, &after.1));
+                }
+                (Some(Part::Entry(before)), Some(Part::Entry(after))) => {
+                    match before.0.cmp(&after.0) {
+                        Ordering::Less => {
+                            self.before.pop();
+                            return Some(Change::Removed(&before.0, &before.1));
+                        }
+                        Ordering::Greater => {
+                            self.after.pop();
+                            return Some(Change::Added(&after.0, &after.1));
+                        }
+                        Ordering::Equal => {
+                            self.before.pop();
+                            self.after.pop();
+                            if before.1 != after.1 {
+                                return Some(Change::Updated {
+                                    key: &before.0,
+                                    old: &before.1,
+                                    new: &after.1,
+                                });
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ArcPersistentAVLMap, Change, Link, PersistentAVLMap, RcPointer};
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+    use std::collections::BTreeMap;
+    use std::rc::Rc;
+
+    /// Checks the order, heights, sizes and balance of the subtree, returning its height.
+    fn validate(link: &Link<i32, i32, RcPointer>) -> u16 {
+        let Some(node) = link else {
+            return 0;
+        };
+        let (left, right) = (validate(&node.left), validate(&node.right));
+        assert!(left.abs_diff(right) <= 1);
+        assert_eq!(node.height, 1 + left.max(right));
+        assert_eq!(
+            node.size,
+            1 + super::size_of(node.left.as_deref()) + super::size_of(node.right.as_deref())
+        );
+        assert!(node.left.as_ref().is_none_or(|l| l.entry.0 < node.entry.0));
+        assert!(node.right.as_ref().is_none_or(|r| r.entry.0 > node.entry.0));
+        node.height
+    }
+
+    #[test]
+    fn should_keep_old_versions_unchanged() {
+        let empty = PersistentAVLMap::new();
+        let one = empty.insert("a", 1);
+        let two = one.insert("b", 2);
+        let replaced = two.insert("a", 10);
+        let removed = replaced.remove("b");
+
+        assert!(empty.is_empty());
+        assert_eq!(one.iter().collect::<Vec<_>>(), vec![(&"a", &1)]);
+        assert_eq!(two.iter().collect::<Vec<_>>(), vec![(&"a", &1), (&"b", &2)]);
+        assert_eq!(replaced.get("a"), Some(&10));
+        assert_eq!(two.get("a"), Some(&1));
+        assert_eq!(removed.len(), 1);
+        assert!(!removed.contains_key("b"));
+        assert!(replaced.contains_key("b"));
+    }
+
+    #[test]
+    fn should_share_untouched_subtrees() {
+        let map = (0..100)
+            .map(|key| (key, key))
+            .collect::<PersistentAVLMap<_, _>>();
+        let root = map.root.as_ref().unwrap();
+
+        let changed = map.insert(99, -1);
+        let changed_root = changed.root.as_ref().unwrap();
+
+        assert!(Rc::ptr_eq(
+            root.left.as_ref().unwrap(),
+            changed_root.left.as_ref().unwrap()
+        ));
+        assert!(Rc::ptr_eq(&root.entry, &changed_root.entry));
+
+        let same = map.remove(&1000);
+        assert!(Rc::ptr_eq(root, same.root.as_ref().unwrap()));
+    }
+
+    #[test]
+    fn should_stay_balanced_like_btreemap() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let mut map = PersistentAVLMap::new();
+        let mut model = BTreeMap::new();
+
+        for _ in 0..2000 {
+            let key = rng.gen_range(0..200);
+            if rng.gen_bool(0.6) {
+                map = map.insert(key, key * 2);
+                model.insert(key, key * 2);
+            } else {
+                map = map.remove(&key);
+                model.remove(&key);
+            }
+            validate(&map.root);
+        }
+
+        assert!(map.iter().eq(model.iter()));
+        assert_eq!(map.iter().len(), model.len());
+    }
+
+    #[test]
+    fn should_diff_versions() {
+        let before = (0..1000)
+            .map(|key| (key, key))
+            .collect::<PersistentAVLMap<_, _>>();
+        let after = before
+            .remove(&10)
+            .insert(500, -500)
+            .insert(2000, 2000)
+            .insert(700, 700);
+
+        assert_eq!(
+            before.diff(&after).collect::<Vec<_>>(),
+            vec![
+                Change::Removed(&10, &10),
+                Change::Updated {
+                    key: &500,
+                    old: &500,
+                    new: &-500
+                },
+                Change::Added(&2000, &2000),
+            ]
+        );
+        assert_eq!(after.diff(&before).count(), 3);
+        assert_eq!(before.diff(&before.clone()).next(), None);
+    }
+
+    #[test]
+    fn should_send_versions_to_other_threads() {
+        let map = (0..10)
+            .map(|key| (key, key))
+            .collect::<ArcPersistentAVLMap<_, _>>();
+        let snapshot = map.clone();
+
+        let changed = std::thread::spawn(move || map.insert(10, 10))
+            .join()
+            .unwrap();
+
+        assert_eq!(snapshot.len(), 10);
+        assert_eq!(snapshot.diff(&changed).count(), 1);
+    }
+}
@@ -0,0 +1,204 @@
+use crate::chapter_6_graph::{Distance, GraphView, VertexId, Weighted};
+use crate::chapter_9_bellman_ford_algorithm::{NegativeCycle, bellman_ford_tree, potentials};
+use crate::chapter_9_dijkstra_algorithm::DijkstraAlgorithm;
+use std::collections::HashMap;
+
+/// The distance from every vertex to every other vertex of a graph.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DistanceMatrix {
+    vertex_ids: Vec<VertexId>,
+    positions: HashMap<VertexId, usize>,
+    /// Row major, with `None` where there is no path.
+    distances: Vec<Option<Distance>>,
+}
+
+impl DistanceMatrix {
+    fn new(vertex_ids: Vec<VertexId>) -> Self {
+        let size = vertex_ids.len();
+        let positions = vertex_ids
+            .iter()
+            .enumerate()
+            .map(|(position, vertex_id)| (*vertex_id, position))
+            .collect();
+        let mut distances = vec![None; size * size];
+        for position in 0..size {
+            distances[position * size + position] = Some(0);
+        }
+        DistanceMatrix {
+            vertex_ids,
+            positions,
+            distances,
+        }
+    }
+
+    /// The vertices of the graph, in the order they were added.
+    pub fn vertex_ids(&self) -> &[VertexId] {
+        &self.vertex_ids
+    }
+
+    /// The distance of the cheapest path from one vertex to another, if there is a path.
+    pub fn distance(&self, from: &VertexId, to: &VertexId) -> Option<Distance> {
+        let from = self.positions.get(from)?;
+        let to = self.positions.get(to)?;
+        self.distances[self.cell(*from, *to)]
+    }
+
+    #[inline]
+    fn cell(&self, from: usize, to: usize) -> usize {
+        from * self.vertex_ids.len() + to
+    }
+
+    fn relax(&mut self, from: usize, to: usize, distance: Distance) {
+        let cell = self.cell(from, to);
+        if self.distances[cell].is_none_or(|known| distance < known) {
+            self.distances[cell] = Some(distance);
+        }
+    }
+}
+
+/// Computes the distances between every pair of vertices with the Floyd-Warshall
+/// algorithm, which takes `O(V³)` time whatever the number of edges, so it suits
+/// dense graphs.
+///
+/// Fails when the graph has any negative cycle.
+pub fn floyd_warshall<G: GraphView>(graph: &G) -> Result<DistanceMatrix, NegativeCycle>
+where
+    G::Edge: Weighted,
+{
+    let mut matrix = DistanceMatrix::new(graph.vertex_ids());
+    let size = matrix.vertex_ids.len();
+    for from_position in 0..size {
+        for leg in graph.legs(&matrix.vertex_ids[from_position]) {
+            let to = matrix.positions[&leg.get_to_id()];
+            matrix.relax(from_position, to, leg.get_edge().weight());
+        }
+    }
+
+    for through in 0..size {
+        for from in 0..size {
+            let Some(first_leg) = matrix.distances[matrix.cell(from, through)] else {
+                continue;
+            };
+            for to in 0..size {
+                if let Some(second_leg) = matrix.distances[matrix.cell(through, to)] {
+                    matrix.relax(from, to, first_leg + second_leg);
+                }
+            }
+        }
+
+        // Stop as soon as some vertex reaches itself for less than nothing, before
+        // going around the cycle again makes the distances overflow.
+        if let Some(vertex_id) = (0..size)
+            .find(|position| matrix.distances[matrix.cell(*position, *position)] < Some(0))
+            .map(|position| matrix.vertex_ids[position])
+        {
+            return Err(bellman_ford_tree(graph, vertex_id).err().expect(
+                "A vertex that reaches itself for less than nothing is on a negative cycle",
+            ));
+        }
+    }
+
+    Ok(matrix)
+}
+
+/// Computes the distances between every pair of vertices with Johnson's algorithm.
+/// It runs Bellman-Ford once to make every weight non negative, then Dijkstra from
+/// every vertex, which takes `O(V·E·log V)` time and suits sparse graphs.
+///
+/// Fails when the graph has any negative cycle.
+pub fn johnson<G: GraphView>(graph: &G) -> Result<DistanceMatrix, NegativeCycle>
+where
+    G::Edge: Weighted,
+{
+    let potentials = potentials(graph)?;
+    let mut matrix = DistanceMatrix::new(graph.vertex_ids());
+
+    // Along any edge the potentials grow by no more than its weight, so their opposites
+    // drop by no more than it: a consistent heuristic, under which Dijkstra handles
+    // negative edges.
+    let heuristic = |vertex_id: VertexId| -potentials[&vertex_id];
+
+    for (from_position, from) in graph.vertex_ids().into_iter().enumerate() {
+        let tree = DijkstraAlgorithm::from_departure(graph, from)
+            .with_heuristic(&heuristic)
+            .into_shortest_path_tree();
+        for (to, distance) in tree.distances() {
+            matrix.relax(from_position, matrix.positions[&to], distance);
+        }
+    }
+
+    Ok(matrix)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{floyd_warshall, johnson};
+    use crate::chapter_6_graph::Graph;
+
+    #[test]
+    fn should_compute_all_pairs_distances() {
+        let mut graph = Graph::new();
+        let a = graph.add_vertex("A");
+        let b = graph.add_vertex("B");
+        let c = graph.add_vertex("C");
+        let d = graph.add_vertex("D");
+
+        graph.attach_weighted(&a, &b, 3);
+        graph.attach_weighted(&a, &c, 8);
+        graph.attach_weighted(&b, &c, -2);
+        graph.attach_weighted(&c, &a, 4);
+        graph.attach_weighted(&b, &d, 7);
+
+        for algorithm in [floyd_warshall, johnson] {
+            let matrix = algorithm(&graph).expect("Should have no negative cycle");
+
+            assert_eq!(matrix.vertex_ids(), &[a, b, c, d]);
+            assert_eq!(matrix.distance(&a, &a), Some(0));
+            assert_eq!(matrix.distance(&a, &c), Some(1));
+            assert_eq!(matrix.distance(&b, &a), Some(2));
+            assert_eq!(matrix.distance(&c, &d), Some(14));
+            assert_eq!(matrix.distance(&d, &a), None);
+        }
+    }
+
+    #[test]
+    fn should_agree_on_every_pair() {
+        let mut graph = Graph::new();
+        let ids = (0..8).map(|i| graph.add_vertex(i)).collect::<Vec<_>>();
+        for (i, from) in ids.iter().enumerate() {
+            for (j, to) in ids.iter().enumerate() {
+                if i == j || (i * 7 + j * 3) % 4 == 1 {
+                    continue;
+                }
+                // Going forward may be cheaper than nothing, but going back costs enough
+                // to keep every cycle positive.
+                let weight = if i < j {
+                    (i * 5 + j * 3) as isize % 7 - 2
+                } else {
+                    20
+                };
+                graph.attach_weighted(from, to, weight);
+            }
+        }
+
+        let matrix = floyd_warshall(&graph).expect("Should have no negative cycle");
+        assert_eq!(Ok(matrix), johnson(&graph));
+    }
+
+    #[test]
+    fn should_report_negative_cycle_anywhere_in_graph() {
+        let mut graph = Graph::new();
+        let a = graph.add_vertex("A");
+        let b = graph.add_vertex("B");
+        let c = graph.add_vertex("C");
+
+        graph.attach_weighted(&a, &b, 1);
+        graph.attach_weighted(&b, &c, 2);
+        graph.attach_weighted(&c, &b, -3);
+
+        for algorithm in [floyd_warshall, johnson] {
+            let cycle = algorithm(&graph).err().unwrap();
+            assert_eq!(cycle.vertex_ids, vec![b, c]);
+        }
+    }
+}
@@ -0,0 +1,327 @@
+use crate::chapter_6_graph::{
+    Distance, GraphView, Path, ShortestPathTree, VertexId, Weight, Weighted,
+};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::error::Error;
+use std::fmt;
+
+/// The error returned when the departure reaches a cycle whose weights add up to less
+/// than zero, so that walking around it forever keeps making paths cheaper.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NegativeCycle {
+    /// The vertices of the cycle in the direction of its edges, starting from the lowest id.
+    pub vertex_ids: Vec<VertexId>,
+}
+
+impl fmt::Display for NegativeCycle {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "the graph has a negative cycle through {} vertices",
+            self.vertex_ids.len()
+        )
+    }
+}
+
+impl Error for NegativeCycle {}
+
+/// Finds the cheapest path between two vertices of the graph using the Bellman-Ford
+/// algorithm, which accepts negative weights.
+///
+/// Fails when any negative cycle can be reached from the departure, even if the
+/// destination cannot be reached from the cycle.
+pub fn bellman_ford<G: GraphView>(
+    graph: &G,
+    departure: VertexId,
+    destination: VertexId,
+) -> Result<Option<Path<'_, G::Item>>, NegativeCycle>
+where
+    G::Edge: Weighted,
+{
+    Ok(bellman_ford_tree(graph, departure)?.path_to(&destination))
+}
+
+/// Finds the cheapest path between two vertices of the graph using the Shortest Path
+/// Faster Algorithm, a queue based Bellman-Ford that only relaxes the edges leaving
+/// vertices whose distance just changed.
+///
+/// Fails under the same conditions as [`bellman_ford`].
+pub fn spfa<G: GraphView>(
+    graph: &G,
+    departure: VertexId,
+    destination: VertexId,
+) -> Result<Option<Path<'_, G::Item>>, NegativeCycle>
+where
+    G::Edge: Weighted,
+{
+    Ok(spfa_tree(graph, departure)?.path_to(&destination))
+}
+
+pub(crate) fn bellman_ford_tree<G: GraphView>(
+    graph: &G,
+    departure: VertexId,
+) -> Result<ShortestPathTree<'_, G::Item, G::Edge>, NegativeCycle>
+where
+    G::Edge: Weighted,
+{
+    let mut distances = HashMap::new();
+    let mut parents = HashMap::new();
+    if graph.contains_vertex(&departure) {
+        distances.insert(departure, 0);
+        relax_until_settled(
+            &weighted_edges(graph),
+            &mut distances,
+            &mut parents,
+            graph.vertex_count(),
+        )?;
+    }
+    Ok(ShortestPathTree::new(graph, departure, distances, parents))
+}
+
+pub(crate) fn spfa_tree<G: GraphView>(
+    graph: &G,
+    departure: VertexId,
+) -> Result<ShortestPathTree<'_, G::Item, G::Edge>, NegativeCycle>
+where
+    G::Edge: Weighted,
+{
+    let mut distances = HashMap::new();
+    let mut parents = HashMap::new();
+    if !graph.contains_vertex(&departure) {
+        return Ok(ShortestPathTree::new(graph, departure, distances, parents));
+    }
+    distances.insert(departure, 0);
+    // How many edges the path to each vertex takes. A path with as many edges as
+    // there are vertices goes around a cycle, which only pays off when it is negative.
+    let mut lengths = HashMap::from([(departure, 0)]);
+    let mut queue = VecDeque::from([departure]);
+    let mut queued = HashSet::from([departure]);
+
+    while let Some(from) = queue.pop_front() {
+        queued.remove(&from);
+        for leg in graph.legs(&from) {
+            let to = leg.get_to_id();
+            let distance = distances[&from] + leg.get_edge().weight();
+            if distances.get(&to).is_some_and(|known| *known <= distance) {
+                continue;
+            }
+            distances.insert(to, distance);
+            parents.insert(to, from);
+            let length = lengths[&from] + 1;
+            lengths.insert(to, length);
+
+            if length >= graph.vertex_count() {
+                return match find_cycle(&parents, to) {
+                    Some(cycle) => Err(cycle),
+                    None => bellman_ford_tree(graph, departure),
+                };
+            }
+            if queued.insert(to) {
+                queue.push_back(to);
+            }
+        }
+    }
+
+    Ok(ShortestPathTree::new(graph, departure, distances, parents))
+}
+
+/// The distances from a virtual vertex attached to every other one with weight zero,
+/// which Johnson's algorithm uses to make every weight non negative.
+///
+/// Fails when the graph has any negative cycle.
+pub(crate) fn potentials<G: GraphView>(
+    graph: &G,
+) -> Result<HashMap<VertexId, Distance>, NegativeCycle>
+where
+    G::Edge: Weighted,
+{
+    // Relaxing the edges leaving the virtual vertex sets every distance to zero.
+    let mut distances = graph
+        .vertex_ids()
+        .into_iter()
+        .map(|vertex_id| (vertex_id, 0))
+        .collect();
+    let mut parents = HashMap::new();
+    relax_until_settled(
+        &weighted_edges(graph),
+        &mut distances,
+        &mut parents,
+        graph.vertex_count(),
+    )?;
+    Ok(distances)
+}
+
+/// Relaxes every edge once per vertex but one, then once more to check that nothing
+/// changes, which would mean a negative cycle.
+fn relax_until_settled(
+    edges: &[(VertexId, VertexId, Weight)],
+    distances: &mut HashMap<VertexId, Distance>,
+    parents: &mut HashMap<VertexId, VertexId>,
+    vertex_count: usize,
+) -> Result<(), NegativeCycle> {
+    for _ in 1..vertex_count {
+        if relax_edges(edges, distances, parents).is_none() {
+            return Ok(());
+        }
+    }
+
+    match relax_edges(edges, distances, parents) {
+        Some(vertex_id) => Err(find_cycle(parents, vertex_id)
+            .expect("A vertex relaxed after |V| - 1 rounds leads to a cycle")),
+        None => Ok(()),
+    }
+}
+
+/// Every edge as `(from, to, weight)`, sorted by the vertex it leaves so that runs are
+/// reproducible.
+fn weighted_edges<G: GraphView>(graph: &G) -> Vec<(VertexId, VertexId, Weight)>
+where
+    G::Edge: Weighted,
+{
+    let mut edges = Vec::new();
+    for from in graph.vertex_ids() {
+        for leg in graph.legs(&from) {
+            edges.push((from, leg.get_to_id(), leg.get_edge().weight()));
+        }
+    }
+    edges
+}
+
+/// Relaxes every edge once, returning the last vertex that got closer to the departure.
+fn relax_edges(
+    edges: &[(VertexId, VertexId, Weight)],
+    distances: &mut HashMap<VertexId, Distance>,
+    parents: &mut HashMap<VertexId, VertexId>,
+) -> Option<VertexId> {
+    let mut relaxed = None;
+    for &(from, to, weight) in edges {
+        let Some(distance) = distances.get(&from).map(|known| known + weight) else {
+            continue;
+        };
+        if distances.get(&to).is_none_or(|known| distance < *known) {
+            distances.insert(to, distance);
+            parents.insert(to, from);
+            relaxed = Some(to);
+        }
+    }
+    relaxed
+}
+
+/// Follows the parents from the given vertex until one of them shows up twice.
+fn find_cycle(parents: &HashMap<VertexId, VertexId>, from: VertexId) -> Option<NegativeCycle> {
+    let mut seen = HashSet::new();
+    let mut vertex_id = from;
+    while seen.insert(vertex_id) {
+        vertex_id = *parents.get(&vertex_id)?;
+    }
+
+    let mut vertex_ids = vec![vertex_id];
+    let mut parent = parents[&vertex_id];
+    while parent != vertex_id {
+        vertex_ids.push(parent);
+        parent = parents[&parent];
+    }
+    vertex_ids.reverse();
+
+    let lowest = (0..vertex_ids.len()).min_by_key(|i| vertex_ids[*i])?;
+    vertex_ids.rotate_left(lowest);
+    Some(NegativeCycle { vertex_ids })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{NegativeCycle, bellman_ford, spfa};
+    use crate::chapter_6_graph::Graph;
+
+    #[test]
+    fn should_find_path_through_negative_edges() {
+        let mut graph = Graph::new();
+        let start = graph.add_vertex("Start");
+        let a = graph.add_vertex("A");
+        let b = graph.add_vertex("B");
+        let finish = graph.add_vertex("Finish");
+
+        graph.attach_weighted(&start, &finish, 5);
+        graph.attach_weighted(&start, &a, 10);
+        graph.attach_weighted(&a, &b, -20);
+        graph.attach_weighted(&b, &finish, 3);
+
+        for algorithm in [bellman_ford, spfa] {
+            let path = algorithm(&graph, start, finish)
+                .expect("Should have no negative cycle")
+                .expect("Should find a route");
+
+            assert_eq!(path.distance, -7);
+            assert_eq!(
+                path.waypoints
+                    .iter()
+                    .map(|vertex| *vertex.get_item())
+                    .collect::<Vec<_>>(),
+                vec!["Start", "A", "B", "Finish"]
+            );
+        }
+    }
+
+    #[test]
+    fn should_report_negative_cycle() {
+        let mut graph = Graph::new();
+        let start = graph.add_vertex("Start");
+        let a = graph.add_vertex("A");
+        let b = graph.add_vertex("B");
+        let c = graph.add_vertex("C");
+        let finish = graph.add_vertex("Finish");
+
+        graph.attach_weighted(&start, &b, 1);
+        graph.attach_weighted(&b, &c, 1);
+        graph.attach_weighted(&c, &a, -3);
+        graph.attach_weighted(&a, &b, 1);
+        graph.attach_weighted(&c, &finish, 1);
+
+        for algorithm in [bellman_ford, spfa] {
+            let Err(cycle) = algorithm(&graph, start, finish) else {
+                panic!("Should detect the negative cycle");
+            };
+
+            assert_eq!(
+                cycle,
+                NegativeCycle {
+                    vertex_ids: vec![a, b, c]
+                }
+            );
+        }
+    }
+
+    #[test]
+    fn should_ignore_negative_cycle_out_of_reach() {
+        let mut graph = Graph::new();
+        let start = graph.add_vertex("Start");
+        let finish = graph.add_vertex("Finish");
+        let a = graph.add_vertex("A");
+        let b = graph.add_vertex("B");
+
+        graph.attach_weighted(&start, &finish, 2);
+        graph.attach_weighted(&a, &b, -1);
+        graph.attach_weighted(&b, &a, -1);
+        graph.attach_weighted(&a, &finish, 0);
+
+        for algorithm in [bellman_ford, spfa] {
+            let path = algorithm(&graph, start, finish).unwrap().unwrap();
+            assert_eq!(path.distance, 2);
+            assert!(algorithm(&graph, finish, start).unwrap().is_none());
+        }
+    }
+
+    #[test]
+    fn should_treat_negative_undirected_edge_as_cycle() {
+        let mut graph = Graph::undirected();
+        let a = graph.add_vertex("A");
+        let b = graph.add_vertex("B");
+
+        graph.attach_weighted(&a, &b, -1);
+
+        for algorithm in [bellman_ford, spfa] {
+            let cycle = algorithm(&graph, a, b).err().unwrap();
+            assert_eq!(cycle.vertex_ids, vec![a, b]);
+        }
+    }
+}
@@ -0,0 +1,347 @@
+use crate::chapter_6_graph::{Distance, GraphView, Path, VertexId, Weight, Weighted};
+use crate::chapter_9_dijkstra_algorithm::{DijkstraAlgorithm, NegativeWeight, find_negative_edge};
+use priority_queue::PriorityQueue;
+use std::collections::{HashMap, HashSet};
+
+/// Finds the cheapest path between two vertices of the graph by running Dijkstra's
+/// algorithm from both ends at once, until the two searches meet. Each search only
+/// has to cover about half the distance, which on road-like graphs means exploring
+/// far fewer vertices than a search from the departure alone.
+///
+/// The search from the destination follows edges backwards, so on directed graphs that
+/// keep no index of the edges reaching each vertex, such as [`Graph`], it first gathers
+/// them. Freeze the graph into a [`CsrGraph`] to gather them once for every search.
+///
+/// [`Graph`]: crate::chapter_6_graph::Graph
+/// [`CsrGraph`]: crate::chapter_6_csr_graph::CsrGraph
+///
+/// Fails when any edge of the graph has a negative weight.
+pub fn bidirectional_dijkstra<G: GraphView>(
+    graph: &G,
+    departure: VertexId,
+    destination: VertexId,
+) -> Result<Option<Path<'_, G::Item>>, NegativeWeight>
+where
+    G::Edge: Weighted,
+{
+    if graph.has_negative_weights() {
+        return Err(find_negative_edge(graph).expect("Some edge should have a negative weight"));
+    }
+    if graph.is_directed() && graph.incoming_weights(&destination).is_none() {
+        let incoming = incoming_legs(graph);
+        return Ok(search(graph, departure, destination, |to| {
+            incoming.get(&to).map_or(&[], Vec::as_slice)
+        }));
+    }
+    Ok(bidirectional_path(graph, departure, destination))
+}
+
+/// Runs the bidirectional search, trusting the caller that no weight is negative. Falls
+/// back to a search from the departure alone on directed graphs that keep no index of
+/// the edges reaching each vertex, as gathering it would cost more than it saves.
+pub(crate) fn bidirectional_path<G: GraphView>(
+    graph: &G,
+    departure: VertexId,
+    destination: VertexId,
+) -> Option<Path<'_, G::Item>>
+where
+    G::Edge: Weighted,
+{
+    if graph.is_directed() && graph.incoming_weights(&destination).is_none() {
+        return DijkstraAlgorithm::new(graph, departure, destination).into_shortest_path();
+    }
+    search(graph, departure, destination, |to| {
+        graph.incoming_weights(&to).unwrap_or_default()
+    })
+}
+
+/// Grows a search from each end until they meet. The backward one follows the edges
+/// reaching each vertex, as `(from, weight)`, unless the graph is undirected and its
+/// legs already go both ways.
+fn search<'a, 'b, G: GraphView>(
+    graph: &'a G,
+    departure: VertexId,
+    destination: VertexId,
+    incoming: impl Fn(VertexId) -> &'b [(VertexId, Weight)],
+) -> Option<Path<'a, G::Item>>
+where
+    G::Edge: Weighted,
+{
+    if !graph.contains_vertex(&departure) || !graph.contains_vertex(&destination) {
+        return None;
+    }
+
+    let mut forward = Frontier::new(departure);
+    let mut backward = Frontier::new(destination);
+    // The cheapest path found so far, with the vertex where its two halves meet.
+    let mut best = (departure == destination).then_some((0, departure));
+
+    while let (Some(forward_distance), Some(backward_distance)) =
+        (forward.closest_distance(), backward.closest_distance())
+    {
+        // Any path left to find has a vertex settled by neither search, so it costs
+        // at least as much as both closest distances together.
+        if best.is_some_and(|(distance, _)| forward_distance + backward_distance >= distance) {
+            break;
+        }
+
+        if forward_distance <= backward_distance {
+            let (from, from_distance) = forward.settle()?;
+            for leg in graph.legs(&from) {
+                let to = leg.get_to_id();
+                let distance = from_distance + leg.get_edge().weight();
+                forward.relax(to, from, distance);
+                meet(&mut best, to, distance, backward.distances.get(&to));
+            }
+        } else {
+            let (to, to_distance) = backward.settle()?;
+            let mut relax = |from: VertexId, weight: Weight| {
+                let distance = to_distance + weight;
+                backward.relax(from, to, distance);
+                meet(&mut best, from, distance, forward.distances.get(&from));
+            };
+            if graph.is_directed() {
+                for (from, weight) in incoming(to) {
+                    relax(*from, *weight);
+                }
+            } else {
+                for leg in graph.legs(&to) {
+                    relax(leg.get_to_id(), leg.get_edge().weight());
+                }
+            }
+        }
+    }
+
+    let (distance, meeting) = best?;
+    let mut vertex_ids = vec![meeting];
+    let mut vertex_id = meeting;
+    while let Some(parent) = forward.parents.get(&vertex_id) {
+        vertex_id = *parent;
+        vertex_ids.push(vertex_id);
+    }
+    vertex_ids.reverse();
+    vertex_id = meeting;
+    while let Some(next) = backward.parents.get(&vertex_id) {
+        vertex_id = *next;
+        vertex_ids.push(vertex_id);
+    }
+
+    Some(Path {
+        distance,
+        waypoints: vertex_ids
+            .iter()
+            .map(|vertex_id| graph.vertex(vertex_id))
+            .collect::<Option<_>>()?,
+    })
+}
+
+/// Keeps the path through the given vertex when it beats the best one found so far.
+fn meet(
+    best: &mut Option<(Distance, VertexId)>,
+    vertex_id: VertexId,
+    distance: Distance,
+    other_side: Option<&Distance>,
+) {
+    let Some(other_side) = other_side else {
+        return;
+    };
+    let distance = distance + other_side;
+    if best.is_none_or(|(known, _)| distance < known) {
+        *best = Some((distance, vertex_id));
+    }
+}
+
+/// The edges reaching each vertex, as `(from, weight)`.
+fn incoming_legs<G: GraphView>(graph: &G) -> HashMap<VertexId, Vec<(VertexId, Weight)>>
+where
+    G::Edge: Weighted,
+{
+    let mut incoming: HashMap<VertexId, Vec<(VertexId, Weight)>> = HashMap::new();
+    for from in graph.vertex_ids() {
+        for leg in graph.legs(&from) {
+            incoming
+                .entry(leg.get_to_id())
+                .or_default()
+                .push((from, leg.get_edge().weight()));
+        }
+    }
+    incoming
+}
+
+/// One of the two searches, growing from its own end.
+struct Frontier {
+    /// Stores the vertex id with its priority, which is minus its distance.
+    priority_queue: PriorityQueue<VertexId, Weight>,
+    distances: HashMap<VertexId, Distance>,
+    /// The vertex each one was reached from, towards the end this search started at.
+    parents: HashMap<VertexId, VertexId>,
+    settled: HashSet<VertexId>,
+}
+
+impl Frontier {
+    fn new(start: VertexId) -> Self {
+        Frontier {
+            priority_queue: PriorityQueue::from(vec![(start, 0)]),
+            distances: HashMap::from([(start, 0)]),
+            parents: HashMap::new(),
+            settled: HashSet::new(),
+        }
+    }
+
+    fn closest_distance(&self) -> Option<Distance> {
+        self.priority_queue.peek().map(|(_, priority)| -priority)
+    }
+
+    fn settle(&mut self) -> Option<(VertexId, Distance)> {
+        let (vertex_id, priority) = self.priority_queue.pop()?;
+        self.settled.insert(vertex_id);
+        Some((vertex_id, -priority))
+    }
+
+    fn relax(&mut self, vertex_id: VertexId, parent: VertexId, distance: Distance) {
+        if self.settled.contains(&vertex_id)
+            || self
+                .distances
+                .get(&vertex_id)
+                .is_some_and(|known| *known <= distance)
+        {
+            return;
+        }
+        self.distances.insert(vertex_id, distance);
+        self.parents.insert(vertex_id, parent);
+        self.priority_queue.push(vertex_id, -distance);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::bidirectional_dijkstra;
+    use crate::chapter_6_graph::{Graph, GraphView, VertexId};
+    use crate::chapter_9_dijkstra_algorithm::{NegativeWeight, dijkstra};
+    use std::collections::HashSet;
+
+    fn waypoint_ids(graph: &Graph<usize>, from: VertexId, to: VertexId) -> Option<Vec<VertexId>> {
+        bidirectional_dijkstra(graph, from, to)
+            .unwrap()
+            .map(|path| path.waypoints.iter().map(|vertex| vertex.id).collect())
+    }
+
+    fn scattered(mut graph: Graph<usize>) -> (Graph<usize>, Vec<VertexId>) {
+        let ids = (0..40).map(|i| graph.add_vertex(i)).collect::<Vec<_>>();
+        for (i, from) in ids.iter().enumerate() {
+            for (j, to) in ids.iter().enumerate() {
+                if i != j && (i * 7 + j * 13) % 11 < 2 {
+                    graph.attach_weighted(from, to, ((i * 5 + j * 3) % 9) as isize);
+                }
+            }
+        }
+        (graph, ids)
+    }
+
+    #[test]
+    fn should_find_same_distances_as_dijkstra() {
+        for graph in [Graph::new(), Graph::undirected()] {
+            let (graph, ids) = scattered(graph);
+
+            for from in &ids {
+                for to in &ids {
+                    let expected = dijkstra(&graph, *from, *to).unwrap();
+                    let found = bidirectional_dijkstra(&graph, *from, *to).unwrap();
+
+                    assert_eq!(
+                        found.as_ref().map(|path| path.distance),
+                        expected.map(|path| path.distance)
+                    );
+                    if let Some(path) = found {
+                        let weights = path
+                            .waypoints
+                            .windows(2)
+                            .map(|pair| *graph.get_edge(&pair[0].id, &pair[1].id).unwrap())
+                            .sum::<isize>();
+                        assert_eq!(weights, path.distance);
+                        assert_eq!(path.waypoints[0].id, *from);
+                        assert_eq!(path.waypoints.last().unwrap().id, *to);
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn should_search_frozen_graphs_backwards_along_incoming_edges() {
+        let (graph, ids) = scattered(Graph::new());
+        let expected = ids
+            .iter()
+            .flat_map(|from| ids.iter().map(move |to| (*from, *to)))
+            .map(|(from, to)| {
+                dijkstra(&graph, from, to)
+                    .unwrap()
+                    .map(|path| path.distance)
+            })
+            .collect::<Vec<_>>();
+        let incoming = ids
+            .iter()
+            .map(|to| {
+                graph
+                    .edges()
+                    .filter(|(_, reached, _)| reached == to)
+                    .map(|(from, _, weight)| (from, *weight))
+                    .collect::<HashSet<_>>()
+            })
+            .collect::<Vec<_>>();
+
+        assert!(graph.incoming_weights(&ids[0]).is_none());
+        let frozen = graph.freeze();
+
+        for (to, reaching) in ids.iter().zip(incoming) {
+            let indexed = frozen.incoming_weights(to).unwrap();
+            assert_eq!(indexed.len(), reaching.len());
+            assert_eq!(indexed.iter().copied().collect::<HashSet<_>>(), reaching);
+        }
+        let found = ids
+            .iter()
+            .flat_map(|from| ids.iter().map(move |to| (*from, *to)))
+            .map(|(from, to)| {
+                bidirectional_dijkstra(&frozen, from, to)
+                    .unwrap()
+                    .map(|path| path.distance)
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(found, expected);
+    }
+
+    #[test]
+    fn should_follow_edge_directions() {
+        let mut graph = Graph::new();
+        let a = graph.add_vertex(0);
+        let b = graph.add_vertex(1);
+        let c = graph.add_vertex(2);
+
+        graph.attach_weighted(&a, &b, 1);
+        graph.attach_weighted(&c, &b, 1);
+        graph.attach_weighted(&a, &c, 5);
+
+        assert_eq!(waypoint_ids(&graph, a, b), Some(vec![a, b]));
+        assert_eq!(waypoint_ids(&graph, a, c), Some(vec![a, c]));
+        assert_eq!(waypoint_ids(&graph, b, a), None);
+        assert_eq!(waypoint_ids(&graph, c, c), Some(vec![c]));
+    }
+
+    #[test]
+    fn should_refuse_negative_weights() {
+        let mut graph = Graph::new();
+        let a = graph.add_vertex(0);
+        let b = graph.add_vertex(1);
+
+        graph.attach_weighted(&a, &b, -1);
+
+        assert_eq!(
+            bidirectional_dijkstra(&graph, a, b).err(),
+            Some(NegativeWeight {
+                from: a,
+                to: b,
+                weight: -1
+            })
+        );
+    }
+}
@@ -1,6 +1,106 @@
-use crate::chapter_6_graph::{Distance, Graph, Path, VertexId, Weight};
+use crate::chapter_6_graph::{
+    Distance, GraphView, Path, ShortestPathTree, VertexId, Weight, Weighted,
+};
 use priority_queue::PriorityQueue;
 use std::collections::{HashMap, HashSet};
+use std::error::Error;
+use std::fmt;
+
+/// The error returned when Dijkstra's algorithm is asked to run over a negative edge,
+/// which it cannot handle. The Bellman-Ford algorithm can.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NegativeWeight {
+    pub from: VertexId,
+    pub to: VertexId,
+    pub weight: Weight,
+}
+
+impl fmt::Display for NegativeWeight {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "the edge from {:?} to {:?} has negative weight {}",
+            self.from, self.to, self.weight
+        )
+    }
+}
+
+impl Error for NegativeWeight {}
+
+/// Finds the cheapest path between two vertices of the graph using Dijkstra's algorithm.
+///
+/// Fails when any edge of the graph has a negative weight.
+pub fn dijkstra<G: GraphView>(
+    graph: &G,
+    departure: VertexId,
+    destination: VertexId,
+) -> Result<Option<Path<'_, G::Item>>, NegativeWeight>
+where
+    G::Edge: Weighted,
+{
+    if graph.has_negative_weights() {
+        return Err(find_negative_edge(graph).expect("Some edge should have a negative weight"));
+    }
+    Ok(DijkstraAlgorithm::new(graph, departure, destination).into_shortest_path())
+}
+
+/// The first edge with a negative weight, leaving the vertices in the order they were added.
+pub(crate) fn find_negative_edge<G: GraphView + ?Sized>(graph: &G) -> Option<NegativeWeight>
+where
+    G::Edge: Weighted,
+{
+    graph.vertex_ids().into_iter().find_map(|from| {
+        graph.legs(&from).iter().find_map(|leg| {
+            let weight = leg.get_edge().weight();
+            (weight < 0).then(|| NegativeWeight {
+                from,
+                to: leg.get_to_id(),
+                weight,
+            })
+        })
+    })
+}
+
+/// The outcome of a search along with how much of the graph it had to explore.
+pub struct Search<'a, T> {
+    pub path: Option<Path<'a, T>>,
+    /// How many vertices had their edges followed before reaching the destination.
+    pub expanded_vertices: usize,
+}
+
+/// Finds the cheapest path between two vertices of the graph using the A* algorithm,
+/// which explores the vertices the heuristic deems closer to the destination first.
+///
+/// The heuristic estimates the distance between two vertex items. It must be
+/// consistent: it never decreases by more than the weight of an edge followed towards
+/// the destination. That makes it admissible as well, so the path found is the
+/// cheapest one. Debug builds assert it on every edge followed. A heuristic that always
+/// returns zero turns A* into Dijkstra's algorithm.
+pub fn astar<G: GraphView>(
+    graph: &G,
+    departure: VertexId,
+    destination: VertexId,
+    heuristic: impl Fn(&G::Item, &G::Item) -> Distance,
+) -> Search<'_, G::Item>
+where
+    G::Edge: Weighted,
+{
+    let Some(target) = graph.vertex(&destination) else {
+        return Search {
+            path: None,
+            expanded_vertices: 0,
+        };
+    };
+    let estimate = |vertex_id: VertexId| {
+        graph
+            .vertex(&vertex_id)
+            .map_or(0, |vertex| heuristic(vertex.get_item(), &target.item))
+    };
+
+    DijkstraAlgorithm::new(graph, departure, destination)
+        .with_heuristic(&estimate)
+        .into_search()
+}
 
 struct Waypoint {
     distance: Distance,
@@ -8,39 +108,110 @@ struct Waypoint {
     vertex: VertexId,
 }
 
-pub(crate) struct DijkstraAlgorithm<'a, T> {
-    /// Stores the vertex id with its priority, which is minus the distance from the departure vertex.
+pub(crate) struct DijkstraAlgorithm<'a, 'h, G> {
+    /// Stores the vertex id with its priority, which is minus the distance from the departure
+    /// vertex plus the estimated distance to the destination.
     priority_queue: PriorityQueue<VertexId, Weight>,
     visited_vertices: HashSet<VertexId>,
     shorter_waypoints: HashMap<VertexId, Waypoint>,
     departure: VertexId,
-    destination: VertexId,
-    graph: &'a Graph<T>,
+    /// Where the search stops. Without one, it reaches every vertex it can.
+    destination: Option<VertexId>,
+    /// Estimates the distance from a vertex to the destination. Without one, the estimate
+    /// is zero and the search is plain Dijkstra.
+    heuristic: Option<&'h dyn Fn(VertexId) -> Distance>,
+    /// Tells which edges, given as `(from, to)`, the search must not follow.
+    avoided: Option<&'h dyn Fn(VertexId, VertexId) -> bool>,
+    expanded_vertices: usize,
+    graph: &'a G,
 }
 
-impl<'a, T> DijkstraAlgorithm<'a, T> {
-    pub(crate) fn new(graph: &'a Graph<T>, departure: VertexId, destination: VertexId) -> Self {
-        let mut dt = DijkstraAlgorithm {
+impl<'a, 'h, G: GraphView> DijkstraAlgorithm<'a, 'h, G>
+where
+    G::Edge: Weighted,
+{
+    pub(crate) fn new(graph: &'a G, departure: VertexId, destination: VertexId) -> Self {
+        DijkstraAlgorithm {
+            destination: Some(destination),
+            ..DijkstraAlgorithm::from_departure(graph, departure)
+        }
+    }
+
+    pub(crate) fn from_departure(graph: &'a G, departure: VertexId) -> Self {
+        DijkstraAlgorithm {
             priority_queue: PriorityQueue::new(),
             visited_vertices: HashSet::new(),
             shorter_waypoints: HashMap::new(),
             departure,
-            destination,
+            destination: None,
+            heuristic: None,
+            avoided: None,
+            expanded_vertices: 0,
             graph,
-        };
+        }
+    }
+
+    /// Runs A* with the given heuristic, which must be consistent. Negative weights are
+    /// fine as long as the heuristic makes up for them, which is how Johnson's algorithm
+    /// gets away with running Dijkstra over them.
+    pub(crate) fn with_heuristic(self, heuristic: &'h dyn Fn(VertexId) -> Distance) -> Self {
+        DijkstraAlgorithm {
+            heuristic: Some(heuristic),
+            ..self
+        }
+    }
+
+    /// Leaves out of the search every edge for which the given function returns true.
+    pub(crate) fn avoiding(self, avoided: &'h dyn Fn(VertexId, VertexId) -> bool) -> Self {
+        DijkstraAlgorithm {
+            avoided: Some(avoided),
+            ..self
+        }
+    }
 
-        dt.save_waypoint(Waypoint {
-            vertex: departure,
-            parent: departure,
-            distance: 0,
-        });
+    pub(crate) fn into_shortest_path(self) -> Option<Path<'a, G::Item>> {
+        self.into_search().path
+    }
 
-        dt
+    pub(crate) fn into_search(self) -> Search<'a, G::Item> {
+        let Some(destination) = self.destination else {
+            return Search {
+                path: None,
+                expanded_vertices: 0,
+            };
+        };
+        let (tree, expanded_vertices) = self.explore();
+        Search {
+            path: tree.path_to(&destination),
+            expanded_vertices,
+        }
     }
 
-    pub(crate) fn into_shortest_path(mut self) -> Option<Path<'a, T>> {
+    pub(crate) fn into_shortest_path_tree(self) -> ShortestPathTree<'a, G::Item, G::Edge> {
+        self.explore().0
+    }
+
+    /// Runs the search, returning what it found along with how many vertices it expanded.
+    fn explore(mut self) -> (ShortestPathTree<'a, G::Item, G::Edge>, usize) {
+        if self.graph.contains_vertex(&self.departure) {
+            self.save_waypoint(Waypoint {
+                vertex: self.departure,
+                parent: self.departure,
+                distance: 0,
+            });
+        }
         self.look_for_waypoints();
-        self.get_path()
+
+        let mut distances = HashMap::with_capacity(self.shorter_waypoints.len());
+        let mut parents = HashMap::with_capacity(self.shorter_waypoints.len());
+        for waypoint in self.shorter_waypoints.values() {
+            distances.insert(waypoint.vertex, waypoint.distance);
+            if waypoint.vertex != self.departure {
+                parents.insert(waypoint.vertex, waypoint.parent);
+            }
+        }
+        let tree = ShortestPathTree::new(self.graph, self.departure, distances, parents);
+        (tree, self.expanded_vertices)
     }
 
     #[inline]
@@ -49,16 +220,29 @@ impl<'a, T> DijkstraAlgorithm<'a, T> {
             let (parent, parent_distance_from_departure) =
                 self.get_vertex_closer_to_departure_not_visited_yet()?;
 
-            if parent == self.destination {
+            if Some(parent) == self.destination {
                 return None;
             }
 
-            for get_neighbor in self.graph.get_vertex(&parent)?.get_neighbors() {
+            self.expanded_vertices += 1;
+            for leg in self.graph.legs(&parent) {
+                if self
+                    .avoided
+                    .is_some_and(|avoided| avoided(parent, leg.get_to_id()))
+                {
+                    continue;
+                }
+                let weight = leg.get_edge().weight();
                 let waypoint = Waypoint {
-                    vertex: get_neighbor.get_id(),
+                    vertex: leg.get_to_id(),
                     parent,
-                    distance: parent_distance_from_departure + get_neighbor.weight,
+                    distance: parent_distance_from_departure + weight,
                 };
+                debug_assert!(
+                    self.estimate(parent) <= weight + self.estimate(waypoint.vertex),
+                    "The heuristic is not consistent: it drops by more than {weight} along the edge from {parent:?} to {:?}",
+                    waypoint.vertex
+                );
 
                 if self.discovered_shorter_path(&waypoint)
                     && !self.visited_vertices.contains(&waypoint.vertex)
@@ -70,10 +254,15 @@ impl<'a, T> DijkstraAlgorithm<'a, T> {
     }
 
     #[inline]
-    fn get_vertex_closer_to_departure_not_visited_yet(&mut self) -> Option<(VertexId, Weight)> {
-        let (next_id, minus_distance) = self.priority_queue.pop()?;
+    fn estimate(&self, vertex_id: VertexId) -> Distance {
+        self.heuristic.map_or(0, |heuristic| heuristic(vertex_id))
+    }
+
+    #[inline]
+    fn get_vertex_closer_to_departure_not_visited_yet(&mut self) -> Option<(VertexId, Distance)> {
+        let (next_id, _) = self.priority_queue.pop()?;
         self.visited_vertices.insert(next_id);
-        Some((next_id, -minus_distance))
+        Some((next_id, self.shorter_waypoints.get(&next_id)?.distance))
     }
 
     #[inline]
@@ -91,30 +280,81 @@ impl<'a, T> DijkstraAlgorithm<'a, T> {
 
     #[inline]
     fn save_waypoint(&mut self, waypoint: Waypoint) {
-        self.priority_queue
-            .push(waypoint.vertex, -waypoint.distance);
+        self.priority_queue.push(
+            waypoint.vertex,
+            -(waypoint.distance + self.estimate(waypoint.vertex)),
+        );
         self.shorter_waypoints.insert(waypoint.vertex, waypoint);
     }
+}
 
-    #[inline]
-    fn get_path(&self) -> Option<Path<'a, T>> {
-        let mut waypoints = vec![self.graph.get_vertex(&self.destination)?.vertex];
-        let mut waypoint = self.destination;
-        let distance = self.shorter_waypoints.get(&self.destination)?.distance;
+#[cfg(test)]
+mod tests {
+    use super::astar;
+    use crate::chapter_6_graph::{Distance, Graph, VertexId};
 
-        loop {
-            let travel = self.shorter_waypoints.get(&waypoint)?;
-            waypoint = travel.parent;
-            waypoints.push(self.graph.get_vertex(&waypoint)?.vertex);
-            if waypoint == self.departure {
-                break;
+    fn manhattan(from: &(isize, isize), to: &(isize, isize)) -> Distance {
+        (from.0 - to.0).abs() + (from.1 - to.1).abs()
+    }
+
+    /// A square grid where every vertex is attached to its four neighbors.
+    fn grid(side: isize) -> (Graph<(isize, isize)>, Vec<VertexId>) {
+        let mut graph = Graph::undirected();
+        let mut ids = Vec::new();
+        for x in 0..side {
+            for y in 0..side {
+                ids.push(graph.add_vertex((x, y)));
+            }
+        }
+        for x in 0..side {
+            for y in 0..side {
+                let id = ids[(x * side + y) as usize];
+                if x + 1 < side {
+                    graph.attach(&id, &ids[((x + 1) * side + y) as usize]);
+                }
+                if y + 1 < side {
+                    graph.attach(&id, &ids[(x * side + y + 1) as usize]);
+                }
             }
         }
-        waypoints.reverse();
+        (graph, ids)
+    }
 
-        Some(Path {
-            distance,
-            waypoints,
-        })
+    #[test]
+    fn should_expand_fewer_vertices_than_dijkstra() {
+        let (graph, ids) = grid(10);
+        let departure = ids[0];
+        let destination = ids[9];
+
+        let dijkstra = astar(&graph, departure, destination, |_, _| 0);
+        let astar = graph.find_path_astar(departure, destination, manhattan);
+
+        assert_eq!(dijkstra.path.unwrap().distance, 9);
+        let path = astar.path.unwrap();
+        assert_eq!(path.distance, 9);
+        assert_eq!(path.waypoints.len(), 10);
+        assert!(path.waypoints.iter().all(|vertex| vertex.get_item().0 == 0));
+        assert!(astar.expanded_vertices < dijkstra.expanded_vertices);
+        assert_eq!(astar.expanded_vertices, 9);
+    }
+
+    #[test]
+    fn should_not_find_path_to_missing_vertex() {
+        let (mut graph, ids) = grid(2);
+        graph.remove_vertex(&ids[3]);
+
+        let search = graph.find_path_astar(ids[0], ids[3], manhattan);
+
+        assert!(search.path.is_none());
+        assert_eq!(search.expanded_vertices, 0);
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "heuristic is not consistent")]
+    fn should_assert_heuristic_consistency() {
+        let (graph, ids) = grid(3);
+
+        graph.find_path_astar(ids[0], ids[8], |from, to| 10 * manhattan(from, to));
     }
 }
@@ -0,0 +1,216 @@
+use crate::chapter_6_graph::{Distance, GraphView, Path, VertexId, Weight, Weighted};
+use crate::chapter_9_bellman_ford_algorithm::{NegativeCycle, potentials};
+use crate::chapter_9_dijkstra_algorithm::DijkstraAlgorithm;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+
+/// Finds up to `k` paths from the departure to the destination with Yen's algorithm,
+/// the cheapest first. Paths never go through the same vertex twice.
+///
+/// Every path after the first one branches off an earlier path at some vertex, the
+/// spur, and takes the cheapest way from there that none of the earlier paths sharing
+/// the same start takes.
+///
+/// Negative weights are fine, as the searches run over the potentials of Johnson's
+/// algorithm, so it only fails when the graph has a negative cycle.
+pub fn k_shortest_paths<G: GraphView>(
+    graph: &G,
+    departure: VertexId,
+    destination: VertexId,
+    k: usize,
+) -> Result<Vec<Path<'_, G::Item>>, NegativeCycle>
+where
+    G::Edge: Weighted,
+{
+    let potentials = if graph.has_negative_weights() {
+        potentials(graph)?
+    } else {
+        HashMap::new()
+    };
+    // The same consistent heuristic that lets Johnson's algorithm run Dijkstra over
+    // negative weights.
+    let heuristic = |vertex_id: VertexId| -potentials.get(&vertex_id).copied().unwrap_or(0);
+    let cheapest_path = |spur: VertexId, avoided: &dyn Fn(VertexId, VertexId) -> bool| {
+        DijkstraAlgorithm::new(graph, spur, destination)
+            .with_heuristic(&heuristic)
+            .avoiding(avoided)
+            .into_shortest_path()
+            .map(|path| Route {
+                distance: path.distance,
+                vertex_ids: path.waypoints.iter().map(|vertex| vertex.id).collect(),
+            })
+    };
+
+    let mut routes = Vec::new();
+    let Some(first) = cheapest_path(departure, &|_, _| false) else {
+        return Ok(Vec::new());
+    };
+    let mut seen = HashSet::from([first.vertex_ids.clone()]);
+    let mut candidates = BinaryHeap::from([Reverse(first)]);
+
+    while let Some(Reverse(route)) = candidates.pop() {
+        routes.push(route);
+        if routes.len() >= k {
+            break;
+        }
+        let route = &routes[routes.len() - 1];
+
+        let mut root_distance = 0;
+        for spur_position in 0..route.vertex_ids.len() - 1 {
+            let root = &route.vertex_ids[..=spur_position];
+            let spur = route.vertex_ids[spur_position];
+            // The next edge of every path found so far that starts the same way.
+            let taken = routes
+                .iter()
+                .filter(|found| found.vertex_ids.starts_with(root))
+                .filter_map(|found| found.vertex_ids.get(spur_position + 1))
+                .map(|next| (spur, *next))
+                .collect::<HashSet<_>>();
+            // The root may not be walked again, or the path would not be loopless.
+            let rooted = root[..spur_position]
+                .iter()
+                .copied()
+                .collect::<HashSet<_>>();
+            let avoided = |from, to| taken.contains(&(from, to)) || rooted.contains(&to);
+
+            if let Some(spur_route) = cheapest_path(spur, &avoided) {
+                let mut vertex_ids = root[..spur_position].to_vec();
+                vertex_ids.extend(spur_route.vertex_ids);
+                if seen.insert(vertex_ids.clone()) {
+                    candidates.push(Reverse(Route {
+                        distance: root_distance + spur_route.distance,
+                        vertex_ids,
+                    }));
+                }
+            }
+
+            root_distance += weight_between(graph, spur, route.vertex_ids[spur_position + 1]);
+        }
+    }
+    routes.truncate(k);
+
+    Ok(routes
+        .into_iter()
+        .map(|route| Path {
+            distance: route.distance,
+            waypoints: route
+                .vertex_ids
+                .iter()
+                .filter_map(|vertex_id| graph.vertex(vertex_id))
+                .collect(),
+        })
+        .collect())
+}
+
+/// A path kept by its ids, ordered by distance first.
+#[derive(PartialEq, Eq, PartialOrd, Ord)]
+struct Route {
+    distance: Distance,
+    vertex_ids: Vec<VertexId>,
+}
+
+fn weight_between<G: GraphView>(graph: &G, from: VertexId, to: VertexId) -> Weight
+where
+    G::Edge: Weighted,
+{
+    graph
+        .legs(&from)
+        .iter()
+        .find(|leg| leg.get_to_id() == to)
+        .map(|leg| leg.get_edge().weight())
+        .expect("Consecutive waypoints of a path are attached")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::k_shortest_paths;
+    use crate::chapter_6_graph::{Graph, VertexId};
+
+    fn routes(graph: &Graph<char>, from: VertexId, to: VertexId, k: usize) -> Vec<(isize, String)> {
+        k_shortest_paths(graph, from, to, k)
+            .unwrap()
+            .iter()
+            .map(|path| {
+                let items = path.waypoints.iter().map(|vertex| vertex.item).collect();
+                (path.distance, items)
+            })
+            .collect()
+    }
+
+    /// The example from the Wikipedia page on Yen's algorithm.
+    fn wikipedia() -> (Graph<char>, VertexId, VertexId) {
+        let mut graph = Graph::new();
+        let ids = "CDEFGH"
+            .chars()
+            .map(|item| graph.add_vertex(item))
+            .collect::<Vec<_>>();
+        let id = |item: char| ids["CDEFGH".find(item).unwrap()];
+        for (from, to, weight) in [
+            ('C', 'D', 3),
+            ('C', 'E', 2),
+            ('D', 'F', 4),
+            ('E', 'D', 1),
+            ('E', 'F', 2),
+            ('E', 'G', 3),
+            ('F', 'G', 2),
+            ('F', 'H', 1),
+            ('G', 'H', 2),
+        ] {
+            graph.attach_weighted(&id(from), &id(to), weight);
+        }
+        (graph, id('C'), id('H'))
+    }
+
+    #[test]
+    fn should_find_k_shortest_paths() {
+        let (graph, departure, destination) = wikipedia();
+
+        assert_eq!(
+            routes(&graph, departure, destination, 3),
+            vec![
+                (5, "CEFH".to_string()),
+                (7, "CEGH".to_string()),
+                (8, "CDFH".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn should_find_every_loopless_path_in_ascending_order() {
+        let (graph, departure, destination) = wikipedia();
+
+        let found = routes(&graph, departure, destination, 100);
+
+        assert_eq!(found.len(), 7);
+        assert!(found.windows(2).all(|pair| pair[0].0 <= pair[1].0));
+        for (_, items) in &found {
+            let unique = items.chars().collect::<std::collections::HashSet<_>>();
+            assert_eq!(unique.len(), items.len());
+        }
+        assert_eq!(found.last().map(|(distance, _)| *distance), Some(11));
+    }
+
+    #[test]
+    fn should_handle_negative_weights_and_missing_paths() {
+        let mut graph = Graph::new();
+        let a = graph.add_vertex('A');
+        let b = graph.add_vertex('B');
+        let c = graph.add_vertex('C');
+        let lonely = graph.add_vertex('L');
+
+        graph.attach_weighted(&a, &b, 4);
+        graph.attach_weighted(&a, &c, 1);
+        graph.attach_weighted(&c, &b, -2);
+
+        assert_eq!(
+            routes(&graph, a, b, 5),
+            vec![(-1, "ACB".to_string()), (4, "AB".to_string())]
+        );
+        assert!(routes(&graph, a, lonely, 5).is_empty());
+        assert!(routes(&graph, a, b, 0).is_empty());
+        assert_eq!(routes(&graph, a, a, 5), vec![(0, "A".to_string())]);
+
+        graph.attach_weighted(&b, &a, -5);
+        assert!(k_shortest_paths(&graph, a, b, 2).is_err());
+    }
+}
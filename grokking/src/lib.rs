@@ -2,16 +2,74 @@
 
 pub mod chapter_2_selection_sort;
 pub mod chapter_3_tail_factorial;
-mod chapter_4_bad_safe_deque;
-mod chapter_4_deque;
+pub mod chapter_4_bad_safe_deque;
+pub mod chapter_4_deque;
+pub mod chapter_4_linked_list;
 pub mod chapter_4_maximum_common_divisor;
 mod chapter_4_mergesort;
-mod chapter_4_persistent_stack;
+pub mod chapter_4_persistent_stack;
 pub mod chapter_4_quicksort;
 pub mod chapter_4_recursive_binary_search;
-mod chapter_4_stack;
-mod chapter_5_hashmap;
-mod chapter_5_hashset;
-mod chapter_6_graph;
-mod chapter_8_avl_binary_tree;
-mod chapter_9_dijkstra_algorithm;
+pub mod chapter_4_stack;
+pub mod chapter_5_hashmap;
+pub mod chapter_5_hashset;
+pub mod chapter_5_robin_hood_hashmap;
+pub mod chapter_6_csr_graph;
+pub mod chapter_6_graph;
+pub mod chapter_6_graph_formats;
+pub mod chapter_6_max_flow;
+pub mod chapter_6_strongly_connected_components;
+pub mod chapter_6_topological_sort;
+pub mod chapter_6_traversal;
+pub mod chapter_8_avl_binary_tree;
+pub mod chapter_8_avl_map;
+pub mod chapter_8_disjoint_set;
+pub mod chapter_8_minimum_spanning_tree;
+pub mod chapter_8_persistent_avl_map;
+pub mod chapter_9_all_pairs_shortest_paths;
+pub mod chapter_9_bellman_ford_algorithm;
+pub mod chapter_9_bidirectional_dijkstra;
+pub mod chapter_9_dijkstra_algorithm;
+pub mod chapter_9_yen_k_shortest_paths;
+
+/// The data structures built along the chapters, gathered under a stable path.
+pub mod collections {
+    pub use crate::chapter_4_deque::Deque;
+    pub use crate::chapter_4_linked_list::LinkedList;
+    pub use crate::chapter_4_persistent_stack::{
+        ArcPersistentQueue, ArcPersistentStack, PersistentQueue, PersistentStack,
+    };
+    pub use crate::chapter_4_stack::Stack;
+    pub use crate::chapter_5_hashmap::HashMap;
+    pub use crate::chapter_5_hashset::HashSet;
+    pub use crate::chapter_5_robin_hood_hashmap::RobinHoodHashMap;
+    pub use crate::chapter_8_avl_binary_tree::{AVLTree, DuplicateKeys, InvariantViolation};
+    pub use crate::chapter_8_avl_map::AVLMap;
+    pub use crate::chapter_8_disjoint_set::DisjointSet;
+    pub use crate::chapter_8_persistent_avl_map::{ArcPersistentAVLMap, PersistentAVLMap};
+}
+
+/// The graph and the algorithms that run over it, gathered under a stable path.
+pub mod graph {
+    pub use crate::chapter_6_csr_graph::CsrGraph;
+    pub use crate::chapter_6_graph::{
+        Distance, GetNeighbor, GetVertex, Graph, GraphView, Leg, Path, ShortestPathTree, Vertex,
+        VertexId, Weight, Weighted, shortest_path,
+    };
+    pub use crate::chapter_6_graph_formats::ParseEdgeListError;
+    pub use crate::chapter_6_max_flow::{MaximumFlow, bipartite_matching, max_flow};
+    pub use crate::chapter_6_strongly_connected_components::{
+        Condensation, condensation, strongly_connected_components,
+    };
+    pub use crate::chapter_6_topological_sort::{Cycle, topological_order};
+    pub use crate::chapter_6_traversal::{
+        BfsStep, BreadthFirst, DepthFirstEvents, DfsEvent, breadth_first_search,
+        depth_first_events, depth_first_search, depth_first_visit,
+    };
+    pub use crate::chapter_8_minimum_spanning_tree::{SpanningForest, kruskal, prim};
+    pub use crate::chapter_9_all_pairs_shortest_paths::{DistanceMatrix, floyd_warshall, johnson};
+    pub use crate::chapter_9_bellman_ford_algorithm::{NegativeCycle, bellman_ford, spfa};
+    pub use crate::chapter_9_bidirectional_dijkstra::bidirectional_dijkstra;
+    pub use crate::chapter_9_dijkstra_algorithm::{NegativeWeight, Search, astar, dijkstra};
+    pub use crate::chapter_9_yen_k_shortest_paths::k_shortest_paths;
+}
@@ -0,0 +1,167 @@
+use grokking::collections::{AVLTree, DuplicateKeys};
+use proptest::prelude::*;
+use std::collections::{BTreeMap, BTreeSet};
+
+#[derive(Debug, Clone)]
+enum Operation {
+    Add(i16, u32),
+    Pop(i16),
+}
+
+/// Keys from a narrow range, so that adds and pops keep hitting the same keys.
+fn operations() -> impl Strategy<Value = Vec<Operation>> {
+    let operation = prop_oneof![
+        3 => (-50i16..50, any::<u32>()).prop_map(|(key, value)| Operation::Add(key, value)),
+        2 => (-50i16..50).prop_map(Operation::Pop),
+    ];
+    prop::collection::vec(operation, 0..300)
+}
+
+fn key(entry: &(i16, u32)) -> &i16 {
+    &entry.0
+}
+
+fn entries(tree: &AVLTree<i16, (i16, u32)>) -> Vec<(i16, u32)> {
+    tree.iter().copied().collect()
+}
+
+fn replacing_tree(operations: &[Operation]) -> AVLTree<i16, (i16, u32)> {
+    let mut tree = AVLTree::<i16, (i16, u32)>::new(key).with_duplicate_keys(DuplicateKeys::Replace);
+    for operation in operations {
+        match *operation {
+            Operation::Add(key, value) => {
+                tree.add((key, value));
+            }
+            Operation::Pop(key) => {
+                tree.pop(&key);
+            }
+        }
+    }
+    tree
+}
+
+fn model(operations: &[Operation]) -> BTreeMap<i16, u32> {
+    let mut model = BTreeMap::new();
+    for operation in operations {
+        match *operation {
+            Operation::Add(key, value) => {
+                model.insert(key, value);
+            }
+            Operation::Pop(key) => {
+                model.remove(&key);
+            }
+        }
+    }
+    model
+}
+
+proptest! {
+    #[test]
+    fn should_behave_like_btreemap(operations in operations()) {
+        let mut tree = replacing_tree(&[]);
+        let mut model = BTreeMap::new();
+
+        for operation in operations {
+            match operation {
+                Operation::Add(key, value) => {
+                    let replaced = tree.add((key, value)).map(|(_, value)| value);
+                    prop_assert_eq!(replaced, model.insert(key, value));
+                }
+                Operation::Pop(key) => {
+                    let popped = tree.pop(&key).map(|(_, value)| value);
+                    prop_assert_eq!(popped, model.remove(&key));
+                }
+            }
+            prop_assert_eq!(tree.debug_validate(), Ok(()));
+            prop_assert_eq!(tree.len(), model.len());
+        }
+
+        prop_assert_eq!(entries(&tree), model.into_iter().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn should_count_duplicate_keys_like_a_multiset(operations in operations()) {
+        let mut tree = AVLTree::new(key);
+        let mut counts = BTreeMap::<i16, usize>::new();
+
+        for operation in operations {
+            match operation {
+                Operation::Add(key, value) => {
+                    tree.add((key, value));
+                    *counts.entry(key).or_default() += 1;
+                }
+                Operation::Pop(key) => {
+                    let popped = tree.pop(&key).is_some();
+                    let count = counts.get_mut(&key);
+                    prop_assert_eq!(popped, count.is_some());
+                    if let Some(count) = count {
+                        *count -= 1;
+                        if *count == 0 {
+                            counts.remove(&key);
+                        }
+                    }
+                }
+            }
+            prop_assert_eq!(tree.debug_validate(), Ok(()));
+        }
+
+        let mut rank = 0;
+        for (key, count) in counts {
+            prop_assert_eq!(tree.count(&key), count);
+            prop_assert_eq!(tree.rank(&key), rank);
+            prop_assert_eq!(tree.select(rank).map(|entry| entry.0), Some(key));
+            rank += count;
+        }
+        prop_assert_eq!(tree.len(), rank);
+    }
+
+    #[test]
+    fn should_split_off_and_append_like_btreemap(operations in operations(), at in -60i16..60) {
+        let mut tree = replacing_tree(&operations);
+        let mut model = model(&operations);
+
+        let mut upper = tree.split_off(&at);
+        let mut model_upper = model.split_off(&at);
+
+        prop_assert_eq!(tree.debug_validate(), Ok(()));
+        prop_assert_eq!(upper.debug_validate(), Ok(()));
+        prop_assert_eq!(entries(&tree), model.clone().into_iter().collect::<Vec<_>>());
+        prop_assert_eq!(entries(&upper), model_upper.clone().into_iter().collect::<Vec<_>>());
+
+        tree.append(&mut upper);
+        model.append(&mut model_upper);
+
+        prop_assert!(upper.is_empty());
+        prop_assert_eq!(tree.debug_validate(), Ok(()));
+        prop_assert_eq!(entries(&tree), model.into_iter().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn should_build_and_combine_like_btreeset(
+        left in prop::collection::btree_set(-100i32..100, 0..100),
+        right in prop::collection::btree_set(-100i32..100, 0..100),
+    ) {
+        let build = |set: &BTreeSet<i32>| {
+            AVLTree::<i32, i32>::from_sorted_iter(|item| item, set.iter().copied())
+                .with_duplicate_keys(DuplicateKeys::Reject)
+        };
+        let items = |tree: AVLTree<i32, i32>| {
+            prop_assert_eq!(tree.debug_validate(), Ok(()));
+            Ok(tree.iter().copied().collect::<Vec<_>>())
+        };
+
+        prop_assert_eq!(items(build(&left))?, left.iter().copied().collect::<Vec<_>>());
+        prop_assert_eq!(
+            items(build(&left).union(build(&right)))?,
+            left.union(&right).copied().collect::<Vec<_>>()
+        );
+        prop_assert_eq!(
+            items(build(&left).intersection(&build(&right)))?,
+            left.intersection(&right).copied().collect::<Vec<_>>()
+        );
+        prop_assert_eq!(
+            items(build(&left).difference(&build(&right)))?,
+            left.difference(&right).copied().collect::<Vec<_>>()
+        );
+    }
+}
@@ -0,0 +1,70 @@
+use grokking::collections::{AVLTree, Deque, HashMap, HashSet, RobinHoodHashMap, Stack};
+
+#[test]
+fn should_use_stack_from_outside_the_crate() {
+    let mut stack = Stack::empty();
+
+    stack.prepend(2);
+    stack.prepend(1);
+
+    assert_eq!(stack.iter().collect::<Vec<_>>(), vec![&1, &2]);
+    assert_eq!(stack.pop_head(), Some(1));
+}
+
+#[test]
+fn should_use_deque_from_outside_the_crate() {
+    let mut deque = Deque::empty();
+
+    deque.add_last(2);
+    deque.add_first(1);
+    deque.add_last(3);
+
+    assert_eq!(deque.drain().rev().collect::<Vec<_>>(), vec![3, 2, 1]);
+}
+
+#[test]
+fn should_use_hashmap_from_outside_the_crate() {
+    let mut map: HashMap<String, i32> = (0..100).map(|i| (format!("key{i}"), i)).collect();
+
+    map.extend([("key0".to_string(), -1)]);
+
+    assert_eq!(map.get(&"key0".to_string()), Some(&-1));
+    assert_eq!(map.remove(&"key99".to_string()), Some(99));
+    assert_eq!(map.size(), 99);
+}
+
+#[test]
+fn should_use_robin_hood_hashmap_from_outside_the_crate() {
+    let mut map: RobinHoodHashMap<String, i32> = (0..100).map(|i| (format!("key{i}"), i)).collect();
+
+    map.set_max_load_factor(0.5);
+    *map.entry("key0".to_string()).or_insert(0) -= 1;
+
+    assert_eq!(map["key0"], -1);
+    assert_eq!(map.remove("key99"), Some(99));
+    assert_eq!(map.len(), 99);
+}
+
+#[test]
+fn should_use_hashset_from_outside_the_crate() {
+    let mut set: HashSet<&str> = HashSet::default();
+
+    set.insert("key");
+
+    assert!(set.contains(&"key"));
+    assert!(!set.contains(&"other"));
+}
+
+#[test]
+fn should_use_avl_tree_from_outside_the_crate() {
+    let mut tree: AVLTree<i32, i32> = (0..10).rev().collect();
+
+    tree.extend([20, 15]);
+
+    assert_eq!(tree.pop(&5), Some(5));
+    assert!(!tree.contains(&5));
+    assert_eq!(
+        (&tree).into_iter().copied().collect::<Vec<_>>(),
+        vec![0, 1, 2, 3, 4, 6, 7, 8, 9, 15, 20]
+    );
+}
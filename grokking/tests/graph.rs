@@ -0,0 +1,44 @@
+use grokking::graph::{Graph, shortest_path};
+
+#[test]
+fn should_find_shortest_path_from_outside_the_crate() {
+    let mut graph = Graph::new();
+    let start = graph.add_vertex("Start");
+    let middle = graph.add_vertex("Middle");
+    let finish = graph.add_vertex("Finish");
+
+    graph.attach(&start, &middle);
+    graph.attach(&middle, &finish);
+
+    let path = shortest_path(&graph, start, finish)
+        .expect("Should have no negative cycle")
+        .expect("Should find a route");
+
+    assert_eq!(path.distance, 2);
+    assert_eq!(
+        path.waypoints
+            .iter()
+            .map(|vertex| *vertex.get_item())
+            .collect::<Vec<_>>(),
+        vec!["Start", "Middle", "Finish"]
+    );
+}
+
+#[test]
+fn should_traverse_graph_from_outside_the_crate() {
+    let mut graph = Graph::new();
+    let a = graph.add_vertex(1);
+    let b = graph.add_vertex(2);
+    let c = graph.add_vertex(3);
+
+    graph.attach(&a, &b);
+    graph.attach(&a, &c);
+
+    let items = graph
+        .breath_search_iterator(&a)
+        .map(|vertex| *vertex.get_item())
+        .collect::<Vec<_>>();
+
+    assert_eq!(items, vec![1, 2, 3]);
+    assert!(shortest_path(&graph, b, a).unwrap().is_none());
+}